@@ -0,0 +1,178 @@
+//! Pluggable accumulators for the masked sum.
+//!
+//! `sum_active_balances_with` is monomorphized per accumulator, so swapping
+//! the summation strategy costs nothing beyond the strategy itself.
+
+use crate::UsersView;
+
+/// Signature shared by every monomorphization of `sum_active_balances_with`.
+pub type MaskedSum = fn(&UsersView, f32) -> f32;
+
+pub trait Accumulator: Default {
+    fn add(&mut self, value: f32);
+    fn finish(self) -> f32;
+}
+
+/// Plain `f32` running sum, identical to `sum_active_balances`.
+#[derive(Default)]
+pub struct NaiveAcc {
+    sum: f32,
+}
+
+impl Accumulator for NaiveAcc {
+    #[inline(always)]
+    fn add(&mut self, value: f32) {
+        self.sum += value;
+    }
+
+    fn finish(self) -> f32 {
+        self.sum
+    }
+}
+
+/// Kahan compensated summation: carries the rounding error of each addition
+/// into the next one.
+#[derive(Default)]
+pub struct KahanAcc {
+    sum: f32,
+    compensation: f32,
+}
+
+impl Accumulator for KahanAcc {
+    #[inline(always)]
+    fn add(&mut self, value: f32) {
+        let adjusted = value - self.compensation;
+        let next_sum = self.sum + adjusted;
+        self.compensation = (next_sum - self.sum) - adjusted;
+        self.sum = next_sum;
+    }
+
+    fn finish(self) -> f32 {
+        self.sum
+    }
+}
+
+/// Widens every value and accumulates in `f64`, rounding once at the end.
+#[derive(Default)]
+pub struct F64Acc {
+    sum: f64,
+}
+
+impl Accumulator for F64Acc {
+    #[inline(always)]
+    fn add(&mut self, value: f32) {
+        self.sum += value as f64;
+    }
+
+    fn finish(self) -> f32 {
+        self.sum as f32
+    }
+}
+
+#[inline(never)]
+pub fn sum_active_balances_with<A: Accumulator>(
+    users_view: &UsersView,
+    minimum_balance: f32,
+) -> f32 {
+    let mut accumulator = A::default();
+
+    for i in 0..users_view.count {
+        let balance_value = users_view.balances[i];
+        let take_value = if users_view.active[i] != 0 && balance_value >= minimum_balance {
+            1.0f32
+        } else {
+            0.0f32
+        };
+        accumulator.add(balance_value * take_value);
+    }
+
+    accumulator.finish()
+}
+
+/// Absolute difference of every pair of named checksums, in input order,
+/// computed in `f64` so the differences themselves do not round.
+pub fn pairwise_differences<'a>(checksums: &[(&'a str, f32)]) -> Vec<(&'a str, &'a str, f64)> {
+    let mut differences = Vec::new();
+
+    for (i, &(left_name, left)) in checksums.iter().enumerate() {
+        for &(right_name, right) in &checksums[i + 1..] {
+            differences.push((left_name, right_name, (left as f64 - right as f64).abs()));
+        }
+    }
+
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UsersColumns;
+
+    fn drifting_columns() -> UsersColumns {
+        // One large balance followed by many values too small to register in f32.
+        let rows: Vec<(i32, f32, bool)> = std::iter::once((0, 16_777_216.0, true))
+            .chain((1..=1000).map(|id| (id, 1.0, true)))
+            .collect();
+        UsersColumns::from_triples(&rows)
+    }
+
+    #[test]
+    fn naive_matches_hand_written_scan() {
+        let columns =
+            UsersColumns::from_triples(&[(0, 100.0, true), (1, 300.0, false), (2, 500.0, true)]);
+        let users_view = columns.view();
+
+        assert_eq!(
+            sum_active_balances_with::<NaiveAcc>(&users_view, 250.0),
+            crate::sum_active_balances(&users_view, 250.0)
+        );
+    }
+
+    #[test]
+    fn naive_loses_small_values() {
+        let columns = drifting_columns();
+
+        assert_eq!(
+            sum_active_balances_with::<NaiveAcc>(&columns.view(), 0.0),
+            16_777_216.0
+        );
+    }
+
+    #[test]
+    fn kahan_recovers_small_values() {
+        let columns = drifting_columns();
+
+        assert_eq!(
+            sum_active_balances_with::<KahanAcc>(&columns.view(), 0.0),
+            16_778_216.0
+        );
+    }
+
+    #[test]
+    fn f64_recovers_small_values() {
+        let columns = drifting_columns();
+
+        assert_eq!(
+            sum_active_balances_with::<F64Acc>(&columns.view(), 0.0),
+            16_778_216.0
+        );
+    }
+
+    #[test]
+    fn pairwise_differences_cover_every_pair() {
+        let differences = pairwise_differences(&[
+            ("naive", 16_777_216.0),
+            ("kahan", 16_778_216.0),
+            ("f64", 16_778_216.0),
+        ]);
+
+        assert_eq!(
+            differences,
+            [
+                ("naive", "kahan", 1000.0),
+                ("naive", "f64", 1000.0),
+                ("kahan", "f64", 0.0)
+            ]
+        );
+    }
+}
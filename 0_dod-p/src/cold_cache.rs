@@ -0,0 +1,42 @@
+//! Cold-cache iterations.
+//!
+//! Before every measured scan the columns are evicted with `clflush`, so each
+//! iteration starts with nothing cached and pays the full memory cost. Unlike
+//! the contention companion this targets exactly the scanned data and leaves
+//! the rest of the hierarchy alone. Builds without `clflush` sweep
+//! `bench_core::evict_cache`'s scratch buffer instead, like the other binaries.
+
+/// Whether `flush` evicts anything in this build; otherwise it is a no-op.
+pub const SUPPORTED: bool = cfg!(all(feature = "cold-cache", target_arch = "x86_64"));
+
+/// How `--cold-cache` evicts the columns in this build.
+pub const EVICTION: &str = if SUPPORTED {
+    "clflush"
+} else {
+    "scratch sweep"
+};
+
+/// Evicts every cache line overlapping `data` from all cache levels.
+#[cfg(all(feature = "cold-cache", target_arch = "x86_64"))]
+pub fn flush<T>(data: &[T]) {
+    use std::arch::x86_64::{_mm_clflush, _mm_mfence};
+
+    const CACHE_LINE_BYTES: usize = 64;
+
+    let start = data.as_ptr() as usize;
+    let end = start + size_of_val(data);
+
+    // Start from the line holding the first byte so a slice that begins
+    // mid-line still has its last line flushed.
+    for line in (start & !(CACHE_LINE_BYTES - 1)..end).step_by(CACHE_LINE_BYTES) {
+        // SAFETY: clflush is part of SSE2, which every x86_64 CPU has, and it
+        // accepts any address; `line` lies in a cache line `data` overlaps.
+        unsafe { _mm_clflush(line as *const u8) };
+    }
+
+    // SAFETY: SSE2 as above. Orders the flushes before the timed scan.
+    unsafe { _mm_mfence() };
+}
+
+#[cfg(not(all(feature = "cold-cache", target_arch = "x86_64")))]
+pub fn flush<T>(_data: &[T]) {}
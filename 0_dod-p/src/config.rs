@@ -0,0 +1,326 @@
+//! `--config <file.toml>`: benchmark options from a version-controlled file.
+//!
+//! Every key mirrors a command-line flag and goes through the same validation
+//! as that flag. The file is applied before the flags, so a valued flag such
+//! as `--format` overrides the file, while on/off flags can only switch modes
+//! on.
+
+use bench_core::{BenchmarkConfigBuilder, sweep};
+use serde::{Deserialize, Serialize};
+
+use crate::Options;
+use crate::report::OutputFormat;
+use crate::scenario_filter::ScenarioFilter;
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    pub elements: Option<usize>,
+    pub min_balance: Option<f32>,
+    pub seed: Option<u64>,
+    pub warmup: Option<usize>,
+    pub adaptive_warmup: bool,
+    pub pin_core: Option<usize>,
+    pub allow_negative: bool,
+    pub iterations: Option<usize>,
+    pub active_prob: Option<f64>,
+    pub format: Option<String>,
+    pub contention: bool,
+    pub contention_bytes: Option<usize>,
+    pub filter_dsl: bool,
+    pub tlb: bool,
+    pub peak: bool,
+    pub accumulators: bool,
+    pub cold_cache: bool,
+    pub precision: bool,
+    pub size_sweep: bool,
+    /// Comma-separated sizes, as `--sweep` takes them.
+    pub sweep: Option<String>,
+    pub working_set_mib: Vec<usize>,
+    pub decay: bool,
+    pub parallel: bool,
+    pub simd: bool,
+    pub simd_auto: bool,
+    pub prefetch: bool,
+    pub active_bits: bool,
+    pub repeat_dataset: Option<usize>,
+    pub summation_order: bool,
+    pub csv_header: bool,
+    pub csv_out: Option<String>,
+    pub json_out: Option<String>,
+    pub dataset: Option<String>,
+    pub dataset_csv: Option<String>,
+    pub histogram: Option<usize>,
+    pub memory: bool,
+    pub time_budget: Option<f64>,
+    pub profile: Option<f64>,
+    pub plot_data: Option<String>,
+    pub scenario_filter: Option<String>,
+}
+
+impl ConfigFile {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|error| format!("invalid config: {error}"))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|error| format!("cannot read config '{path}': {error}"))?;
+        Self::parse(&text)
+    }
+
+    /// Writes the benchmark parameters into `benchmark`, whose `build` validates
+    /// them together with the flags.
+    pub fn apply_benchmark(&self, benchmark: &mut BenchmarkConfigBuilder) {
+        if let Some(elements) = self.elements {
+            benchmark.elements(elements);
+        }
+        if let Some(min_balance) = self.min_balance {
+            benchmark.min_balance(min_balance);
+        }
+        if let Some(seed) = self.seed {
+            benchmark.seed(seed);
+        }
+        if let Some(warmup) = self.warmup {
+            benchmark.warmup(warmup);
+        }
+        if let Some(iterations) = self.iterations {
+            benchmark.iterations(iterations);
+        }
+        if let Some(active_prob) = self.active_prob {
+            benchmark.active_prob(active_prob);
+        }
+    }
+
+    /// Validates every other value and writes it into `options`.
+    pub fn apply(self, options: &mut Options) -> Result<(), String> {
+        if let Some(format) = &self.format {
+            options.output_format = OutputFormat::parse(format)?;
+        }
+        if self.working_set_mib.contains(&0) {
+            return Err("config: working-set-mib sizes must be positive".to_string());
+        }
+        if let Some(pattern) = &self.scenario_filter {
+            options.scenario_filter = Some(ScenarioFilter::parse(pattern)?);
+        }
+        if let Some(sizes) = &self.sweep {
+            options.sweep = Some(sweep::parse_sizes(sizes)?);
+        }
+
+        options.contention |= self.contention;
+        options.filter_dsl |= self.filter_dsl;
+        options.tlb |= self.tlb;
+        options.peak |= self.peak;
+        options.accumulators |= self.accumulators;
+        options.cold_cache |= self.cold_cache;
+        options.precision |= self.precision;
+        options.size_sweep |= self.size_sweep;
+        options.decay |= self.decay;
+        options.parallel |= self.parallel;
+        options.simd |= self.simd;
+        options.simd_auto |= self.simd_auto;
+        options.prefetch |= self.prefetch;
+        options.adaptive_warmup |= self.adaptive_warmup;
+        options.allow_negative |= self.allow_negative;
+        options.active_bits |= self.active_bits;
+        options.summation_order |= self.summation_order;
+        options.csv_header |= self.csv_header;
+        options.memory |= self.memory;
+        if self.pin_core.is_some() {
+            options.pin_core = self.pin_core;
+        }
+        if self.contention_bytes.is_some() {
+            options.contention_bytes = self.contention_bytes;
+        }
+        if self.csv_out.is_some() {
+            options.csv_out = self.csv_out;
+        }
+        if self.json_out.is_some() {
+            options.json_out = self.json_out;
+        }
+        if self.dataset.is_some() {
+            options.dataset = self.dataset;
+        }
+        if self.dataset_csv.is_some() {
+            options.dataset_csv = self.dataset_csv;
+        }
+        if self.repeat_dataset.is_some() {
+            options.repeat_dataset = self.repeat_dataset;
+        }
+        if self.histogram.is_some() {
+            options.histogram = self.histogram;
+        }
+        if self.time_budget.is_some() {
+            options.time_budget = self.time_budget;
+        }
+        if self.profile.is_some() {
+            options.profile = self.profile;
+        }
+        if self.plot_data.is_some() {
+            options.plot_data = self.plot_data;
+        }
+        options.working_set_mebibytes = self.working_set_mib;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+format = "prometheus"
+tlb = true
+precision = true
+working-set-mib = [8, 32]
+scenario-filter = "dod_*"
+sweep = "1000,10000"
+allow-negative = true
+"#;
+
+    #[test]
+    fn sample_config_round_trips() {
+        let config = ConfigFile::parse(SAMPLE).unwrap();
+
+        assert_eq!(config.format.as_deref(), Some("prometheus"));
+        assert_eq!(config.working_set_mib, [8, 32]);
+        assert_eq!(config.sweep.as_deref(), Some("1000,10000"));
+        assert!(config.tlb && config.precision && config.allow_negative && !config.peak);
+        assert_eq!(
+            ConfigFile::parse(&toml::to_string(&config).unwrap()).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let apply = |text: &str| {
+            let mut options = crate::parse_options(std::iter::empty()).unwrap();
+            ConfigFile::parse(text)?.apply(&mut options)
+        };
+
+        assert!(apply("format = \"xml\"").unwrap_err().contains("'xml'"));
+        assert!(apply("tbl = true").unwrap_err().contains("unknown field"));
+        assert!(
+            apply("peak = \"yes\"")
+                .unwrap_err()
+                .contains("invalid type")
+        );
+        assert!(
+            apply("working-set-mib = [8, 0]")
+                .unwrap_err()
+                .contains("positive")
+        );
+        assert!(
+            apply("scenario-filter = \"a|b\"")
+                .unwrap_err()
+                .contains("'|'")
+        );
+        assert!(apply("sweep = \"10,0\"").unwrap_err().contains("nonzero"));
+    }
+
+    /// `parse_options` reading `text` as the `--config` file, then `flags`.
+    fn parse_with_config(name: &str, text: &str, flags: &[&str]) -> Result<crate::Options, String> {
+        let path =
+            std::env::temp_dir().join(format!("dod-p-config-{name}-{}.toml", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+
+        let args = ["--config", path.to_str().unwrap()]
+            .into_iter()
+            .chain(flags.iter().copied());
+        let options = crate::parse_options(args.map(str::to_string));
+        std::fs::remove_file(&path).unwrap();
+        options
+    }
+
+    #[test]
+    fn repeat_dataset_is_checked_like_its_flag() {
+        let parse = |text: &str| parse_with_config("repeat-dataset", text, &[]);
+
+        assert_eq!(parse("repeat-dataset = 8").unwrap().repeat_dataset, Some(8));
+        assert!(
+            parse("repeat-dataset = 0")
+                .err()
+                .unwrap()
+                .contains("nonzero")
+        );
+        assert!(
+            parse("repeat-dataset = 9223372036854775807")
+                .err()
+                .unwrap()
+                .contains("overflows usize")
+        );
+    }
+
+    #[test]
+    fn profile_is_checked_like_its_flag() {
+        let parse = |text: &str| parse_with_config("profile", text, &[]);
+
+        assert_eq!(parse("profile = 2.5").unwrap().profile, Some(2.5));
+        assert!(parse("profile = 0.0").err().unwrap().contains("positive"));
+        assert!(parse("profile = nan").err().unwrap().contains("positive"));
+        assert!(parse("profile = 1e20").err().unwrap().contains("too big"));
+    }
+
+    #[test]
+    fn contention_bytes_is_checked_like_its_flag() {
+        let parse = |text: &str, flags: &[&str]| parse_with_config("contention-bytes", text, flags);
+
+        assert_eq!(
+            parse("contention = true\ncontention-bytes = 4096", &[])
+                .unwrap()
+                .contention_bytes,
+            Some(4096)
+        );
+        assert!(
+            parse("contention-bytes = 4096", &["--contention"])
+                .unwrap()
+                .contention
+        );
+        assert!(
+            parse("contention-bytes = 4096", &[])
+                .err()
+                .unwrap()
+                .contains("--contention companion")
+        );
+        assert!(
+            parse("contention = true\ncontention-bytes = 0", &[])
+                .err()
+                .unwrap()
+                .contains("nonzero")
+        );
+    }
+
+    #[test]
+    fn only_one_file_is_accepted() {
+        assert!(
+            parse_with_config("twice", SAMPLE, &["--config", "other.toml"])
+                .err()
+                .unwrap()
+                .contains("only be given once")
+        );
+    }
+
+    #[test]
+    fn flags_override_the_file() {
+        let path = std::env::temp_dir().join(format!("dod-p-config-{}.toml", std::process::id()));
+        std::fs::write(&path, SAMPLE).unwrap();
+
+        let args = [
+            "--format",
+            "text",
+            "--config",
+            path.to_str().unwrap(),
+            "--peak",
+        ];
+        let options = crate::parse_options(args.iter().map(|arg| arg.to_string()));
+        std::fs::remove_file(&path).unwrap();
+        let options = options.unwrap();
+
+        assert_eq!(options.output_format, OutputFormat::Text);
+        assert!(options.peak && options.tlb && options.allow_negative);
+        assert_eq!(options.working_set_mebibytes, [8, 32]);
+        assert_eq!(options.sweep, Some(vec![1_000, 10_000]));
+    }
+}
@@ -0,0 +1,86 @@
+//! Decay-then-retain maintenance pass.
+//!
+//! Every balance is multiplied by a decay factor and users that fall below a
+//! floor are removed, once over an AoS `Vec<User>` and once over the SoA
+//! columns. The SoA version decays the balance column alone (a plain,
+//! vectorizable loop) and then compacts all three columns in a single pass.
+
+use bench_core::{Money, User};
+
+use crate::{UsersColumns, UsersView};
+
+/// Memory traffic per user of `decay_and_retain_aos`: the decay pass reads and
+/// writes every row, and `retain` reads it once more. Moving the survivors is
+/// not counted, so this is a lower bound.
+pub const AOS_BYTES_PER_ELEMENT: f64 = (3 * size_of::<User>()) as f64;
+
+/// Memory traffic per user of `decay_and_retain_soa`: the decay pass reads and
+/// writes the balance, and the compaction reads all four columns. Moving the
+/// survivors is not counted, so this is a lower bound.
+pub const SOA_BYTES_PER_ELEMENT: f64 = (2 * size_of::<f32>() + COLUMNS_BYTES_PER_ELEMENT) as f64;
+
+/// One id, balance, active flag and score.
+const COLUMNS_BYTES_PER_ELEMENT: usize =
+    size_of::<i32>() + size_of::<f32>() + size_of::<u8>() + size_of::<f32>();
+
+pub fn to_rows(users_view: &UsersView) -> Vec<User> {
+    (0..users_view.count).map(|i| users_view.row(i)).collect()
+}
+
+#[inline(never)]
+pub fn decay_and_retain_aos(users: &mut Vec<User>, decay_factor: f32, floor: Money) {
+    for user in users.iter_mut() {
+        user.balance *= decay_factor;
+    }
+    users.retain(|user| user.balance >= floor);
+}
+
+#[inline(never)]
+pub fn decay_and_retain_soa(columns: &mut UsersColumns, decay_factor: f32, floor: f32) {
+    for balance in &mut columns.balances {
+        *balance *= decay_factor;
+    }
+
+    // Reslicing to one length lets the compiler drop the per-column bounds checks.
+    let len = columns.balances.len();
+    let ids = &mut columns.ids[..len];
+    let balances = &mut columns.balances[..len];
+    let active = &mut columns.active[..len];
+    let scores = &mut columns.scores[..len];
+
+    let mut kept = 0;
+    for i in 0..len {
+        if balances[i] >= floor {
+            ids[kept] = ids[i];
+            balances[kept] = balances[i];
+            active[kept] = active[i];
+            scores[kept] = scores[i];
+            kept += 1;
+        }
+    }
+
+    columns.ids.truncate(kept);
+    columns.balances.truncate(kept);
+    columns.active.truncate(kept);
+    columns.scores.truncate(kept);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aos_and_soa_keep_the_same_users() {
+        let rows: Vec<(i32, f32, bool)> = (0..1000)
+            .map(|i| (i, (i * 37 % 1000) as f32, i % 3 != 0))
+            .collect();
+        let mut columns = UsersColumns::from_triples(&rows);
+        let mut users = to_rows(&columns.view());
+
+        decay_and_retain_aos(&mut users, 0.5, Money(250.0));
+        decay_and_retain_soa(&mut columns, 0.5, 250.0);
+
+        assert!(!users.is_empty() && users.len() < rows.len());
+        assert_eq!(users, to_rows(&columns.view()));
+    }
+}
@@ -0,0 +1,95 @@
+//! `--histogram <buckets>`: the distribution of the generated balances.
+//!
+//! Splits the range between the smallest and the largest balance into equal
+//! bins and prints one ASCII bar per bin, so the dataset can be checked before
+//! its numbers are trusted.
+
+/// Length of the bar of the fullest bin.
+const BAR_WIDTH: usize = 50;
+
+pub struct Histogram {
+    pub minimum: f32,
+    pub maximum: f32,
+    /// Balances per bin; the maximum itself falls into the last bin.
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// Bins `balances` into `buckets` equal-width bins. When every balance is
+    /// equal the range is empty, so they all land in the first bin.
+    pub fn new(balances: &[f32], buckets: usize) -> Self {
+        assert!(buckets > 0, "a histogram needs at least one bucket");
+
+        let minimum = balances.iter().copied().fold(f32::INFINITY, f32::min);
+        let maximum = balances.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let width = (maximum - minimum) / buckets as f32;
+
+        let mut counts = vec![0; buckets];
+        for &balance in balances {
+            let bucket = if width > 0.0 {
+                (((balance - minimum) / width) as usize).min(buckets - 1)
+            } else {
+                0
+            };
+            counts[bucket] += 1;
+        }
+
+        Self {
+            minimum,
+            maximum,
+            counts,
+        }
+    }
+
+    /// Lower bound of bin `bucket`.
+    fn lower_bound(&self, bucket: usize) -> f32 {
+        let width = (self.maximum - self.minimum) / self.counts.len() as f32;
+        self.minimum + width * bucket as f32
+    }
+}
+
+pub fn print_balance_histogram(balances: &[f32], buckets: usize) {
+    println!();
+    println!("[ Balance Histogram ]");
+    if balances.is_empty() {
+        println!("No balances");
+        return;
+    }
+
+    let histogram = Histogram::new(balances, buckets);
+    let fullest = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+    for (bucket, &count) in histogram.counts.iter().enumerate() {
+        let upper_bound = if bucket + 1 == buckets {
+            histogram.maximum
+        } else {
+            histogram.lower_bound(bucket + 1)
+        };
+        println!(
+            "{:>10.2} .. {:>10.2} | {:<width$} {count}",
+            histogram.lower_bound(bucket),
+            upper_bound,
+            "#".repeat(count * BAR_WIDTH / fullest),
+            width = BAR_WIDTH
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn balances_are_binned_over_their_range() {
+        let histogram = Histogram::new(&[0.0, 1.0, 2.5, 9.9, 10.0], 4);
+
+        assert_eq!((histogram.minimum, histogram.maximum), (0.0, 10.0));
+        assert_eq!(histogram.counts, [2, 1, 0, 2]);
+    }
+
+    #[test]
+    fn equal_balances_fill_a_single_bin() {
+        let histogram = Histogram::new(&[250.0; 7], 5);
+
+        assert_eq!(histogram.counts, [7, 0, 0, 0, 0]);
+    }
+}
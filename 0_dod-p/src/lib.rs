@@ -0,0 +1,747 @@
+//! The SoA layout and its scan, as a library so the Criterion benches can
+//! call the same code the binary times.
+//!
+//! The column kernels take and return raw floats, not `bench_core::Money`:
+//! `sum_active_balances` is generic over `Balance` so the same scan compiles
+//! for `f32` and `f64`, and the SIMD variants add lanes of plain `f32`. `Money`
+//! is the boundary type; it leaves the rows in `to_soa` and comes back in
+//! `UsersView::row`.
+
+pub use bench_core::rng;
+
+#[cfg(feature = "mmap")]
+pub mod mmap;
+
+use std::ops::{Add, Mul};
+
+use bench_core::{Money, User};
+use rng::Pcg32;
+
+/// The float types a balance column can hold, so the same scan can be
+/// compiled for `f32` and `f64` and the two compared.
+pub trait Balance: Copy + Default + PartialOrd + Add<Output = Self> + Mul<Output = Self> {
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+impl Balance for f32 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+}
+
+impl Balance for f64 {
+    const ZERO: Self = 0.0;
+    const ONE: Self = 1.0;
+}
+
+/// Borrowed SoA view; `UsersView::default()` is an empty view. Balances are
+/// `f32` unless another `Balance` type is named.
+///
+/// `scores` is a column no balance scan reads; it is there so the cost of a
+/// wider record can be compared between the layouts. Views made for one
+/// scan, such as a chunk's, leave it and `ids` empty. Sources that do not
+/// store scores, such as a mapped file, leave `scores` empty too, and rows
+/// read a missing score as 0.0.
+#[derive(Default)]
+pub struct UsersView<'a, T = f32> {
+    pub ids: &'a [i32],
+    pub balances: &'a [T],
+    pub active: &'a [u8],
+    pub scores: &'a [f32],
+    pub count: usize,
+}
+
+impl<'a, T> UsersView<'a, T> {
+    /// The first `count` rows, i.e. the dataset the generator yields at that size.
+    pub fn prefix(&self, count: usize) -> UsersView<'a, T> {
+        UsersView {
+            ids: &self.ids[..count],
+            balances: &self.balances[..count],
+            active: &self.active[..count],
+            scores: &self.scores[..count.min(self.scores.len())],
+            count,
+        }
+    }
+}
+
+impl UsersView<'_> {
+    /// Materializes row `i` as a `User`; panics if `i` is out of bounds.
+    pub fn row(&self, i: usize) -> User {
+        self.try_row(i)
+            .unwrap_or_else(|| panic!("row {i} out of bounds for {} users", self.count))
+    }
+
+    pub fn try_row(&self, i: usize) -> Option<User> {
+        (i < self.count).then(|| User {
+            id: self.ids[i],
+            balance: Money(self.balances[i]),
+            active: self.active[i] != 0,
+            score: self.scores.get(i).copied().unwrap_or_default(),
+        })
+    }
+}
+
+impl<'a> UsersView<'a> {
+    /// The same rows with `active` swapped for its `pack_active` bitset.
+    pub fn with_active_bits(&self, active: &'a [u64]) -> UsersViewBits<'a> {
+        assert_eq!(
+            active.len(),
+            self.count.div_ceil(64),
+            "bitset does not cover {} users",
+            self.count
+        );
+        UsersViewBits {
+            ids: self.ids,
+            balances: self.balances,
+            active,
+            count: self.count,
+        }
+    }
+}
+
+/// `UsersView` with the active flags packed one bit per user, bit `i % 64`
+/// of word `i / 64`, so the flag column is 8x smaller than the bytes.
+#[derive(Default)]
+pub struct UsersViewBits<'a> {
+    pub ids: &'a [i32],
+    pub balances: &'a [f32],
+    pub active: &'a [u64],
+    pub count: usize,
+}
+
+/// `base` repeated `repeats` times back to back: row `i` is base row
+/// `i % base.count`, so a scan over `count` rows touches as many rows as a
+/// dataset `repeats` times larger without allocating one. It only moves that
+/// much memory once `base` is too big to stay in cache between passes.
+pub struct RepeatingUsersView<'a> {
+    pub base: UsersView<'a>,
+    pub repeats: usize,
+    pub count: usize,
+}
+
+impl<'a> RepeatingUsersView<'a> {
+    /// Panics if `base.count * repeats` overflows `usize`.
+    pub fn new(base: UsersView<'a>, repeats: usize) -> Self {
+        let count = base.count.checked_mul(repeats).unwrap_or_else(|| {
+            panic!(
+                "{} users repeated {repeats} times overflow usize",
+                base.count
+            )
+        });
+        Self {
+            base,
+            repeats,
+            count,
+        }
+    }
+
+    /// Materializes virtual row `i`; panics if `i` is out of bounds.
+    pub fn row(&self, i: usize) -> User {
+        assert!(
+            i < self.count,
+            "row {i} out of bounds for {} users",
+            self.count
+        );
+        self.base.row(i % self.base.count)
+    }
+}
+
+/// Borrowed SoA view for in-place balance updates. Only the balances are
+/// writable; `dirty` records which rows were written, in the bit layout of
+/// `pack_active`, so a consumer can sync just those rows.
+pub struct UsersViewMut<'a> {
+    pub balances: &'a mut [f32],
+    pub active: &'a [u8],
+    pub dirty: Vec<u64>,
+    pub count: usize,
+}
+
+impl UsersViewMut<'_> {
+    /// Adds `delta` to exactly the balances `sum_active_balances` would take
+    /// at `minimum_balance` and marks their rows dirty. Rows that do not
+    /// qualify are read but never stored to. Returns the rows written.
+    pub fn add_to_active_balances(&mut self, delta: f32, minimum_balance: f32) -> usize {
+        let balances = &mut self.balances[..self.count];
+        let active = &self.active[..self.count];
+        let mut written = 0;
+
+        for (i, (balance_value, &is_active)) in balances.iter_mut().zip(active).enumerate() {
+            if is_active != 0 && *balance_value >= minimum_balance {
+                *balance_value += delta;
+                self.dirty[i / 64] |= 1 << (i % 64);
+                written += 1;
+            }
+        }
+
+        written
+    }
+
+    /// Rows written since the view was made or last cleaned; a row written
+    /// twice counts once.
+    pub fn dirty_count(&self) -> usize {
+        self.dirty
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn clear_dirty(&mut self) {
+        self.dirty.fill(0);
+    }
+}
+
+/// Packs 0/1 `active` bytes into the bitset `UsersViewBits` reads.
+pub fn pack_active(active: &[u8]) -> Vec<u64> {
+    active
+        .chunks(64)
+        .map(|flags| {
+            flags.iter().enumerate().fold(0u64, |word, (bit, &flag)| {
+                word | (u64::from(flag != 0) << bit)
+            })
+        })
+        .collect()
+}
+
+/// Owned SoA storage that hands out `UsersView`s.
+#[derive(Debug, Clone, Default)]
+pub struct UsersColumns {
+    pub ids: Vec<i32>,
+    pub balances: Vec<f32>,
+    pub active: Vec<u8>,
+    pub scores: Vec<f32>,
+}
+
+impl UsersColumns {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            ids: Vec::with_capacity(capacity),
+            balances: Vec::with_capacity(capacity),
+            active: Vec::with_capacity(capacity),
+            scores: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Builds columns from `(id, balance, active)` rows, without the generator;
+    /// every score is zero.
+    ///
+    /// ```
+    /// use dod_p::{UsersColumns, sum_active_balances};
+    ///
+    /// let columns = UsersColumns::from_triples(&[(0, 100.0, true), (1, 300.0, false), (2, 500.0, true)]);
+    /// assert_eq!(sum_active_balances(&columns.view(), 250.0), 500.0);
+    /// ```
+    pub fn from_triples(rows: &[(i32, f32, bool)]) -> Self {
+        let mut columns = Self::with_capacity(rows.len());
+        for &(id, balance, active) in rows {
+            columns.push(id, balance, active, 0.0);
+        }
+        columns
+    }
+
+    /// Splits generated rows, or rows loaded from a `--dataset` file, into
+    /// columns.
+    pub fn from_users(users: &[User]) -> Self {
+        let (ids, balances, active, scores) = to_soa(users);
+        Self {
+            ids,
+            balances,
+            active,
+            scores,
+        }
+    }
+
+    pub fn push(&mut self, id: i32, balance: f32, active: bool, score: f32) {
+        self.ids.push(id);
+        self.balances.push(balance);
+        self.active.push(active as u8);
+        self.scores.push(score);
+    }
+
+    /// Removes row `index` from every column by moving the last row into its
+    /// slot, so the columns stay aligned at O(1) cost but rows are reordered.
+    pub fn swap_remove(&mut self, index: usize) -> (i32, f32, bool, f32) {
+        (
+            self.ids.swap_remove(index),
+            self.balances.swap_remove(index),
+            self.active.swap_remove(index) != 0,
+            self.scores.swap_remove(index),
+        )
+    }
+
+    /// Moves the rows `sum_active_balances` takes at `minimum_balance` to the
+    /// front and returns how many there are, so later passes over them read
+    /// `balances[..k]` with no filter. Qualifying rows keep their order, which
+    /// leaves their sum bit for bit the same; the rest follow in no
+    /// particular order. Every column is swapped together, so rows stay whole.
+    pub fn partition_qualifying(&mut self, minimum_balance: f32) -> usize {
+        let mut qualifying = 0;
+
+        for i in 0..self.balances.len() {
+            if self.active[i] != 0 && self.balances[i] >= minimum_balance {
+                self.ids.swap(qualifying, i);
+                self.balances.swap(qualifying, i);
+                self.active.swap(qualifying, i);
+                self.scores.swap(qualifying, i);
+                qualifying += 1;
+            }
+        }
+
+        qualifying
+    }
+
+    pub fn view(&self) -> UsersView<'_> {
+        UsersView {
+            ids: &self.ids,
+            balances: &self.balances,
+            active: &self.active,
+            scores: &self.scores,
+            count: self.ids.len(),
+        }
+    }
+
+    /// A view for updating balances in place, with every row clean.
+    pub fn view_mut(&mut self) -> UsersViewMut<'_> {
+        let count = self.ids.len();
+        UsersViewMut {
+            balances: &mut self.balances,
+            active: &self.active,
+            dirty: vec![0; count.div_ceil(64)],
+            count,
+        }
+    }
+}
+
+/// Splits `users` into id, balance, active and score columns, `active` as
+/// 0/1 bytes.
+pub fn to_soa(users: &[User]) -> (Vec<i32>, Vec<f32>, Vec<u8>, Vec<f32>) {
+    (
+        users.iter().map(|user| user.id).collect(),
+        users.iter().map(|user| user.balance.raw()).collect(),
+        users.iter().map(|user| user.active as u8).collect(),
+        users.iter().map(|user| user.score).collect(),
+    )
+}
+
+/// The shared dataset in columns; the rows are dropped once split.
+pub fn generate_columns(count: usize, active_probability: f64, rng: &mut Pcg32) -> UsersColumns {
+    UsersColumns::from_users(&bench_core::generate_users_with_rng(
+        count,
+        active_probability,
+        rng,
+    ))
+}
+
+/// `generate_columns` with balances from `[-1000, 1000)`, see
+/// `bench_core::generate_users_allowing_negative`.
+pub fn generate_columns_allowing_negative(
+    count: usize,
+    active_probability: f64,
+    rng: &mut Pcg32,
+) -> UsersColumns {
+    UsersColumns::from_users(&bench_core::generate_users_allowing_negative_with_rng(
+        count,
+        active_probability,
+        rng,
+    ))
+}
+
+#[inline(never)]
+pub fn sum_active_balances<T: Balance>(users_view: &UsersView<T>, minimum_balance: T) -> T {
+    let mut accumulated_balance = T::ZERO;
+    let threshold_balance = minimum_balance;
+
+    for i in 0..users_view.count {
+        let balance_value = users_view.balances[i];
+        let take_value = if users_view.active[i] != 0 && balance_value >= threshold_balance {
+            T::ONE
+        } else {
+            T::ZERO
+        };
+        accumulated_balance = accumulated_balance + balance_value * take_value;
+    }
+
+    accumulated_balance
+}
+
+/// `sum_active_balances` over all `count` rows of a repeating view, in
+/// virtual row order. The base index wraps with a compare rather than a `%`,
+/// so the loop costs what the plain scan does per row.
+#[inline(never)]
+pub fn sum_active_balances_repeating(users_view: &RepeatingUsersView, minimum_balance: f32) -> f32 {
+    let base = &users_view.base;
+    let mut accumulated_balance = 0.0f32;
+    let mut j = 0;
+
+    for _ in 0..users_view.count {
+        let balance_value = base.balances[j];
+        let take_value = if base.active[j] != 0 && balance_value >= minimum_balance {
+            1.0f32
+        } else {
+            0.0f32
+        };
+        accumulated_balance += balance_value * take_value;
+
+        j += 1;
+        if j == base.count {
+            j = 0;
+        }
+    }
+
+    accumulated_balance
+}
+
+/// `sum_active_balances` reading the flags from the bitset: one word per 64
+/// balances, each flag taken with a shift and a mask. The sum runs in the
+/// same order, so it matches the byte scan bit for bit.
+#[inline(never)]
+pub fn sum_active_balances_bits(users_view: &UsersViewBits, minimum_balance: f32) -> f32 {
+    let balances = &users_view.balances[..users_view.count];
+    let mut accumulated_balance = 0.0f32;
+
+    for (&active_word, balance_chunk) in users_view.active.iter().zip(balances.chunks(64)) {
+        for (bit, &balance_value) in balance_chunk.iter().enumerate() {
+            let is_active = (active_word >> bit) & 1 != 0;
+            let take_value = if is_active && balance_value >= minimum_balance {
+                1.0f32
+            } else {
+                0.0f32
+            };
+            accumulated_balance += balance_value * take_value;
+        }
+    }
+
+    accumulated_balance
+}
+
+/// The balances `sum_active_balances` takes, in row order, as one contiguous
+/// column: downstream vectorized code gets packed `f32`s instead of a
+/// `Vec<&User>` that points back into scattered rows.
+///
+/// Branchless like the sum: every balance is written to the next free slot
+/// and the slot only advances if the row qualifies, so a random mix of
+/// active flags costs no mispredictions. The result keeps the capacity of
+/// one slot per row.
+#[inline(never)]
+pub fn qualifying_balances(users_view: &UsersView, minimum_balance: f32) -> Vec<f32> {
+    let balances = &users_view.balances[..users_view.count];
+    let active = &users_view.active[..users_view.count];
+    let mut selected = vec![0.0f32; users_view.count];
+    let mut selected_count = 0;
+
+    for (&balance_value, &is_active) in balances.iter().zip(active) {
+        selected[selected_count] = balance_value;
+        selected_count += usize::from(is_active != 0 && balance_value >= minimum_balance);
+    }
+
+    selected.truncate(selected_count);
+    selected
+}
+
+/// Wrapping sum of the bit patterns of the qualifying balances. Integer
+/// addition is associative, so unlike the float sum this comes out the same
+/// for any reduction order, serial, chunked or SIMD.
+pub fn integer_checksum(users_view: &UsersView, minimum_balance: f32) -> u64 {
+    let mut checksum = 0u64;
+
+    for i in 0..users_view.count {
+        let balance_value = users_view.balances[i];
+        if users_view.active[i] != 0 && balance_value >= minimum_balance {
+            checksum = checksum.wrapping_add(balance_value.to_bits().into());
+        }
+    }
+
+    checksum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bench_core::BenchmarkConfig;
+    use rng::{Bernoulli, Uniform};
+
+    #[test]
+    fn default_view_is_empty() {
+        let users_view = UsersView::default();
+
+        assert_eq!(users_view.count, 0);
+        assert_eq!(sum_active_balances(&users_view, 0.0), 0.0);
+        assert_eq!(
+            sum_active_balances(&UsersColumns::default().view(), 0.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn swap_remove_keeps_columns_aligned() {
+        let mut columns = UsersColumns::from_triples(&[
+            (0, 100.0, true),
+            (1, 300.0, false),
+            (2, 500.0, true),
+            (3, 700.0, false),
+        ]);
+
+        assert_eq!(columns.swap_remove(1), (1, 300.0, false, 0.0));
+        assert_eq!(columns.swap_remove(0), (0, 100.0, true, 0.0));
+
+        let users_view = columns.view();
+        assert_eq!(users_view.count, 2);
+        assert_eq!(users_view.ids, &[2, 3]);
+        assert_eq!(users_view.balances, &[500.0, 700.0]);
+        assert_eq!(users_view.active, &[1, 0]);
+    }
+
+    #[test]
+    fn partition_moves_exactly_the_qualifiers_to_the_front() {
+        let columns = generate_columns(1000, 0.6, &mut Pcg32::seed_from_u64(17));
+        let mut partitioned = columns.clone();
+        let qualifying = partitioned.partition_qualifying(250.0);
+
+        let users_view = partitioned.view();
+        let qualifies = |user: &User| user.active && user.balance >= Money(250.0);
+        assert!((0..qualifying).all(|i| qualifies(&users_view.row(i))));
+        assert!((qualifying..1000).all(|i| !qualifies(&users_view.row(i))));
+        // Every row survives intact, wherever it moved.
+        assert!((0..1000).all(|i| {
+            let user = users_view.row(i);
+            user == columns.view().row(user.id as usize)
+        }));
+        assert_eq!(
+            partitioned.balances[..qualifying]
+                .iter()
+                .sum::<f32>()
+                .to_bits(),
+            sum_active_balances(&columns.view(), 250.0).to_bits()
+        );
+        assert_eq!(UsersColumns::default().partition_qualifying(0.0), 0);
+    }
+
+    #[test]
+    fn three_user_sum_from_triples() {
+        let columns =
+            UsersColumns::from_triples(&[(0, 100.0, true), (1, 300.0, false), (2, 500.0, true)]);
+        let users_view = columns.view();
+
+        assert_eq!(users_view.count, 3);
+        assert_eq!(users_view.ids, &[0, 1, 2]);
+        assert_eq!(sum_active_balances(&users_view, 250.0), 500.0);
+    }
+
+    #[test]
+    fn active_bits_pack_low_bit_first() {
+        let mut active = vec![0u8; 65];
+        active[0] = 1;
+        active[63] = 1;
+        active[64] = 1;
+
+        assert_eq!(pack_active(&active), [1 | (1 << 63), 1]);
+        assert!(pack_active(&[]).is_empty());
+    }
+
+    #[test]
+    fn bitset_scan_matches_the_byte_scan_bit_for_bit() {
+        for count in [0, 1, 63, 64, 65, 10_007] {
+            let columns = generate_columns(count, 0.6, &mut Pcg32::seed_from_u64(17));
+            let users_view = columns.view();
+            let active_bits = pack_active(&columns.active);
+
+            assert_eq!(
+                sum_active_balances_bits(&users_view.with_active_bits(&active_bits), 250.0)
+                    .to_bits(),
+                sum_active_balances(&users_view, 250.0).to_bits()
+            );
+        }
+    }
+
+    #[test]
+    fn balance_updates_write_and_mark_only_qualifying_rows() {
+        let mut rows: Vec<(i32, f32, bool)> = (0..70).map(|i| (i, 100.0, false)).collect();
+        rows[1] = (1, 300.0, true);
+        rows[2] = (2, 200.0, true);
+        rows[65] = (65, 500.0, true);
+        let mut columns = UsersColumns::from_triples(&rows);
+
+        let mut users_view = columns.view_mut();
+        assert_eq!(users_view.add_to_active_balances(10.0, 250.0), 2);
+        assert_eq!(users_view.dirty, [1 << 1, 1 << 1]);
+        // Row 2 now qualifies too; rows 1 and 65 are already dirty.
+        assert_eq!(users_view.add_to_active_balances(-90.0, 200.0), 3);
+        assert_eq!(users_view.dirty_count(), 3);
+        users_view.clear_dirty();
+        assert_eq!(users_view.dirty_count(), 0);
+
+        assert_eq!(columns.balances[..3], [100.0, 220.0, 110.0]);
+        assert_eq!(columns.balances[65], 420.0);
+        assert!(
+            columns.balances[3..65]
+                .iter()
+                .all(|&balance| balance == 100.0)
+        );
+    }
+
+    #[test]
+    fn qualifying_balances_are_the_summed_ones_in_row_order() {
+        let columns = UsersColumns::from_triples(&[
+            (0, 100.0, true),
+            (1, 300.0, false),
+            (2, 500.0, true),
+            (3, 250.0, true),
+        ]);
+        assert_eq!(qualifying_balances(&columns.view(), 250.0), [500.0, 250.0]);
+        assert!(qualifying_balances(&UsersView::default(), 0.0).is_empty());
+
+        let columns = generate_columns(10_007, 0.6, &mut Pcg32::seed_from_u64(17));
+        let users_view = columns.view();
+        let summed: f32 = qualifying_balances(&users_view, 250.0)
+            .iter()
+            .fold(0.0, |sum, &balance| sum + balance);
+        assert_eq!(
+            summed.to_bits(),
+            sum_active_balances(&users_view, 250.0).to_bits()
+        );
+    }
+
+    #[test]
+    fn rows_round_trip_against_the_generator() {
+        let columns = generate_columns(1000, 0.6, &mut Pcg32::seed_from_u64(17));
+        let users_view = columns.view();
+
+        let mut rng = Pcg32::seed_from_u64(17);
+        let balance_dist = Uniform::new(0.0f32, 1000.0f32);
+        let active_dist = Bernoulli::new(0.6).unwrap();
+        for i in 0..1000 {
+            let expected = User {
+                id: i as i32,
+                balance: Money(rng.sample(balance_dist)),
+                active: rng.sample(active_dist),
+                // Drawn from a stream of its own, see `generate_users`.
+                score: users_view.scores[i],
+            };
+            assert_eq!(users_view.row(i), expected);
+        }
+
+        assert_eq!(users_view.try_row(999), Some(users_view.row(999)));
+        assert_eq!(users_view.try_row(1000), None);
+        assert_eq!(UsersView::default().try_row(0), None);
+    }
+
+    #[test]
+    fn f32_and_f64_views_of_the_same_rows_agree() {
+        let columns =
+            UsersColumns::from_triples(&[(0, 100.5, true), (1, 300.0, false), (2, 500.25, true)]);
+        let wide_balances: Vec<f64> = columns
+            .balances
+            .iter()
+            .map(|&balance| balance.into())
+            .collect();
+        let narrow = columns.view();
+        let wide = UsersView {
+            ids: &columns.ids,
+            balances: &wide_balances,
+            active: &columns.active,
+            scores: &columns.scores,
+            count: columns.ids.len(),
+        };
+
+        assert_eq!(sum_active_balances(&narrow, 250.0f32), 500.25);
+        assert_eq!(sum_active_balances(&wide, 250.0f64), 500.25);
+        assert_eq!(sum_active_balances(&wide.prefix(2), 0.0), 100.5);
+        assert_eq!(sum_active_balances(&UsersView::<f64>::default(), 0.0), 0.0);
+    }
+
+    #[test]
+    fn integer_checksum_ignores_the_reduction_order() {
+        let columns = generate_columns(1000, 0.6, &mut Pcg32::seed_from_u64(17));
+        let users_view = columns.view();
+        let chunked = (0..1000).step_by(100).fold(0u64, |checksum, start| {
+            let chunk = UsersView {
+                ids: &columns.ids[start..start + 100],
+                balances: &columns.balances[start..start + 100],
+                active: &columns.active[start..start + 100],
+                scores: &[],
+                count: 100,
+            };
+            checksum.wrapping_add(integer_checksum(&chunk, 250.0))
+        });
+
+        let mut reversed = columns.clone();
+        reversed.ids.reverse();
+        reversed.balances.reverse();
+        reversed.active.reverse();
+        reversed.scores.reverse();
+
+        let expected = integer_checksum(&users_view, 250.0);
+        assert_eq!(chunked, expected);
+        assert_eq!(integer_checksum(&reversed.view(), 250.0), expected);
+        assert_eq!(
+            integer_checksum(&UsersColumns::from_triples(&[(0, 1.0, true)]).view(), 0.0),
+            u64::from(1.0f32.to_bits())
+        );
+        assert_eq!(integer_checksum(&UsersView::default(), 0.0), 0);
+    }
+
+    #[test]
+    fn repeating_view_sums_to_repeats_times_the_base() {
+        // Whole balances well below 2^24 add exactly, so the passes sum to
+        // exactly `repeats` times the base checksum.
+        let rows: Vec<(i32, f32, bool)> =
+            (0..37).map(|i| (i, (i * 50) as f32, i % 3 != 0)).collect();
+        let columns = UsersColumns::from_triples(&rows);
+        let base_checksum = sum_active_balances(&columns.view(), 250.0);
+
+        for repeats in [0, 1, 4, 25] {
+            let repeating = RepeatingUsersView::new(columns.view(), repeats);
+
+            assert_eq!(repeating.count, 37 * repeats);
+            assert_eq!(
+                sum_active_balances_repeating(&repeating, 250.0),
+                repeats as f32 * base_checksum
+            );
+        }
+
+        let repeating = RepeatingUsersView::new(columns.view(), 3);
+        assert_eq!(repeating.row(37 * 2 + 5), columns.view().row(5));
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow usize")]
+    fn repeating_view_rejects_an_overflowing_count() {
+        let columns = UsersColumns::from_triples(&[(0, 1.0, true), (1, 2.0, true)]);
+        RepeatingUsersView::new(columns.view(), usize::MAX);
+    }
+
+    #[test]
+    fn views_without_scores_read_them_as_zero() {
+        let columns = UsersColumns::from_triples(&[(0, 100.0, true), (1, 300.0, false)]);
+        let users_view = UsersView {
+            scores: &[],
+            ..columns.view()
+        };
+
+        assert_eq!(users_view.try_row(1).map(|user| user.score), Some(0.0));
+        assert_eq!(users_view.try_row(2), None);
+        let prefix = users_view.prefix(1);
+        assert_eq!(prefix.count, 1);
+        assert_eq!(prefix.row(0).balance, Money(100.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn row_panics_past_the_end() {
+        UsersView::default().row(0);
+    }
+
+    #[test]
+    fn canonical_dataset_matches_the_shared_checksum() {
+        let config = BenchmarkConfig::default();
+        let columns = generate_columns(
+            config.elements_count,
+            config.active_probability,
+            &mut Pcg32::seed_from_u64(config.random_seed),
+        );
+
+        assert_eq!(
+            sum_active_balances(&columns.view(), config.minimum_balance),
+            bench_core::CANONICAL_CHECKSUM
+        );
+    }
+}
@@ -1,108 +1,1770 @@
-use std::time::Instant;
-use rand::prelude::*;
-
-struct UsersView<'a> {
-    ids: &'a [i32],
-    balances: &'a [f32],
-    active: &'a [u8],
-    count: usize,
-}
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 
-#[inline(never)]
-fn sum_active_balances(users_view: &UsersView, minimum_balance: f32) -> f32 {
-    let mut accumulated_balance = 0.0f32;
-    let threshold_balance = minimum_balance;
+mod accumulate;
+mod cold_cache;
+mod config;
+mod decay;
+mod filter;
+mod histogram;
+mod memory;
+mod peak;
+mod plot;
+mod prefetch;
+mod profile;
+mod report;
+mod scenario_filter;
+mod simd;
+mod simd_dispatch;
+mod size_sweep;
+mod summation_order;
+mod tlb;
+mod working_set;
+
+use std::time::Duration;
+
+use bench_core::contention::{self, CacheThrasher};
+use bench_core::csv::{self, CsvRecord};
+use bench_core::dataset;
+use bench_core::timer::{InstantTimer, Timer};
+use bench_core::{
+    ADAPTIVE_WARMUP_MAX_ITERATIONS, ADAPTIVE_WARMUP_TOLERANCE, BenchmarkConfig,
+    COLD_CACHE_SCRATCH_BYTES, Money, Stats, User, adaptive_warmup, evict_cache, generate_users,
+    generate_users_allowing_negative, measure_execution_time, measure_execution_time_detailed,
+    measure_execution_time_with, measure_for_duration, sanitize_balances,
+};
+use bench_core::{affinity, json, sweep};
+use dod_p::rng::{self, Pcg32};
+use dod_p::{
+    RepeatingUsersView, UsersColumns, UsersView, generate_columns,
+    generate_columns_allowing_negative, integer_checksum, pack_active, sum_active_balances,
+    sum_active_balances_bits, sum_active_balances_repeating,
+};
+
+use accumulate::{F64Acc, KahanAcc, MaskedSum, NaiveAcc};
+use config::ConfigFile;
+use filter::Filter;
+use plot::PlotPoint;
+use rayon::prelude::*;
+use report::{BenchResult, OutputFormat};
+use scenario_filter::ScenarioFilter;
+use simd_dispatch::SimdPath;
+use working_set::BackgroundBuffer;
+
+/// `sum_active_balances` accumulating in `f64`, so large datasets do not lose
+/// the low bits of each addition. Not benchmarked: the `f32` loop stays the
+/// apples-to-apples comparison with the other implementations.
+fn sum_active_balances_f64(users_view: &UsersView, minimum_balance: f32) -> f64 {
+    let mut accumulated_balance = 0.0f64;
 
     for i in 0..users_view.count {
         let balance_value = users_view.balances[i];
-        let take_value = if users_view.active[i] != 0 && balance_value >= threshold_balance {
-            1.0f32
-        } else {
-            0.0f32
-        };
-        accumulated_balance += balance_value * take_value;
+        if users_view.active[i] != 0 && balance_value >= minimum_balance {
+            accumulated_balance += balance_value as f64;
+        }
     }
 
     accumulated_balance
 }
 
-fn measure_execution_time<F, R>(iterations: usize, mut f: F) -> f64
-where
-    F: FnMut() -> R,
-{
-    let start = Instant::now();
+/// Rows per rayon task; large enough that scheduling is noise next to the scan.
+const PARALLEL_CHUNK_ELEMENTS: usize = 64 * 1024;
+
+/// Splits the columns into chunks, scans each with `sum_active_balances` on a
+/// rayon worker and adds up the partial sums. The grouping differs from the
+/// serial loop, so the checksum matches it only up to f32 rounding.
+#[inline(never)]
+fn sum_active_balances_parallel(users_view: &UsersView, minimum_balance: f32) -> f32 {
+    let balances = &users_view.balances[..users_view.count];
+    let active = &users_view.active[..users_view.count];
+
+    balances
+        .par_chunks(PARALLEL_CHUNK_ELEMENTS)
+        .zip(active.par_chunks(PARALLEL_CHUNK_ELEMENTS))
+        .map(|(balances, active)| {
+            let chunk_view = UsersView {
+                ids: &[],
+                balances,
+                active,
+                scores: &[],
+                count: balances.len(),
+            };
+            sum_active_balances(&chunk_view, minimum_balance)
+        })
+        .sum()
+}
+
+/// Chunks of the `dod_parallel_stable` scan. A fixed count rather than one
+/// per thread, so the grouping, and with it the result, is the same on every
+/// machine; 64 keeps all cores of a large box busy.
+const STABLE_PARALLEL_CHUNKS: usize = 64;
+
+/// `sum_active_balances_parallel` with a reproducible result: the columns are
+/// split into `chunks` equal parts (the last one may be shorter), each part
+/// is summed on a rayon worker, and the partial sums are added in index order
+/// on the calling thread. Work stealing only changes which worker sums a
+/// part, never how the additions are grouped.
+#[inline(never)]
+fn sum_active_balances_stable_parallel(
+    users_view: &UsersView,
+    minimum_balance: f32,
+    chunks: usize,
+) -> f32 {
+    assert!(chunks > 0, "cannot split the columns into zero chunks");
+    let balances = &users_view.balances[..users_view.count];
+    let active = &users_view.active[..users_view.count];
+    let chunk_elements = users_view.count.div_ceil(chunks).max(1);
+
+    let partial_sums: Vec<f32> = balances
+        .par_chunks(chunk_elements)
+        .zip(active.par_chunks(chunk_elements))
+        .map(|(balances, active)| {
+            let chunk_view = UsersView {
+                ids: &[],
+                balances,
+                active,
+                scores: &[],
+                count: balances.len(),
+            };
+            sum_active_balances(&chunk_view, minimum_balance)
+        })
+        .collect();
+
+    partial_sums.iter().sum()
+}
+
+/// Scans once for the checksum, then warms up for `warmup_iterations` scans,
+/// or until timings settle if `adaptive`. Returns the checksum and the warmup
+/// scans run. The first scan is untimed and always runs, like the one in
+/// `bench_core::sweep::run`, so `--warmup 0` still reports the real sum.
+fn warm_up(
+    users_view: &UsersView,
+    minimum_balance: f32,
+    warmup_iterations: usize,
+    adaptive: bool,
+) -> (f32, usize) {
+    let mut checksum = sum_active_balances(users_view, minimum_balance);
+    let warmup_ran = if adaptive {
+        adaptive_warmup(
+            ADAPTIVE_WARMUP_MAX_ITERATIONS,
+            ADAPTIVE_WARMUP_TOLERANCE,
+            || checksum = sum_active_balances(users_view, minimum_balance),
+        )
+    } else {
+        for _ in 0..warmup_iterations {
+            checksum = sum_active_balances(users_view, minimum_balance);
+        }
+        warmup_iterations
+    };
+
+    (checksum, warmup_ran)
+}
+
+struct Options {
+    benchmark: BenchmarkConfig,
+    /// Warm up until timings settle instead of for `warmup_iterations`.
+    adaptive_warmup: bool,
+    /// Core the benchmark thread is pinned to before warming up.
+    pin_core: Option<usize>,
+    /// Generate with `generate_users_allowing_negative`.
+    allow_negative: bool,
+    output_format: OutputFormat,
+    contention: bool,
+    /// Size of the companion's buffer, `contention::DEFAULT_BUFFER_BYTES` if unset.
+    contention_bytes: Option<usize>,
+    filter_dsl: bool,
+    tlb: bool,
+    peak: bool,
+    accumulators: bool,
+    cold_cache: bool,
+    precision: bool,
+    size_sweep: bool,
+    /// Sizes to regenerate and rerun the baseline scan at, see `bench_core::sweep`.
+    sweep: Option<Vec<usize>>,
+    working_set_mebibytes: Vec<usize>,
+    decay: bool,
+    parallel: bool,
+    simd: bool,
+    simd_auto: bool,
+    prefetch: bool,
+    active_bits: bool,
+    /// Times the `dod_repeat` scenario scans the columns back to back.
+    repeat_dataset: Option<usize>,
+    summation_order: bool,
+    csv_header: bool,
+    csv_out: Option<String>,
+    /// `-` writes the JSON Lines to stdout.
+    json_out: Option<String>,
+    dataset: Option<String>,
+    dataset_csv: Option<String>,
+    histogram: Option<usize>,
+    memory: bool,
+    /// Seconds the `dod_time_budget` scenario runs for.
+    time_budget: Option<f64>,
+    /// Seconds to run only the baseline scan for, under a profiler.
+    profile: Option<f64>,
+    plot_data: Option<String>,
+    scenario_filter: Option<ScenarioFilter>,
+}
+
+impl Options {
+    /// The `dod` baseline always runs; the filter only selects among the extra scenarios.
+    fn selects(&self, scenario: &str) -> bool {
+        self.scenario_filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches(scenario))
+    }
+}
+
+fn parse_options(args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let args: Vec<String> = args.collect();
+    let mut options = Options {
+        benchmark: BenchmarkConfig::default(),
+        adaptive_warmup: false,
+        pin_core: None,
+        allow_negative: false,
+        output_format: OutputFormat::Text,
+        contention: false,
+        contention_bytes: None,
+        filter_dsl: false,
+        tlb: false,
+        peak: false,
+        accumulators: false,
+        cold_cache: false,
+        precision: false,
+        size_sweep: false,
+        sweep: None,
+        working_set_mebibytes: Vec::new(),
+        decay: false,
+        parallel: false,
+        simd: false,
+        simd_auto: false,
+        prefetch: false,
+        active_bits: false,
+        repeat_dataset: None,
+        summation_order: false,
+        csv_header: false,
+        csv_out: None,
+        json_out: None,
+        dataset: None,
+        dataset_csv: None,
+        histogram: None,
+        memory: false,
+        time_budget: None,
+        profile: None,
+        plot_data: None,
+        scenario_filter: None,
+    };
+
+    let mut benchmark = BenchmarkConfig::builder();
 
-    for _ in 0..iterations {
-        let _ = f();
+    // The file is applied first so flags override it wherever they appear.
+    let mut config_positions = (0..args.len()).filter(|&i| args[i] == "--config");
+    if let Some(position) = config_positions.next() {
+        if config_positions.next().is_some() {
+            return Err("--config can only be given once".to_string());
+        }
+        let path = args.get(position + 1).ok_or("--config requires a file")?;
+        let config = ConfigFile::load(path)?;
+        config.apply_benchmark(&mut benchmark);
+        config.apply(&mut options)?;
     }
 
-    start.elapsed().as_secs_f64()
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--elements" => {
+                benchmark.elements(parse_value(&arg, args.next())?);
+            }
+            "--min-balance" => {
+                benchmark.min_balance(parse_value(&arg, args.next())?);
+            }
+            "--seed" => {
+                benchmark.seed(parse_value(&arg, args.next())?);
+            }
+            "--warmup" => {
+                benchmark.warmup(parse_value(&arg, args.next())?);
+            }
+            "--adaptive-warmup" => options.adaptive_warmup = true,
+            "--pin-core" => options.pin_core = Some(parse_value(&arg, args.next())?),
+            "--iterations" => {
+                benchmark.iterations(parse_value(&arg, args.next())?);
+            }
+            "--active-prob" => {
+                benchmark.active_prob(parse_value(&arg, args.next())?);
+            }
+            "--allow-negative" => options.allow_negative = true,
+            "--format" => {
+                let value = args.next().ok_or("--format requires a value")?;
+                options.output_format = OutputFormat::parse(&value)?;
+            }
+            "--contention" => options.contention = true,
+            "--contention-bytes" => {
+                options.contention_bytes = Some(parse_value(&arg, args.next())?);
+            }
+            "--filter-dsl" => options.filter_dsl = true,
+            "--tlb" => options.tlb = true,
+            "--peak" => options.peak = true,
+            "--accumulators" => options.accumulators = true,
+            "--cold-cache" => options.cold_cache = true,
+            "--precision" => options.precision = true,
+            "--size-sweep" => options.size_sweep = true,
+            "--sweep" => {
+                let value = args
+                    .next()
+                    .ok_or("--sweep requires comma-separated sizes")?;
+                options.sweep = Some(sweep::parse_sizes(&value)?);
+            }
+            "--decay" => options.decay = true,
+            "--parallel" => options.parallel = true,
+            "--simd" => options.simd = true,
+            "--simd-auto" => options.simd_auto = true,
+            "--prefetch" => options.prefetch = true,
+            "--active-bits" => options.active_bits = true,
+            "--repeat-dataset" => {
+                options.repeat_dataset = Some(parse_value(&arg, args.next())?);
+            }
+            "--summation-order" => options.summation_order = true,
+            "--csv-header" => options.csv_header = true,
+            "--csv-out" => {
+                options.csv_out = Some(args.next().ok_or("--csv-out requires a file")?);
+            }
+            "--json-out" => {
+                options.json_out = Some(args.next().ok_or("--json-out requires a file or '-'")?);
+            }
+            "--dataset" => {
+                options.dataset = Some(args.next().ok_or("--dataset requires a file")?);
+            }
+            "--dataset-csv" => {
+                options.dataset_csv = Some(args.next().ok_or("--dataset-csv requires a file")?);
+            }
+            "--histogram" => {
+                options.histogram = Some(parse_value(&arg, args.next())?);
+            }
+            "--memory" => options.memory = true,
+            "--time-budget" => options.time_budget = Some(parse_value(&arg, args.next())?),
+            "--profile" => options.profile = Some(parse_value(&arg, args.next())?),
+            "--plot-data" => {
+                options.plot_data = Some(args.next().ok_or("--plot-data requires a file")?);
+            }
+            "--working-set" => {
+                let value = args.next().ok_or("--working-set requires sizes in MiB")?;
+                options.working_set_mebibytes = working_set::parse_mebibytes(&value)?;
+            }
+            "--config" => {
+                args.next();
+            }
+            "--scenario-filter" => {
+                let value = args.next().ok_or("--scenario-filter requires a pattern")?;
+                options.scenario_filter = Some(ScenarioFilter::parse(&value)?);
+            }
+            other => return Err(format!("unknown argument '{other}'")),
+        }
+    }
+
+    options.benchmark = benchmark.build().map_err(|error| error.to_string())?;
+    if options.dataset.is_some() && options.dataset_csv.is_some() {
+        return Err("--dataset and --dataset-csv are mutually exclusive".to_string());
+    }
+    if (options.dataset.is_some() || options.dataset_csv.is_some())
+        && (options.size_sweep || options.sweep.is_some())
+    {
+        return Err("--dataset fixes the element count, so it cannot sweep".to_string());
+    }
+    if (options.dataset.is_some() || options.dataset_csv.is_some()) && options.allow_negative {
+        return Err("--dataset replaces the generator, so it cannot --allow-negative".to_string());
+    }
+    if options.size_sweep && options.sweep.is_some() {
+        return Err("--size-sweep and --sweep are mutually exclusive".to_string());
+    }
+    if let Some(seconds) = options.time_budget {
+        if !(seconds.is_finite() && seconds > 0.0) {
+            return Err(format!("time-budget must be positive, got {seconds}"));
+        }
+        Duration::try_from_secs_f64(seconds)
+            .map_err(|error| format!("time-budget of {seconds} s: {error}"))?;
+    }
+    if let Some(seconds) = options.profile {
+        if !(seconds.is_finite() && seconds > 0.0) {
+            return Err(format!("profile must be positive, got {seconds}"));
+        }
+        Duration::try_from_secs_f64(seconds)
+            .map_err(|error| format!("profile of {seconds} s: {error}"))?;
+    }
+    if options.profile.is_some() && (options.size_sweep || options.sweep.is_some()) {
+        return Err("--profile runs one fixed-size scan, so it cannot sweep".to_string());
+    }
+    if options.contention_bytes.is_some() && !options.contention {
+        return Err("--contention-bytes sizes the --contention companion".to_string());
+    }
+    if options.contention_bytes == Some(0) {
+        return Err("contention-bytes must be nonzero".to_string());
+    }
+    if let Some(repeats) = options.repeat_dataset {
+        if repeats == 0 {
+            return Err("repeat-dataset must be nonzero".to_string());
+        }
+        let elements = options.benchmark.elements_count;
+        if elements.checked_mul(repeats).is_none() {
+            return Err(format!(
+                "repeat-dataset {repeats} times {elements} elements overflows usize"
+            ));
+        }
+    }
+    if options.histogram == Some(0) {
+        return Err("histogram needs at least one bucket".to_string());
+    }
+    if json::writes_to_stdout(options.json_out.as_deref())
+        && options.output_format != OutputFormat::Text
+    {
+        return Err("--json-out - and --format both write to stdout".to_string());
+    }
+    if options.plot_data.is_some()
+        && !options.size_sweep
+        && options.working_set_mebibytes.is_empty()
+    {
+        return Err("--plot-data needs a sweep: --size-sweep or --working-set".to_string());
+    }
+
+    Ok(options)
+}
+
+/// Parses the value following a numeric flag such as `--elements`.
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{flag} requires a value"))?;
+    value
+        .parse()
+        .map_err(|_| format!("{flag} expects a number, got '{value}'"))
+}
+
+fn save_plot_data<'a>(path: &str, points: impl IntoIterator<Item = PlotPoint<'a>>) {
+    if let Err(message) = plot::save(path, points) {
+        eprintln!("error: {message}");
+        std::process::exit(1);
+    }
 }
 
 fn main() {
-    const ELEMENTS_COUNT: usize = 10_000;
-    const MINIMUM_BALANCE: f32 = 250.0;
-    const RANDOM_SEED: u64 = 17;
-    const WARMUP_ITERATIONS: usize = 2;
-    const ITERATIONS: usize = 8;
-
-    println!();
-    println!("[ DoD Benchmark ]");
-    println!("Elements Count    : {}", ELEMENTS_COUNT);
-    println!("Minimum Balance   : {:.2}", MINIMUM_BALANCE);
-    println!("Random Seed       : {}", RANDOM_SEED);
-    println!("Warmup Iterations : {}", WARMUP_ITERATIONS);
-    println!("Iterations        : {}", ITERATIONS);
-
-    let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
-    let balance_dist = rand::distributions::Uniform::new(0.0f32, 1000.0f32);
-    let active_dist = rand::distributions::Bernoulli::new(0.6).unwrap();
-
-    println!();
-    println!("Generating elements...");
-
-    let mut user_ids = Vec::with_capacity(ELEMENTS_COUNT);
-    let mut user_balances = Vec::with_capacity(ELEMENTS_COUNT);
-    let mut user_active_flags = Vec::with_capacity(ELEMENTS_COUNT);
-
-    for i in 0..ELEMENTS_COUNT {
-        user_ids.push(i as i32);
-        user_balances.push(rng.sample(balance_dist));
-        user_active_flags.push(if rng.sample(active_dist) { 1u8 } else { 0u8 });
-    }
-
-    let users_view = UsersView {
-        ids: &user_ids,
-        balances: &user_balances,
-        active: &user_active_flags,
-        count: ELEMENTS_COUNT,
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {message}");
+            std::process::exit(2);
+        }
+    };
+    let BenchmarkConfig {
+        elements_count,
+        minimum_balance,
+        random_seed,
+        warmup_iterations,
+        iterations,
+        active_probability,
+    } = options.benchmark;
+    // Machine-readable formats must contain nothing but the results.
+    let json_to_stdout = json::writes_to_stdout(options.json_out.as_deref());
+    let verbose = options.output_format == OutputFormat::Text && !json_to_stdout;
+
+    let generate: fn(usize, f64, &mut Pcg32) -> UsersColumns = if options.allow_negative {
+        generate_columns_allowing_negative
+    } else {
+        generate_columns
     };
 
-    println!();
-    println!("Warming up...");
+    // The sweep replaces the fixed-size run and prints nothing but CSV.
+    if options.size_sweep {
+        affinity::pin_or_warn(options.pin_core);
+        let columns = generate(
+            size_sweep::MAX_ELEMENTS,
+            active_probability,
+            &mut Pcg32::seed_from_u64(random_seed),
+        );
+        let rows = size_sweep::run(
+            &mut InstantTimer::default(),
+            &columns.view(),
+            minimum_balance,
+        );
+        size_sweep::write_csv(&mut std::io::stdout().lock(), &rows)
+            .expect("failed to write the size sweep to stdout");
 
-    let mut checksum = 0.0f32;
-    for _ in 0..WARMUP_ITERATIONS {
-        checksum = sum_active_balances(&users_view, MINIMUM_BALANCE);
+        if let Some(path) = &options.plot_data {
+            save_plot_data(
+                path,
+                rows.iter().map(|row| PlotPoint {
+                    sweep_variable: "elements",
+                    value: row.result.elements,
+                    result: &row.result,
+                }),
+            );
+        }
+        return;
     }
 
-    println!();
-    println!("Benchmarking...");
+    // Regenerates the users at every size; the table replaces the fixed-size run.
+    if let Some(sizes) = &options.sweep {
+        affinity::pin_or_warn(options.pin_core);
+        let generate_users: fn(usize, u64, f64) -> Vec<User> = if options.allow_negative {
+            generate_users_allowing_negative
+        } else {
+            generate_users
+        };
+        let rows = sweep::run(
+            &mut InstantTimer::default(),
+            sizes,
+            &options.benchmark,
+            generate_users,
+            || {},
+            |users| UsersColumns::from_users(&users),
+            |columns| sum_active_balances(&columns.view(), minimum_balance),
+        );
+        sweep::print_table("DoD", &rows);
+        if let Some(path) = &options.csv_out
+            && let Err(message) = sweep::append_csv(path, "dod", random_seed, &rows)
+        {
+            eprintln!("error: {message}");
+            std::process::exit(1);
+        }
+        return;
+    }
 
-    let total_time_seconds = measure_execution_time(ITERATIONS, || {
-        sum_active_balances(&users_view, MINIMUM_BALANCE)
+    // A dataset file replaces the generator and fixes the element count.
+    let dataset_path = options
+        .dataset
+        .as_deref()
+        .or(options.dataset_csv.as_deref());
+    let dataset = dataset_path.map(|path| {
+        let loaded = if options.dataset.is_some() {
+            dataset::load_users_json(path).map_err(|error| error.to_string())
+        } else {
+            csv::load_users_csv(path).map_err(|error| error.to_string())
+        };
+        match loaded {
+            Ok(mut users) if !users.is_empty() => {
+                let replaced = sanitize_balances(users.iter_mut().map(|user| &mut user.balance.0));
+                if replaced > 0 {
+                    eprintln!(
+                        "warning: replaced {replaced} non-finite balances in '{path}' with 0.0"
+                    );
+                }
+                users
+            }
+            Ok(_) => {
+                eprintln!("error: dataset '{path}' is empty");
+                std::process::exit(1);
+            }
+            Err(error) => {
+                eprintln!("error: cannot load dataset '{path}': {error}");
+                std::process::exit(1);
+            }
+        }
     });
+    let elements_count = dataset.as_ref().map_or(elements_count, Vec::len);
+
+    if verbose {
+        println!();
+        println!("[ DoD Benchmark ]");
+        println!("Elements Count    : {}", elements_count);
+        println!("Minimum Balance   : {:.2}", minimum_balance);
+        println!("Random Seed       : {}", random_seed);
+        if options.adaptive_warmup {
+            println!(
+                "Warmup Iterations : adaptive (max {}, {} % tolerance)",
+                ADAPTIVE_WARMUP_MAX_ITERATIONS,
+                ADAPTIVE_WARMUP_TOLERANCE * 100.0
+            );
+        } else {
+            println!("Warmup Iterations : {}", warmup_iterations);
+        }
+        println!("Iterations        : {}", iterations);
+        println!("Active Prob       : {}", active_probability);
+        println!("Allow Negative    : {}", options.allow_negative);
+        println!("Pinned Core       : {:?}", options.pin_core);
+        println!("Contention        : {}", options.contention);
+        println!("Filter DSL        : {}", options.filter_dsl);
+        println!("TLB Pressure      : {}", options.tlb);
+        println!("SIMD Peak         : {}", options.peak);
+        println!("Accumulators      : {}", options.accumulators);
+        if options.cold_cache {
+            println!("Cold Cache        : true ({})", cold_cache::EVICTION);
+        } else {
+            println!("Cold Cache        : false");
+        }
+        println!("Precision         : {}", options.precision);
+        println!("Working Set MiB   : {:?}", options.working_set_mebibytes);
+        println!("Decay + Retain    : {}", options.decay);
+        println!("Summation Order   : {}", options.summation_order);
+        println!("Parallel (rayon)  : {}", options.parallel);
+        println!("Portable SIMD     : {}", options.simd);
+        println!("Auto SIMD         : {}", options.simd_auto);
+        println!("SIMD Dispatch     : {}", SimdPath::detect().name());
+        println!("Prefetch Hints    : {}", options.prefetch);
+        println!("Active Bitset     : {}", options.active_bits);
+        println!("Repeat Dataset    : {:?}", options.repeat_dataset);
+        println!("Histogram Buckets : {:?}", options.histogram);
+        println!("Memory Footprint  : {}", options.memory);
+        println!("Time Budget (s)   : {:?}", options.time_budget);
+        println!("Profile (s)       : {:?}", options.profile);
+        println!(
+            "Dataset           : {}",
+            dataset_path.unwrap_or("generated")
+        );
+    }
+
+    let mut rng = Pcg32::seed_from_u64(random_seed);
+
+    let columns = match &dataset {
+        Some(users) => UsersColumns::from_users(users),
+        None => {
+            if verbose {
+                println!();
+                println!("Generating elements...");
+            }
+            generate(elements_count, active_probability, &mut rng)
+        }
+    };
+
+    if let Some(buckets) = options.histogram.filter(|_| verbose) {
+        histogram::print_balance_histogram(&columns.balances, buckets);
+    }
+    if options.memory && verbose {
+        memory::print_memory_footprint(&columns);
+    }
+
+    let users_view = columns.view();
+    let mut timer = InstantTimer::default();
+
+    if options.parallel && options.pin_core.is_some() {
+        // Starts the global pool while this thread can still run anywhere;
+        // workers spawned after pinning would all share its one core.
+        rayon::current_num_threads();
+    }
+    affinity::pin_or_warn(options.pin_core);
+
+    // Replaces warmup, timing and every other scenario.
+    if let Some(seconds) = options.profile {
+        profile::run(
+            &users_view,
+            minimum_balance,
+            Duration::from_secs_f64(seconds),
+        );
+        return;
+    }
+
+    if verbose {
+        println!();
+        println!("Warming up...");
+    }
+
+    let (checksum, warmup_ran) = warm_up(
+        &users_view,
+        minimum_balance,
+        warmup_iterations,
+        options.adaptive_warmup,
+    );
+    if verbose {
+        println!("Warmup Iterations Run      : {}", warmup_ran);
+    }
+
+    if verbose {
+        println!();
+        println!("Benchmarking...");
+    }
+
+    let stats = measure_execution_time_detailed(&mut timer, iterations, || {
+        sum_active_balances(&users_view, minimum_balance)
+    });
+
+    let mut results = vec![
+        BenchResult::new(
+            "dod",
+            "DoD",
+            elements_count,
+            iterations,
+            checksum,
+            stats.mean * iterations as f64,
+        )
+        .with_stats(stats),
+    ];
+    // Mode-specific comparison lines printed after the results blocks.
+    let mut comparisons = Vec::new();
+    let checksum_f64 = sum_active_balances_f64(&users_view, minimum_balance);
+    comparisons.push(format!("Checksum (f32)             : {:.8}", checksum));
+    comparisons.push(format!("Checksum (f64)             : {:.8}", checksum_f64));
+    comparisons.push(format!(
+        "f32 Drift                  : {:.8}",
+        (checksum as f64 - checksum_f64).abs()
+    ));
+    comparisons.push(format!(
+        "Integer Checksum           : {}",
+        integer_checksum(&users_view, minimum_balance)
+    ));
+    let mut skipped = Vec::new();
+    let mut select = |scenario: &str| {
+        let selected = options.selects(scenario);
+        if !selected {
+            skipped.push(scenario.to_string());
+        }
+        selected
+    };
+
+    if options.peak {
+        let width = peak::detect_simd_width();
+        comparisons.push(format!(
+            "SIMD Width                 : {} f32 lanes ({})",
+            width.f32_lanes, width.name
+        ));
+
+        match peak::estimate_clock_hz() {
+            Some(clock_hz) => {
+                let peak_elements_per_second = peak::peak_elements_per_second(&width, clock_hz);
+                let achieved = results[0].elements_per_second / peak_elements_per_second * 100.0;
+                comparisons.push(format!(
+                    "Estimated Clock (approx.)  : {:.2} GHz",
+                    clock_hz / 1e9
+                ));
+                comparisons.push(format!(
+                    "Theoretical Peak (approx.) : {:.2} M elements/s",
+                    peak_elements_per_second / 1e6
+                ));
+                comparisons.push(format!("Fraction of Peak (approx.) : {:.1} %", achieved));
+            }
+            None => comparisons
+                .push("Estimated Clock            : unavailable on this target".to_string()),
+        }
+    }
+
+    if options.cold_cache && select("dod_cold") {
+        if verbose {
+            println!();
+            println!("Benchmarking with evicted columns...");
+        }
+
+        // Only one of the two evicts anything: `flush` is a no-op without
+        // clflush, and the scratch buffer is empty with it.
+        let mut scratch = if cold_cache::SUPPORTED {
+            Vec::new()
+        } else {
+            vec![0u8; COLD_CACHE_SCRATCH_BYTES]
+        };
+        let mut cold_checksum = 0.0f32;
+        let cold_time_seconds = measure_execution_time_with(
+            &mut timer,
+            iterations,
+            || {
+                cold_cache::flush(users_view.balances);
+                cold_cache::flush(users_view.active);
+                evict_cache(&mut scratch);
+            },
+            || cold_checksum = sum_active_balances(&users_view, minimum_balance),
+        );
+
+        results.push(BenchResult::new(
+            "dod_cold",
+            "DoD (cold cache)",
+            elements_count,
+            iterations,
+            cold_checksum,
+            cold_time_seconds,
+        ));
+
+        let cold = &results[results.len() - 1];
+        comparisons.push(format!(
+            "Cold Cache Slowdown        : {:.2}x",
+            cold.nanoseconds_per_element / results[0].nanoseconds_per_element
+        ));
+    }
+
+    // (background MiB, index into `results`); the hot baseline is 0 MiB.
+    let mut working_set_points = vec![(0, 0)];
+
+    if !options.working_set_mebibytes.is_empty() {
+        if verbose {
+            println!();
+            println!("Benchmarking between background working-set passes...");
+        }
+
+        for &mebibytes in &options.working_set_mebibytes {
+            let scenario = format!("dod_working_set_{mebibytes}mib");
+            if !select(&scenario) {
+                continue;
+            }
+
+            let mut background = BackgroundBuffer::new(mebibytes * 1024 * 1024);
+            let mut working_set_checksum = 0.0f32;
+            let working_set_time_seconds = measure_execution_time_with(
+                &mut timer,
+                iterations,
+                || background.touch(),
+                || working_set_checksum = sum_active_balances(&users_view, minimum_balance),
+            );
+
+            results.push(BenchResult::new(
+                &scenario,
+                &format!("DoD (working set {mebibytes} MiB)"),
+                elements_count,
+                iterations,
+                working_set_checksum,
+                working_set_time_seconds,
+            ));
+
+            working_set_points.push((mebibytes, results.len() - 1));
+
+            let measured = &results[results.len() - 1];
+            comparisons.push(format!(
+                "{:<26} : {:.1} % of DoD throughput",
+                format!("Working Set {mebibytes} MiB"),
+                measured.elements_per_second / results[0].elements_per_second * 100.0
+            ));
+        }
+    }
+
+    if options.contention && select("dod_contended") {
+        if verbose {
+            println!();
+            println!("Benchmarking with cache-thrashing companion...");
+        }
+
+        let thrasher = CacheThrasher::start(
+            options
+                .contention_bytes
+                .unwrap_or(contention::DEFAULT_BUFFER_BYTES),
+            options.pin_core,
+        );
+        let mut contended_checksum = 0.0f32;
+        let contended_time_seconds = measure_execution_time(&mut timer, iterations, || {
+            contended_checksum = sum_active_balances(&users_view, minimum_balance);
+        });
+        thrasher.stop();
+
+        results.push(BenchResult::new(
+            "dod_contended",
+            "DoD (contended)",
+            elements_count,
+            iterations,
+            contended_checksum,
+            contended_time_seconds,
+        ));
+
+        let contended = &results[results.len() - 1];
+        comparisons.push(format!(
+            "Contention Degradation     : {:.1} %",
+            (1.0 - contended.elements_per_second / results[0].elements_per_second) * 100.0
+        ));
+        comparisons.push(format!(
+            "Contended Checksum         : {:.8} ({})",
+            contended_checksum,
+            if contended_checksum.to_bits() == checksum.to_bits() {
+                "matches baseline"
+            } else {
+                "MISMATCH"
+            }
+        ));
+    }
+
+    if options.parallel && select("dod_parallel") {
+        if verbose {
+            println!();
+            println!("Benchmarking parallel reduction...");
+        }
+
+        let mut parallel_checksum = sum_active_balances_parallel(&users_view, minimum_balance);
+        let parallel_time_seconds = measure_execution_time(&mut timer, iterations, || {
+            parallel_checksum = sum_active_balances_parallel(&users_view, minimum_balance);
+        });
+
+        results.push(BenchResult::new(
+            "dod_parallel",
+            "DoD (rayon parallel)",
+            elements_count,
+            iterations,
+            parallel_checksum,
+            parallel_time_seconds,
+        ));
+
+        let parallel = &results[results.len() - 1];
+        comparisons.push(format!(
+            "Rayon Threads              : {}",
+            rayon::current_num_threads()
+        ));
+        comparisons.push(format!(
+            "Serial | Parallel Time     : {:.2} us | {:.2} us",
+            results[0].average_time_seconds * 1e6,
+            parallel.average_time_seconds * 1e6
+        ));
+        comparisons.push(format!(
+            "Parallel Speedup           : {:.2}x",
+            parallel.elements_per_second / results[0].elements_per_second
+        ));
+    }
+
+    if options.parallel && select("dod_parallel_stable") {
+        if verbose {
+            println!();
+            println!("Benchmarking stable-order parallel reduction...");
+        }
+
+        let mut stable_checksum = sum_active_balances_stable_parallel(
+            &users_view,
+            minimum_balance,
+            STABLE_PARALLEL_CHUNKS,
+        );
+        let stable_time_seconds = measure_execution_time(&mut timer, iterations, || {
+            stable_checksum = sum_active_balances_stable_parallel(
+                &users_view,
+                minimum_balance,
+                STABLE_PARALLEL_CHUNKS,
+            );
+        });
+
+        results.push(BenchResult::new(
+            "dod_parallel_stable",
+            "DoD (rayon, stable order)",
+            elements_count,
+            iterations,
+            stable_checksum,
+            stable_time_seconds,
+        ));
+    }
+
+    if options.simd && !simd::SUPPORTED {
+        eprintln!(
+            "warning: --simd needs the `simd` feature on nightly; \
+             skipping the portable SIMD scans"
+        );
+    }
 
-    let average_time_seconds = total_time_seconds / ITERATIONS as f64;
-    let elements_per_second = ELEMENTS_COUNT as f64 / average_time_seconds;
-    let nanoseconds_per_element = (average_time_seconds * 1e9) / ELEMENTS_COUNT as f64;
-
-    println!();
-    println!("[ DoD Results ]");
-    println!("Checksum                   : {:.8}", checksum);
-    println!("Total Time                 : {:.2} s", total_time_seconds);
-    println!("Average Time per Iteration : {:.2} s", average_time_seconds);
-    println!("Elements per Second        : {:.2} M", elements_per_second / 1e6);
-    println!("Nanoseconds per Element    : {:.2}", nanoseconds_per_element);
-    println!();
+    // One kernel per lane width, so the sweet spot for this CPU shows up in
+    // a single run. Without the feature every width is the same scalar
+    // fallback, so none of them is reported as a SIMD result.
+    let simd_widths: [(&str, usize, simd::SimdSum); 3] = [
+        ("dod_simd_4", 4, simd::sum_active_balances_simd::<4>),
+        ("dod_simd_8", 8, simd::sum_active_balances_simd::<8>),
+        ("dod_simd_16", 16, simd::sum_active_balances_simd::<16>),
+    ];
+    for (scenario, lanes, sum_simd) in simd_widths {
+        if !(options.simd && simd::SUPPORTED && select(scenario)) {
+            continue;
+        }
+
+        if verbose {
+            println!();
+            println!("Benchmarking portable SIMD scan ({lanes} lanes)...");
+        }
+
+        let mut simd_checksum = sum_simd(&users_view, minimum_balance);
+        let simd_time_seconds = measure_execution_time(&mut timer, iterations, || {
+            simd_checksum = sum_simd(&users_view, minimum_balance);
+        });
+
+        results.push(BenchResult::new(
+            scenario,
+            &format!("DoD (portable SIMD, {lanes} lanes)"),
+            elements_count,
+            iterations,
+            simd_checksum,
+            simd_time_seconds,
+        ));
+
+        let simd = &results[results.len() - 1];
+        comparisons.push(format!(
+            "Portable SIMD x{lanes:<2} Speedup  : {:.2}x",
+            simd.elements_per_second / results[0].elements_per_second
+        ));
+    }
+
+    if options.simd_auto && select("dod_simd_auto") {
+        let path = SimdPath::detect();
+
+        if verbose {
+            println!();
+            println!("Benchmarking {} SIMD scan...", path.name());
+        }
+
+        let mut auto_checksum =
+            simd_dispatch::sum_active_balances_auto(&users_view, minimum_balance);
+        let auto_time_seconds = measure_execution_time(&mut timer, iterations, || {
+            auto_checksum = simd_dispatch::sum_active_balances_auto(&users_view, minimum_balance);
+        });
+
+        results.push(BenchResult::new(
+            "dod_simd_auto",
+            &format!("DoD ({} SIMD)", path.name()),
+            elements_count,
+            iterations,
+            auto_checksum,
+            auto_time_seconds,
+        ));
+
+        let auto = &results[results.len() - 1];
+        comparisons.push(format!(
+            "Auto SIMD Speedup          : {:.2}x",
+            auto.elements_per_second / results[0].elements_per_second
+        ));
+        let auto_integer_checksum =
+            simd_dispatch::integer_checksum_auto(&users_view, minimum_balance);
+        comparisons.push(format!(
+            "Auto SIMD Integer Checksum : {} ({})",
+            auto_integer_checksum,
+            if auto_integer_checksum == integer_checksum(&users_view, minimum_balance) {
+                "matches scalar"
+            } else {
+                "MISMATCH"
+            }
+        ));
+    }
+
+    if options.prefetch && select("dod_prefetch") {
+        if !prefetch::SUPPORTED {
+            eprintln!("warning: --prefetch hints are x86_64-only; measuring the plain scan");
+        }
+
+        if verbose {
+            println!();
+            println!("Benchmarking prefetch-hinted scan...");
+        }
+
+        let mut prefetch_checksum =
+            prefetch::sum_active_balances_prefetch(&users_view, minimum_balance);
+        let prefetch_time_seconds = measure_execution_time(&mut timer, iterations, || {
+            prefetch_checksum =
+                prefetch::sum_active_balances_prefetch(&users_view, minimum_balance);
+        });
+
+        results.push(BenchResult::new(
+            "dod_prefetch",
+            "DoD (prefetch hints)",
+            elements_count,
+            iterations,
+            prefetch_checksum,
+            prefetch_time_seconds,
+        ));
+
+        let prefetched = &results[results.len() - 1];
+        comparisons.push(format!(
+            "Prefetch Distance          : {} elements",
+            prefetch::PREFETCH_DISTANCE_ELEMENTS
+        ));
+        comparisons.push(format!(
+            "Prefetch Speedup           : {:.2}x",
+            prefetched.elements_per_second / results[0].elements_per_second
+        ));
+    }
+
+    if options.active_bits && select("dod_bits") {
+        if verbose {
+            println!();
+            println!("Benchmarking bit-packed active scan...");
+        }
+
+        let active_bits = pack_active(&columns.active);
+        let bits_view = users_view.with_active_bits(&active_bits);
+        let mut bits_checksum = sum_active_balances_bits(&bits_view, minimum_balance);
+        let bits_time_seconds = measure_execution_time(&mut timer, iterations, || {
+            bits_checksum = sum_active_balances_bits(&bits_view, minimum_balance);
+        });
+
+        results.push(
+            BenchResult::new(
+                "dod_bits",
+                "DoD (active bitset)",
+                elements_count,
+                iterations,
+                bits_checksum,
+                bits_time_seconds,
+            )
+            // One balance and one bit of the packed flags.
+            .with_bytes_per_element(size_of::<f32>() as f64 + 1.0 / 8.0),
+        );
+
+        let packed = &results[results.len() - 1];
+        comparisons.push(format!(
+            "Active Bytes | Bitset      : {} B | {} B",
+            columns.active.len(),
+            active_bits.len() * size_of::<u64>()
+        ));
+        comparisons.push(format!(
+            "Bitset Checksum Match      : {}",
+            packed.checksum.to_bits() == results[0].checksum.to_bits()
+        ));
+        comparisons.push(format!(
+            "Bitset Speedup             : {:.2}x",
+            packed.elements_per_second / results[0].elements_per_second
+        ));
+    }
+
+    if let Some(repeats) = options.repeat_dataset
+        && select("dod_repeat")
+    {
+        if verbose {
+            println!();
+            println!("Benchmarking repeated dataset ({repeats}x)...");
+        }
+
+        let repeating_view = RepeatingUsersView::new(columns.view(), repeats);
+        let mut repeat_checksum = sum_active_balances_repeating(&repeating_view, minimum_balance);
+        let repeat_time_seconds = measure_execution_time(&mut timer, iterations, || {
+            repeat_checksum = sum_active_balances_repeating(&repeating_view, minimum_balance);
+        });
+
+        results.push(BenchResult::new(
+            "dod_repeat",
+            "DoD (repeated dataset)",
+            repeating_view.count,
+            iterations,
+            repeat_checksum,
+            repeat_time_seconds,
+        ));
+
+        let repeated = &results[results.len() - 1];
+        // Each pass rounds from a different running total, so the ratio is
+        // only exact for balances that add without rounding.
+        comparisons.push(format!(
+            "Repeat Checksum / Base     : {:.4} (expected {repeats})",
+            repeated.checksum / results[0].checksum
+        ));
+        comparisons.push(format!(
+            "Repeat Throughput vs Base  : {:.2}x",
+            repeated.elements_per_second / results[0].elements_per_second
+        ));
+    }
+
+    if options.filter_dsl {
+        if verbose {
+            println!();
+            println!("Benchmarking composed filter...");
+        }
+
+        // Same rule twice: directly, and via De Morgan to measure deeper nesting.
+        let filters = [
+            (
+                "dod_filter",
+                "DoD (composed filter)",
+                Filter::active().and(Filter::balance_gte(minimum_balance)),
+            ),
+            (
+                "dod_filter_nested",
+                "DoD (nested filter)",
+                !(!Filter::active()).or(Filter::balance_lt(minimum_balance)),
+            ),
+        ];
+
+        for (scenario, label, composed) in &filters {
+            if !select(scenario) {
+                continue;
+            }
+
+            let mut filter_checksum = 0.0f32;
+            let filter_time_seconds = measure_execution_time(&mut timer, iterations, || {
+                filter_checksum = filter::sum_where(&users_view, composed);
+            });
+
+            results.push(BenchResult::new(
+                scenario,
+                label,
+                elements_count,
+                iterations,
+                filter_checksum,
+                filter_time_seconds,
+            ));
+
+            let filtered = &results[results.len() - 1];
+            comparisons.push(format!(
+                "{:<26} : {:.1} % of DoD throughput",
+                filtered.label,
+                filtered.elements_per_second / results[0].elements_per_second * 100.0
+            ));
+        }
+    }
+
+    // --precision compares the accumulators' checksums, so it runs them too.
+    if options.accumulators || options.precision {
+        if verbose {
+            println!();
+            println!("Benchmarking accumulators...");
+        }
+
+        let accumulators: [(&str, &str, MaskedSum); 3] = [
+            (
+                "dod_acc_naive",
+                "DoD (naive accumulator)",
+                accumulate::sum_active_balances_with::<NaiveAcc>,
+            ),
+            (
+                "dod_acc_kahan",
+                "DoD (Kahan accumulator)",
+                accumulate::sum_active_balances_with::<KahanAcc>,
+            ),
+            (
+                "dod_acc_f64",
+                "DoD (f64 accumulator)",
+                accumulate::sum_active_balances_with::<F64Acc>,
+            ),
+        ];
+
+        for (scenario, label, sum) in accumulators {
+            if !select(scenario) {
+                continue;
+            }
+
+            let mut accumulator_checksum = 0.0f32;
+            let accumulator_time_seconds = measure_execution_time(&mut timer, iterations, || {
+                accumulator_checksum = sum(&users_view, minimum_balance);
+            });
+
+            results.push(BenchResult::new(
+                scenario,
+                label,
+                elements_count,
+                iterations,
+                accumulator_checksum,
+                accumulator_time_seconds,
+            ));
+
+            let accumulated = &results[results.len() - 1];
+            comparisons.push(format!(
+                "{:<26} : {:.1} % of DoD throughput, checksum delta {:+.4}",
+                accumulated.label,
+                accumulated.elements_per_second / results[0].elements_per_second * 100.0,
+                accumulated.checksum - results[0].checksum
+            ));
+        }
+
+        if options.precision {
+            let checksums: Vec<(&str, f32)> = results
+                .iter()
+                .filter_map(|result| {
+                    let mode = result.scenario.strip_prefix("dod_acc_")?;
+                    Some((mode, result.checksum))
+                })
+                .collect();
+            let differences = accumulate::pairwise_differences(&checksums);
+            let largest = differences
+                .iter()
+                .map(|&(_, _, difference)| difference)
+                .fold(0.0, f64::max);
+
+            for (left, right, difference) in differences {
+                let marker = if largest > 0.0 && difference == largest {
+                    "  <- largest discrepancy"
+                } else {
+                    ""
+                };
+                comparisons.push(format!(
+                    "{:<26} : {:.4}{}",
+                    format!("|{left} - {right}|"),
+                    difference,
+                    marker
+                ));
+            }
+        }
+    }
+
+    if options.decay {
+        const DECAY_FACTOR: f32 = 0.5;
+
+        if verbose {
+            println!();
+            println!("Benchmarking decay + retain...");
+        }
+
+        let rows = decay::to_rows(&users_view);
+        let mut survivors = Vec::new();
+
+        if select("dod_decay_aos") {
+            let mut aos_time_seconds = 0.0;
+            let mut aos_checksum = 0.0f32;
+            for _ in 0..iterations {
+                // Each pass starts from the undecayed data; the copy is not timed.
+                let mut users = rows.clone();
+                timer.start();
+                decay::decay_and_retain_aos(&mut users, DECAY_FACTOR, Money(minimum_balance));
+                aos_time_seconds += timer.stop().seconds;
+                aos_checksum = users.iter().map(|user| user.balance).sum::<Money>().raw();
+                survivors.push(("AoS", users.len()));
+            }
+
+            results.push(
+                BenchResult::new(
+                    "dod_decay_aos",
+                    "AoS (decay + retain)",
+                    elements_count,
+                    iterations,
+                    aos_checksum,
+                    aos_time_seconds,
+                )
+                .with_bytes_per_element(decay::AOS_BYTES_PER_ELEMENT),
+            );
+        }
+
+        if select("dod_decay_soa") {
+            let mut soa_time_seconds = 0.0;
+            let mut soa_checksum = 0.0f32;
+            for _ in 0..iterations {
+                let mut decayed = columns.clone();
+                timer.start();
+                decay::decay_and_retain_soa(&mut decayed, DECAY_FACTOR, minimum_balance);
+                soa_time_seconds += timer.stop().seconds;
+                soa_checksum = decayed.balances.iter().sum();
+                survivors.push(("SoA", decayed.balances.len()));
+            }
+
+            results.push(
+                BenchResult::new(
+                    "dod_decay_soa",
+                    "SoA (decay + retain)",
+                    elements_count,
+                    iterations,
+                    soa_checksum,
+                    soa_time_seconds,
+                )
+                .with_bytes_per_element(decay::SOA_BYTES_PER_ELEMENT),
+            );
+        }
+
+        survivors.dedup();
+        for (layout, count) in survivors {
+            comparisons.push(format!(
+                "{:<26} : {} of {}",
+                format!("{layout} Decay Survivors"),
+                count,
+                elements_count
+            ));
+        }
+
+        let find = |scenario| results.iter().find(|result| result.scenario == scenario);
+        if let (Some(aos), Some(soa)) = (find("dod_decay_aos"), find("dod_decay_soa")) {
+            comparisons.push(format!(
+                "SoA / AoS Decay Speedup    : {:.2}x",
+                soa.elements_per_second / aos.elements_per_second
+            ));
+        }
+    }
+
+    if options.tlb {
+        if verbose {
+            println!();
+            println!("Benchmarking page-strided access...");
+        }
+
+        let column = tlb::scaled_column(&columns.balances, tlb::DEFAULT_COLUMN_BYTES);
+        let pages = column.len() * size_of::<f32>() / tlb::PAGE_BYTES;
+        let orders = [
+            (
+                "dod_tlb_sequential",
+                "DoD (sequential pages)",
+                tlb::sequential_page_order(pages),
+            ),
+            (
+                "dod_tlb_random",
+                "DoD (random pages)",
+                tlb::random_page_order(pages, &mut rng),
+            ),
+        ];
+
+        for (scenario, label, page_order) in &orders {
+            if !select(scenario) {
+                continue;
+            }
+
+            let mut page_checksum = tlb::sum_page_heads(&column, page_order);
+            let page_time_seconds = measure_execution_time(&mut timer, iterations, || {
+                page_checksum = tlb::sum_page_heads(&column, page_order);
+            });
+
+            results.push(
+                BenchResult::new(
+                    scenario,
+                    label,
+                    pages,
+                    iterations,
+                    page_checksum,
+                    page_time_seconds,
+                )
+                // One balance per page.
+                .with_bytes_per_element(size_of::<f32>() as f64),
+            );
+        }
+
+        let find = |scenario| results.iter().find(|result| result.scenario == scenario);
+        if let (Some(sequential), Some(random)) =
+            (find("dod_tlb_sequential"), find("dod_tlb_random"))
+        {
+            comparisons.push(format!(
+                "Random Page Slowdown       : {:.2}x",
+                random.nanoseconds_per_element / sequential.nanoseconds_per_element
+            ));
+        }
+    }
+
+    if options.summation_order {
+        if verbose {
+            println!();
+            println!("Benchmarking summation orders...");
+        }
+
+        let column = tlb::scaled_column(&columns.balances, summation_order::DEFAULT_COLUMN_BYTES);
+        let orders: [(&str, &str, summation_order::ColumnSum); 3] = [
+            (
+                "dod_order_forward",
+                "DoD (forward sum)",
+                summation_order::sum_forward,
+            ),
+            (
+                "dod_order_reverse",
+                "DoD (reverse sum)",
+                summation_order::sum_reverse,
+            ),
+            ("dod_order_strided", "DoD (strided sum)", |balances| {
+                summation_order::sum_strided(balances, summation_order::STRIDE)
+            }),
+        ];
+
+        for (scenario, label, sum) in orders {
+            if !select(scenario) {
+                continue;
+            }
+
+            let mut order_checksum = sum(&column);
+            let order_time_seconds = measure_execution_time(&mut timer, iterations, || {
+                order_checksum = sum(std::hint::black_box(&column));
+            });
+
+            results.push(
+                BenchResult::new(
+                    scenario,
+                    label,
+                    column.len(),
+                    iterations,
+                    order_checksum,
+                    order_time_seconds,
+                )
+                .with_bytes_per_element(size_of::<f32>() as f64),
+            );
+        }
+
+        let find = |scenario| results.iter().find(|result| result.scenario == scenario);
+        if let Some(forward) = find("dod_order_forward") {
+            for (name, scenario) in [
+                ("Reverse", "dod_order_reverse"),
+                ("Strided", "dod_order_strided"),
+            ] {
+                if let Some(other) = find(scenario) {
+                    comparisons.push(format!(
+                        "{:<26} : {:.2}x",
+                        format!("{name} Sum Speedup"),
+                        other.elements_per_second / forward.elements_per_second
+                    ));
+                    comparisons.push(format!(
+                        "{:<26} : {:+.2}",
+                        format!("{name} Checksum Delta"),
+                        other.checksum - forward.checksum
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(budget_seconds) = options.time_budget
+        && select("dod_time_budget")
+    {
+        if verbose {
+            println!();
+            println!("Benchmarking for {budget_seconds} s...");
+        }
+
+        let mut budget_checksum = 0.0f32;
+        let (budget_iterations, budget_time_seconds) =
+            measure_for_duration(Duration::from_secs_f64(budget_seconds), || {
+                budget_checksum = sum_active_balances(&users_view, minimum_balance);
+            });
+
+        results.push(BenchResult::new(
+            "dod_time_budget",
+            "DoD (time budget)",
+            elements_count,
+            budget_iterations,
+            budget_checksum,
+            budget_time_seconds,
+        ));
+        comparisons.push(format!(
+            "Time Budget Iterations     : {} in {:.2} s",
+            budget_iterations, budget_time_seconds
+        ));
+    }
+
+    if let Some(path) = &options.plot_data {
+        save_plot_data(
+            path,
+            working_set_points
+                .iter()
+                .map(|&(mebibytes, index)| PlotPoint {
+                    sweep_variable: "working_set_mib",
+                    value: mebibytes,
+                    result: &results[index],
+                }),
+        );
+    }
+
+    if let Some(path) = &options.csv_out {
+        let records: Vec<CsvRecord> = results
+            .iter()
+            .map(|result| CsvRecord {
+                implementation: &result.scenario,
+                elements: result.elements,
+                seed: random_seed,
+                iterations: result.iterations,
+                total_time_seconds: result.total_time_seconds,
+                checksum: result.checksum,
+            })
+            .collect();
+        if let Err(message) = csv::append(path, &records) {
+            eprintln!("error: {message}");
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(path) = &options.json_out {
+        for result in &results {
+            // Scenarios timed without per-iteration samples only report their
+            // mean, so their spread reads as zero.
+            let stats = result
+                .stats
+                .unwrap_or_else(|| Stats::from_samples(&[result.average_time_seconds]));
+            if let Err(message) = json::write_results(
+                path,
+                &result.scenario,
+                &stats,
+                result.elements,
+                result.checksum as f64,
+            ) {
+                eprintln!("error: {message}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if !skipped.is_empty() {
+        let message = format!("Skipped Scenarios          : {}", skipped.join(", "));
+        if verbose {
+            comparisons.push(message);
+        } else {
+            eprintln!("{}", message);
+        }
+    }
+
+    if json_to_stdout {
+        return;
+    }
+
+    if options.output_format == OutputFormat::Prometheus {
+        report::write_prometheus(&mut std::io::stdout().lock(), &results)
+            .expect("failed to write metrics to stdout");
+        return;
+    }
+
+    if options.output_format == OutputFormat::Markdown {
+        print!("{}", report::results_markdown(&results));
+        return;
+    }
+
+    if options.output_format == OutputFormat::CsvRow {
+        report::write_csv_row(
+            &mut std::io::stdout().lock(),
+            &results,
+            random_seed,
+            options.csv_header,
+        )
+        .expect("failed to write the CSV row to stdout");
+        return;
+    }
+
+    for result in &results {
+        report::print_text(result);
+    }
+
+    for comparison in &comparisons {
+        println!("{}", comparison);
+    }
+    if !comparisons.is_empty() {
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bench_core::User;
+
+    #[test]
+    fn zero_warmup_still_reports_the_checksum() {
+        let columns = generate_columns(1000, 0.6, &mut Pcg32::seed_from_u64(17));
+        let users_view = columns.view();
+        let expected = sum_active_balances(&users_view, 250.0);
+
+        assert_eq!(warm_up(&users_view, 250.0, 0, false), (expected, 0));
+        assert_eq!(warm_up(&users_view, 250.0, 3, false), (expected, 3));
+        assert_ne!(expected, 0.0);
+    }
+
+    #[test]
+    fn saved_dataset_reproduces_the_generated_columns() {
+        let columns = generate_columns(1000, 0.6, &mut Pcg32::seed_from_u64(17));
+        let users_view = columns.view();
+        let users: Vec<User> = (0..users_view.count).map(|i| users_view.row(i)).collect();
+        let path = std::env::temp_dir().join(format!("dod-p-dataset-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        dataset::save_users_json(&users, path).unwrap();
+        let loaded = dataset::load_users_json(path);
+        std::fs::remove_file(path).unwrap();
+        let reloaded = UsersColumns::from_users(&loaded.unwrap());
+
+        assert_eq!(reloaded.ids, columns.ids);
+        assert_eq!(reloaded.balances, columns.balances);
+        assert_eq!(reloaded.active, columns.active);
+    }
+
+    #[test]
+    fn f64_sum_keeps_the_bits_the_f32_sum_drops() {
+        // Past 2^24 an f32 running sum can no longer add 1.0.
+        let mut columns = UsersColumns::from_triples(&[(0, 16_777_216.0, true)]);
+        for id in 1..=100 {
+            columns.push(id, 1.0, true, 0.0);
+        }
+        let users_view = columns.view();
+
+        assert_eq!(sum_active_balances(&users_view, 0.0), 16_777_216.0);
+        assert_eq!(sum_active_balances_f64(&users_view, 0.0), 16_777_316.0);
+        assert_eq!(sum_active_balances_f64(&users_view, 2.0), 16_777_216.0);
+    }
+
+    #[test]
+    fn parallel_sum_matches_serial_within_rounding() {
+        let columns = generate_columns(300_000, 0.6, &mut Pcg32::seed_from_u64(17));
+        let users_view = columns.view();
+
+        let serial = sum_active_balances(&users_view, 250.0);
+        let parallel = sum_active_balances_parallel(&users_view, 250.0);
+        assert!((parallel - serial).abs() <= serial * 1e-5);
+        assert_eq!(
+            sum_active_balances_parallel(&UsersView::default(), 250.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn stable_parallel_sum_is_the_same_on_every_run() {
+        let columns = generate_columns(300_007, 0.6, &mut Pcg32::seed_from_u64(17));
+        let users_view = columns.view();
+
+        // The same grouping summed on one thread.
+        let chunk_elements = users_view.count.div_ceil(STABLE_PARALLEL_CHUNKS);
+        let expected: f32 = columns
+            .balances
+            .chunks(chunk_elements)
+            .zip(columns.active.chunks(chunk_elements))
+            .map(|(balances, active)| {
+                sum_active_balances(
+                    &UsersView {
+                        ids: &[],
+                        balances,
+                        active,
+                        scores: &[],
+                        count: balances.len(),
+                    },
+                    250.0,
+                )
+            })
+            .sum();
+
+        for _ in 0..100 {
+            assert_eq!(
+                sum_active_balances_stable_parallel(&users_view, 250.0, STABLE_PARALLEL_CHUNKS)
+                    .to_bits(),
+                expected.to_bits()
+            );
+        }
+        assert_eq!(
+            sum_active_balances_stable_parallel(&UsersView::default(), 250.0, 3),
+            0.0
+        );
+    }
+
+    #[test]
+    fn parameter_flags_override_defaults_and_are_validated() {
+        let parse = |args: &[&str]| parse_options(args.iter().map(|arg| arg.to_string()));
+        let error = |args: &[&str]| parse(args).err().unwrap();
+
+        let options = parse(&["--elements", "1000000", "--min-balance", "500"]).unwrap();
+        assert_eq!(options.benchmark.elements_count, 1_000_000);
+        assert_eq!(options.benchmark.minimum_balance, 500.0);
+        assert_eq!(
+            options.benchmark.random_seed,
+            BenchmarkConfig::default().random_seed
+        );
+        assert_eq!(parse(&["--seed", "42"]).unwrap().benchmark.random_seed, 42);
+        assert_eq!(
+            parse(&["--active-prob", "0.9"])
+                .unwrap()
+                .benchmark
+                .active_probability,
+            0.9
+        );
+
+        assert!(error(&["--elements", "0"]).contains("nonzero"));
+        assert!(error(&["--min-balance", "inf"]).contains("finite"));
+        assert!(error(&["--iterations", "ten"]).contains("'ten'"));
+        assert!(error(&["--seed"]).contains("requires a value"));
+        assert!(error(&["--histogram", "0"]).contains("bucket"));
+        assert!(error(&["--time-budget", "-1"]).contains("positive"));
+        assert!(error(&["--time-budget", "1e20"]).contains("too big"));
+        assert_eq!(parse(&["--profile", "2.5"]).unwrap().profile, Some(2.5));
+        assert!(error(&["--profile", "0"]).contains("positive"));
+        assert!(error(&["--profile", "1e20"]).contains("too big"));
+        assert!(error(&["--profile", "1", "--size-sweep"]).contains("cannot sweep"));
+        assert_eq!(
+            parse(&["--sweep", "1000,10000"]).unwrap().sweep,
+            Some(vec![1_000, 10_000])
+        );
+        assert!(error(&["--sweep", "10,0"]).contains("nonzero"));
+        assert!(error(&["--sweep", "10", "--size-sweep"]).contains("mutually exclusive"));
+        assert!(error(&["--dataset", "users.json", "--sweep", "10"]).contains("cannot sweep"));
+        assert!(
+            parse(&["--allow-negative", "--sweep", "10"])
+                .unwrap()
+                .allow_negative
+        );
+        assert!(
+            error(&["--dataset-csv", "users.csv", "--allow-negative"])
+                .contains("cannot --allow-negative")
+        );
+        assert!(error(&["--active-prob", "1.5"]).contains("between 0 and 1"));
+        assert_eq!(
+            parse(&["--json-out", "-"]).unwrap().json_out.as_deref(),
+            Some("-")
+        );
+        assert!(error(&["--json-out"]).contains("requires a file or '-'"));
+        assert!(
+            error(&["--json-out", "-", "--format", "csv-row"]).contains("both write to stdout")
+        );
+        assert!(parse(&["--json-out", "out.jsonl", "--format", "csv-row"]).is_ok());
+        assert_eq!(parse(&["--pin-core", "3"]).unwrap().pin_core, Some(3));
+        assert_eq!(
+            parse(&["--repeat-dataset", "8"]).unwrap().repeat_dataset,
+            Some(8)
+        );
+        assert!(error(&["--repeat-dataset", "0"]).contains("nonzero"));
+        assert!(error(&["--repeat-dataset", &usize::MAX.to_string()]).contains("overflows usize"));
+        assert_eq!(
+            parse(&["--contention", "--contention-bytes", "4096"])
+                .unwrap()
+                .contention_bytes,
+            Some(4096)
+        );
+        assert!(error(&["--contention-bytes", "4096"]).contains("--contention companion"));
+        assert!(error(&["--contention", "--contention-bytes", "0"]).contains("nonzero"));
+        assert!(error(&["--pin-core", "-1"]).contains("'-1'"));
+        assert!(
+            error(&["--dataset", "users.json", "--dataset-csv", "users.csv"])
+                .contains("mutually exclusive")
+        );
+    }
 }
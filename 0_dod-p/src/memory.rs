@@ -0,0 +1,110 @@
+//! `--memory`: heap bytes held by the same users in each layout.
+//!
+//! The SoA columns pack each field tightly, the `Vec<User>` rows pay for the
+//! padding after `active`, and a `HashMap<i32, User>` adds a key and a control
+//! byte per bucket on top of spare capacity.
+
+use std::mem::size_of;
+
+use bench_core::User;
+use dod_p::UsersColumns;
+
+/// Control bytes the standard `HashMap` (hashbrown) keeps past the last bucket
+/// so SIMD probes never read out of bounds.
+const HASH_MAP_GROUP_WIDTH: usize = 16;
+
+/// Bytes used by the columns' elements; spare `Vec` capacity is not counted.
+pub fn soa_bytes(columns: &UsersColumns) -> usize {
+    columns.ids.len() * size_of::<i32>()
+        + columns.balances.len() * size_of::<f32>()
+        + columns.active.len() * size_of::<u8>()
+        + columns.scores.len() * size_of::<f32>()
+}
+
+pub fn aos_bytes(count: usize) -> usize {
+    count * size_of::<User>()
+}
+
+/// Approximate heap bytes of a `HashMap<K, V>` built from `count` inserts: the
+/// table keeps at most 7/8 of its power-of-two buckets full, and every bucket
+/// costs one `(K, V)` slot plus one control byte.
+pub fn hash_map_bytes<K, V>(count: usize) -> usize {
+    if count == 0 {
+        return 0;
+    }
+    let buckets = if count < 4 {
+        4
+    } else if count < 8 {
+        8
+    } else {
+        (count * 8 / 7).next_power_of_two()
+    };
+    buckets * (size_of::<(K, V)>() + 1) + HASH_MAP_GROUP_WIDTH
+}
+
+pub fn report_memory(label: &str, bytes: usize, elements: usize) {
+    println!(
+        "{:<18} : {} bytes ({:.2} B/element)",
+        label,
+        bytes,
+        bytes as f64 / elements as f64
+    );
+}
+
+pub fn print_memory_footprint(columns: &UsersColumns) {
+    let count = columns.ids.len();
+
+    println!();
+    println!("[ Memory Footprint ]");
+    report_memory("SoA (columns)", soa_bytes(columns), count);
+    report_memory("AoS (Vec<User>)", aos_bytes(count), count);
+    report_memory(
+        "HashMap<i32, User>",
+        hash_map_bytes::<i32, User>(count),
+        count,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use bench_core::Money;
+
+    #[test]
+    fn aos_pays_for_padding_the_columns_avoid() {
+        let columns = UsersColumns::from_triples(&[(0, 1.0, true), (1, 2.0, false)]);
+
+        assert_eq!(soa_bytes(&columns), 2 * 13);
+        assert_eq!(aos_bytes(2), 2 * 16);
+    }
+
+    #[test]
+    fn hash_map_estimate_follows_the_real_capacity() {
+        for count in [1, 3, 4, 7, 8, 100, 10_000] {
+            let map: HashMap<i32, User> = (0..count as i32)
+                .map(|id| {
+                    let user = User {
+                        id,
+                        balance: Money::ZERO,
+                        active: false,
+                        score: 0.0,
+                    };
+                    (id, user)
+                })
+                .collect();
+            let buckets = (hash_map_bytes::<i32, User>(count) - HASH_MAP_GROUP_WIDTH)
+                / (size_of::<(i32, User)>() + 1);
+
+            assert_eq!(
+                map.capacity(),
+                if buckets < 8 {
+                    buckets - 1
+                } else {
+                    buckets / 8 * 7
+                }
+            );
+        }
+    }
+}
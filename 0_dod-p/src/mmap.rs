@@ -0,0 +1,224 @@
+//! Memory-mapped SoA datasets, for inputs too large to read into columns.
+//!
+//! The file holds the three columns back to back behind a fixed header, so
+//! `mmap_soa` only maps it and `MmapUsersView::view` borrows the slices
+//! straight from the mapping: pages are faulted in as the scan reaches them
+//! and nothing is copied. All integers are little-endian.
+//!
+//! | Offset | Size | Field                                     |
+//! |--------|------|-------------------------------------------|
+//! | 0      | 8    | magic, `MAGIC`                            |
+//! | 8      | 8    | `count`, rows per column (`u64`)          |
+//! | 16     | 8    | offset of the ids (`count` x `i32`)       |
+//! | 24     | 8    | offset of the balances (`count` x `f32`)  |
+//! | 32     | 8    | offset of the active flags (`count` x u8) |
+//!
+//! Offsets count bytes from the start of the file. The ids and balances must
+//! start on a multiple of 4 so they can be borrowed in place; `write_soa`
+//! places them right after the header, then the flags, with no gaps.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use memmap2::Mmap;
+
+use crate::UsersView;
+
+pub const MAGIC: [u8; 8] = *b"DODSOA01";
+
+pub const HEADER_BYTES: usize = 40;
+
+/// A mapped dataset file; `view` hands out its columns.
+pub struct MmapUsersView {
+    map: Mmap,
+    count: usize,
+    ids_offset: usize,
+    balances_offset: usize,
+    active_offset: usize,
+}
+
+impl MmapUsersView {
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The mapped columns. The format does not store scores, so `scores` is
+    /// empty and rows read as score 0.0.
+    pub fn view(&self) -> UsersView<'_> {
+        UsersView {
+            ids: cast(&self.map[self.ids_offset..][..self.count * 4]),
+            balances: cast(&self.map[self.balances_offset..][..self.count * 4]),
+            active: &self.map[self.active_offset..][..self.count],
+            scores: &[],
+            count: self.count,
+        }
+    }
+}
+
+/// Reinterprets checked, 4-byte aligned bytes as `i32`s or `f32`s.
+fn cast<T: Copy>(bytes: &[u8]) -> &[T] {
+    // SAFETY: `mmap_soa` only builds views for little-endian targets, over
+    // ranges it checked are in bounds and 4-byte aligned, and every bit
+    // pattern is a valid `i32` or `f32`.
+    let (prefix, values, suffix) = unsafe { bytes.align_to::<T>() };
+    debug_assert!(prefix.is_empty() && suffix.is_empty());
+    values
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Maps `path` and checks its header against the file length.
+pub fn mmap_soa(path: &str) -> io::Result<MmapUsersView> {
+    if cfg!(target_endian = "big") {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "mapped columns are little-endian and cannot be borrowed on this target",
+        ));
+    }
+
+    let file = File::open(path)?;
+    // SAFETY: the mapping is read-only. Truncating or rewriting the file while
+    // it is mapped is undefined behaviour, which callers rule out by not
+    // modifying dataset files during a run.
+    let map = unsafe { Mmap::map(&file)? };
+
+    if map.len() < HEADER_BYTES || map[..8] != MAGIC {
+        return Err(invalid(format!("'{path}' is not a mapped SoA dataset")));
+    }
+    let field = |index: usize| -> io::Result<usize> {
+        let start = 8 + index * 8;
+        let value = u64::from_le_bytes(map[start..start + 8].try_into().unwrap());
+        usize::try_from(value).map_err(|_| invalid(format!("header field {value} is too large")))
+    };
+    let count = field(0)?;
+    let [ids_offset, balances_offset, active_offset] = [field(1)?, field(2)?, field(3)?];
+
+    for (name, offset, width) in [
+        ("ids", ids_offset, 4),
+        ("balances", balances_offset, 4),
+        ("active", active_offset, 1),
+    ] {
+        let end = count
+            .checked_mul(width)
+            .and_then(|bytes| bytes.checked_add(offset));
+        if end.is_none_or(|end| end > map.len()) {
+            return Err(invalid(format!(
+                "{name} column of {count} rows at offset {offset} runs past the end of '{path}'"
+            )));
+        }
+        if !(map.as_ptr() as usize + offset).is_multiple_of(width) {
+            return Err(invalid(format!(
+                "{name} column at offset {offset} is not {width}-byte aligned"
+            )));
+        }
+    }
+
+    Ok(MmapUsersView {
+        map,
+        count,
+        ids_offset,
+        balances_offset,
+        active_offset,
+    })
+}
+
+/// Writes `users_view`'s ids, balances and active flags in the format
+/// `mmap_soa` reads.
+pub fn write_soa(users_view: &UsersView, path: &str) -> io::Result<()> {
+    let count = users_view.count;
+    let ids_offset = HEADER_BYTES;
+    let balances_offset = ids_offset + count * 4;
+    let active_offset = balances_offset + count * 4;
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&MAGIC)?;
+    for value in [count, ids_offset, balances_offset, active_offset] {
+        writer.write_all(&(value as u64).to_le_bytes())?;
+    }
+    for id in &users_view.ids[..count] {
+        writer.write_all(&id.to_le_bytes())?;
+    }
+    for balance in &users_view.balances[..count] {
+        writer.write_all(&balance.to_le_bytes())?;
+    }
+    writer.write_all(&users_view.active[..count])?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pcg32, generate_columns, sum_active_balances};
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("dod-p-{}-{name}.soa", std::process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn written_columns_map_back_unchanged() {
+        let path = temp_path("round-trip");
+        let columns = generate_columns(1001, 0.6, &mut Pcg32::seed_from_u64(17));
+        write_soa(&columns.view(), &path).unwrap();
+
+        let mapped = mmap_soa(&path);
+        std::fs::remove_file(&path).unwrap();
+        let mapped = mapped.unwrap();
+        let users_view = mapped.view();
+
+        assert_eq!(mapped.count(), 1001);
+        assert_eq!(users_view.ids, columns.ids);
+        assert_eq!(users_view.balances, columns.balances);
+        assert_eq!(users_view.active, columns.active);
+        assert_eq!(
+            sum_active_balances(&users_view, 250.0).to_bits(),
+            sum_active_balances(&columns.view(), 250.0).to_bits()
+        );
+    }
+
+    #[test]
+    fn mapped_rows_and_prefixes_match_the_columns() {
+        let path = temp_path("rows");
+        let columns = generate_columns(100, 0.6, &mut Pcg32::seed_from_u64(17));
+        write_soa(&columns.view(), &path).unwrap();
+
+        let mapped = mmap_soa(&path);
+        std::fs::remove_file(&path).unwrap();
+        let mapped = mapped.unwrap();
+        let users_view = mapped.view();
+        let expected = columns.view().row(0);
+
+        let row = users_view.row(0);
+        assert_eq!(
+            (row.id, row.balance, row.active, row.score),
+            (expected.id, expected.balance, expected.active, 0.0)
+        );
+        assert_eq!(users_view.try_row(100), None);
+        let prefix = users_view.prefix(10);
+        assert_eq!(prefix.count, 10);
+        assert_eq!(prefix.row(9).id, 9);
+        assert_eq!(
+            sum_active_balances(&prefix, 250.0).to_bits(),
+            sum_active_balances(&columns.view().prefix(10), 250.0).to_bits()
+        );
+    }
+
+    #[test]
+    fn truncated_or_foreign_files_are_rejected() {
+        let path = temp_path("truncated");
+        let columns = generate_columns(10, 0.6, &mut Pcg32::seed_from_u64(17));
+        write_soa(&columns.view(), &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        let truncated = mmap_soa(&path).err().unwrap();
+        std::fs::write(&path, b"id,balance,active\n").unwrap();
+        let foreign = mmap_soa(&path).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(truncated.to_string().contains("active column of 10 rows"));
+        assert!(foreign.to_string().contains("not a mapped SoA dataset"));
+    }
+}
@@ -0,0 +1,92 @@
+//! Rough theoretical peak for the masked sum.
+//!
+//! The model is deliberately simple: one vector of balances consumed per
+//! cycle, so the peak is `lanes * clock`. Both inputs are estimates — the lane
+//! count comes from runtime feature detection and the clock from timing a
+//! dependent add chain — so every figure derived here is approximate.
+
+pub struct SimdWidth {
+    pub name: &'static str,
+    pub f32_lanes: usize,
+}
+
+pub fn detect_simd_width() -> SimdWidth {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx512f") {
+            return SimdWidth {
+                name: "avx512f",
+                f32_lanes: 16,
+            };
+        }
+        if is_x86_feature_detected!("avx2") {
+            return SimdWidth {
+                name: "avx2",
+                f32_lanes: 8,
+            };
+        }
+        SimdWidth {
+            name: "sse2",
+            f32_lanes: 4,
+        }
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    {
+        SimdWidth {
+            name: "neon",
+            f32_lanes: 4,
+        }
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        SimdWidth {
+            name: "scalar",
+            f32_lanes: 1,
+        }
+    }
+}
+
+/// Estimates the core clock in Hz by timing a chain of dependent register-to-
+/// register adds, each of which takes one cycle on every mainstream x86_64
+/// core. Immediate adds are avoided because newer cores fold them at rename.
+#[cfg(target_arch = "x86_64")]
+pub fn estimate_clock_hz() -> Option<f64> {
+    const ROUNDS: u64 = 50_000_000;
+    const ADDS_PER_ROUND: u64 = 8;
+
+    let mut value = 0u64;
+    let start = std::time::Instant::now();
+    for _ in 0..ROUNDS {
+        // SAFETY: pure register arithmetic, no memory or flags are observed.
+        unsafe {
+            std::arch::asm!(
+                "add {v}, {one}",
+                "add {v}, {one}",
+                "add {v}, {one}",
+                "add {v}, {one}",
+                "add {v}, {one}",
+                "add {v}, {one}",
+                "add {v}, {one}",
+                "add {v}, {one}",
+                v = inout(reg) value,
+                one = in(reg) 1u64,
+                options(nomem, nostack),
+            );
+        }
+    }
+    let elapsed_seconds = start.elapsed().as_secs_f64();
+    std::hint::black_box(value);
+
+    Some((ROUNDS * ADDS_PER_ROUND) as f64 / elapsed_seconds)
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn estimate_clock_hz() -> Option<f64> {
+    None
+}
+
+pub fn peak_elements_per_second(width: &SimdWidth, clock_hz: f64) -> f64 {
+    width.f32_lanes as f64 * clock_hz
+}
@@ -0,0 +1,98 @@
+//! `--plot-data <file>`: sweep results as a tidy long-format table.
+//!
+//! Every row holds a single number, keyed by which variable was swept, its
+//! value, the scenario and the metric, so pandas can `pivot` it and gnuplot
+//! can filter it without any reshaping:
+//!
+//! ```gnuplot
+//! # ns per element against dataset size, one line per scenario
+//! set datafile separator ","
+//! set logscale x 2
+//! set xlabel "elements"
+//! set ylabel "ns per element"
+//! plot "< awk -F, '$1 == \"elements\" && $4 == \"ns_per_element\"' plot.csv" \
+//!      using 2:5 with linespoints title "dod"
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::report::BenchResult;
+
+pub const HEADER: &str = "sweep_variable,value,scenario,metric,measurement";
+
+/// One sweep measurement: `result` was taken with `sweep_variable = value`.
+pub struct PlotPoint<'a> {
+    pub sweep_variable: &'static str,
+    pub value: usize,
+    pub result: &'a BenchResult,
+}
+
+type Metric = (&'static str, fn(&BenchResult) -> f64);
+
+const METRICS: [Metric; 3] = [
+    ("ns_per_element", |r| r.nanoseconds_per_element),
+    ("elements_per_second", |r| r.elements_per_second),
+    ("checksum", |r| r.checksum as f64),
+];
+
+pub fn write_plot_data<'a>(
+    w: &mut impl Write,
+    points: impl IntoIterator<Item = PlotPoint<'a>>,
+) -> io::Result<()> {
+    writeln!(w, "{HEADER}")?;
+
+    for point in points {
+        for (metric, value) in METRICS {
+            writeln!(
+                w,
+                "{},{},{},{},{}",
+                point.sweep_variable,
+                point.value,
+                point.result.scenario,
+                metric,
+                value(point.result)
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn save<'a>(path: &str, points: impl IntoIterator<Item = PlotPoint<'a>>) -> Result<(), String> {
+    let write = || -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        write_plot_data(&mut w, points)?;
+        w.flush()
+    };
+
+    write().map_err(|error| format!("cannot write plot data to '{path}': {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_row_per_metric_in_long_format() {
+        let result = BenchResult::new("dod", "DoD", 1024, 4, 2.5, 0.000_008);
+
+        let mut out = Vec::new();
+        write_plot_data(
+            &mut out,
+            [PlotPoint {
+                sweep_variable: "elements",
+                value: 1024,
+                result: &result,
+            }],
+        )
+        .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines[0], HEADER);
+        assert_eq!(lines.len(), 1 + METRICS.len());
+        assert_eq!(lines[1], "elements,1024,dod,ns_per_element,1.953125");
+        assert_eq!(lines[3], "elements,1024,dod,checksum,2.5");
+    }
+}
@@ -0,0 +1,29 @@
+//! `--profile <seconds>`: the baseline scan and nothing else, for a sampler.
+//!
+//! Warmup, per-sample timing and the other scenarios each leave frames of
+//! their own in a profile. This mode calls `sum_active_balances` back to back
+//! until the budget runs out and prints only once it is done, so `perf record`
+//! or `cargo flamegraph --bin dod-p -- --profile 10` sees the scan almost
+//! exclusively. `sum_active_balances` keeps its `#[inline(never)]`, so it
+//! stays a frame of its own.
+//!
+//! Only the loop around the scan changes: the scan and its checksum are
+//! exactly those of the `dod` scenario.
+
+use std::hint::black_box;
+use std::time::Duration;
+
+use bench_core::measure_for_duration;
+
+use crate::{UsersView, sum_active_balances};
+
+pub fn run(users_view: &UsersView, minimum_balance: f32, budget: Duration) {
+    let mut checksum = 0.0f32;
+    let (calls, seconds) = measure_for_duration(budget, || {
+        checksum = sum_active_balances(black_box(users_view), black_box(minimum_balance));
+    });
+
+    println!(
+        "Profiled sum_active_balances: {calls} calls in {seconds:.3} s, checksum {checksum:.8}"
+    );
+}
@@ -0,0 +1,315 @@
+use std::io::{self, Write};
+
+use bench_core::Stats;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Prometheus,
+    /// One CSV line per scenario: a plain run prints exactly one line, and
+    /// each extra scenario (`--tlb`, `--precision`, ...) adds its own row.
+    CsvRow,
+    Markdown,
+}
+
+impl OutputFormat {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "text" => Ok(Self::Text),
+            "prometheus" => Ok(Self::Prometheus),
+            "csv-row" => Ok(Self::CsvRow),
+            "markdown" => Ok(Self::Markdown),
+            other => Err(format!(
+                "unknown --format '{other}' (expected 'text', 'prometheus', 'csv-row' or 'markdown')"
+            )),
+        }
+    }
+}
+
+/// Bytes the baseline scan reads per user: one balance and one active flag.
+pub const SCANNED_BYTES_PER_ELEMENT: f64 = (size_of::<f32>() + size_of::<u8>()) as f64;
+
+/// Extracts one metric's value from a result.
+type MetricValue = fn(&BenchResult) -> f64;
+
+/// Everything a results block reports about one benchmarked scenario.
+pub struct BenchResult {
+    /// Machine-friendly identifier, used as the metrics label.
+    pub scenario: String,
+    /// Human-friendly title of the results block.
+    pub label: String,
+    pub elements: usize,
+    pub iterations: usize,
+    pub checksum: f32,
+    pub total_time_seconds: f64,
+    pub average_time_seconds: f64,
+    pub elements_per_second: f64,
+    pub nanoseconds_per_element: f64,
+    /// Memory traffic per element, read and written, behind the reported
+    /// bandwidth. `SCANNED_BYTES_PER_ELEMENT` unless the scenario sets its own.
+    pub bytes_per_element: f64,
+    /// Per-iteration summary, for scenarios timed one iteration at a time.
+    pub stats: Option<Stats>,
+}
+
+impl BenchResult {
+    /// Derives the per-iteration and per-element figures from a measurement.
+    pub fn new(
+        scenario: &str,
+        label: &str,
+        elements: usize,
+        iterations: usize,
+        checksum: f32,
+        total_time_seconds: f64,
+    ) -> Self {
+        let average_time_seconds = total_time_seconds / iterations as f64;
+
+        Self {
+            scenario: scenario.to_string(),
+            label: label.to_string(),
+            elements,
+            iterations,
+            checksum,
+            total_time_seconds,
+            average_time_seconds,
+            elements_per_second: elements as f64 / average_time_seconds,
+            nanoseconds_per_element: (average_time_seconds * 1e9) / elements as f64,
+            bytes_per_element: SCANNED_BYTES_PER_ELEMENT,
+            stats: None,
+        }
+    }
+
+    pub fn with_stats(mut self, stats: Stats) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    pub fn with_bytes_per_element(mut self, bytes_per_element: f64) -> Self {
+        self.bytes_per_element = bytes_per_element;
+        self
+    }
+
+    pub fn bytes_per_iteration(&self) -> usize {
+        (self.elements as f64 * self.bytes_per_element).round() as usize
+    }
+}
+
+/// Prints the human-readable results block.
+pub fn print_text(result: &BenchResult) {
+    bench_core::print_results(
+        &result.label,
+        result.checksum as f64,
+        result.total_time_seconds,
+        result.iterations,
+        result.elements,
+    );
+    bench_core::print_bandwidth(result.bytes_per_iteration(), result.average_time_seconds);
+    if let Some(stats) = &result.stats {
+        stats.print();
+    }
+    println!();
+}
+
+/// Writes the results in the Prometheus textfile exposition format, one gauge
+/// family per metric so the file can be dropped into node_exporter's
+/// textfile collector directory.
+pub fn write_prometheus(w: &mut impl Write, results: &[BenchResult]) -> io::Result<()> {
+    let metrics: [(&str, &str, MetricValue); 6] = [
+        (
+            "benchmark_ns_per_element",
+            "Average nanoseconds spent per element.",
+            |r| r.nanoseconds_per_element,
+        ),
+        (
+            "benchmark_elements_per_second",
+            "Elements processed per second.",
+            |r| r.elements_per_second,
+        ),
+        (
+            "benchmark_average_seconds",
+            "Average wall time of one iteration in seconds.",
+            |r| r.average_time_seconds,
+        ),
+        (
+            "benchmark_total_seconds",
+            "Wall time of all measured iterations in seconds.",
+            |r| r.total_time_seconds,
+        ),
+        (
+            "benchmark_elements",
+            "Number of elements in the dataset.",
+            |r| r.elements as f64,
+        ),
+        (
+            "benchmark_checksum",
+            "Checksum of the benchmarked computation.",
+            |r| r.checksum as f64,
+        ),
+    ];
+
+    for (name, help, value) in metrics {
+        writeln!(w, "# HELP {name} {help}")?;
+        writeln!(w, "# TYPE {name} gauge")?;
+        for result in results {
+            writeln!(
+                w,
+                "{name}{{scenario=\"{}\"}} {}",
+                escape_label_value(&result.scenario),
+                value(result)
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `gb_per_s` is decimal: 10^9 bytes per second.
+pub const CSV_ROW_HEADER: &str = "scenario,elements,seed,ns_per_element,gb_per_s";
+
+/// Writes one CSV line per result, optionally preceded by `CSV_ROW_HEADER`.
+/// A plain run has only `dod`, so it prints exactly one line; every extra
+/// scenario adds a row under the same header, so a pipeline reading a single
+/// line must not enable any.
+pub fn write_csv_row(
+    w: &mut impl Write,
+    results: &[BenchResult],
+    seed: u64,
+    header: bool,
+) -> io::Result<()> {
+    if header {
+        writeln!(w, "{CSV_ROW_HEADER}")?;
+    }
+
+    for result in results {
+        writeln!(
+            w,
+            "{},{},{},{:.4},{:.4}",
+            result.scenario,
+            result.elements,
+            seed,
+            result.nanoseconds_per_element,
+            result.bytes_per_iteration() as f64 / result.average_time_seconds / 1e9
+        )?;
+    }
+
+    Ok(())
+}
+
+/// The results as a GitHub-flavored Markdown table, one row per scenario, for
+/// pasting into issues and pull requests.
+pub fn results_markdown(results: &[BenchResult]) -> String {
+    let mut table = String::from(
+        "| Implementation | Elements | ns/element | M elem/s | Checksum |\n\
+         |:---|---:|---:|---:|---:|\n",
+    );
+
+    for result in results {
+        table.push_str(&format!(
+            "| {} | {} | {:.4} | {:.2} | {:.2} |\n",
+            result.label.replace('|', "\\|"),
+            result.elements,
+            result.nanoseconds_per_element,
+            result.elements_per_second / 1e6,
+            result.checksum
+        ));
+    }
+
+    table
+}
+
+/// Escapes a label value as required by the exposition format.
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_row_is_one_line_with_optional_header() {
+        let result = BenchResult::new("dod", "DoD", 1000, 4, 2.5, 0.000_008);
+
+        let mut out = Vec::new();
+        write_csv_row(&mut out, std::slice::from_ref(&result), 17, false).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "dod,1000,17,2.0000,2.5000\n"
+        );
+
+        // 4.125 B per element: the bitset scan's balance plus one bit.
+        let mut out = Vec::new();
+        let bits = BenchResult::new("dod_bits", "DoD", 1000, 4, 2.5, 0.000_008)
+            .with_bytes_per_element(4.125);
+        write_csv_row(&mut out, std::slice::from_ref(&bits), 17, false).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "dod_bits,1000,17,2.0000,2.0625\n"
+        );
+
+        // Extra scenarios add rows under the one header.
+        let mut out = Vec::new();
+        let dod = BenchResult::new("dod", "DoD", 1000, 4, 2.5, 0.000_008);
+        write_csv_row(&mut out, &[dod, bits], 17, true).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 3);
+
+        let mut out = Vec::new();
+        write_csv_row(&mut out, &[result], 17, true).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert_eq!(text.lines().next(), Some(CSV_ROW_HEADER));
+        assert_eq!(text.lines().count(), 2);
+    }
+
+    #[test]
+    fn markdown_table_has_a_header_and_a_row_per_result() {
+        let results = [
+            BenchResult::new("dod", "DoD", 1000, 4, 2.5, 0.000_008),
+            BenchResult::new("dod_bits", "DoD | bits", 1000, 4, 2.5, 0.000_004),
+        ];
+
+        assert_eq!(
+            results_markdown(&results),
+            "| Implementation | Elements | ns/element | M elem/s | Checksum |\n\
+             |:---|---:|---:|---:|---:|\n\
+             | DoD | 1000 | 2.0000 | 500.00 | 2.50 |\n\
+             | DoD \\| bits | 1000 | 1.0000 | 1000.00 | 2.50 |\n"
+        );
+    }
+
+    #[test]
+    fn prometheus_output_has_headers_and_escaped_labels() {
+        let result = BenchResult {
+            scenario: "dod \"soa\"\\\n".to_string(),
+            label: "DoD".to_string(),
+            elements: 10,
+            iterations: 8,
+            checksum: 1.5,
+            total_time_seconds: 0.8,
+            average_time_seconds: 0.1,
+            elements_per_second: 100.0,
+            nanoseconds_per_element: 0.42,
+            bytes_per_element: SCANNED_BYTES_PER_ELEMENT,
+            stats: None,
+        };
+
+        let mut out = Vec::new();
+        write_prometheus(&mut out, &[result]).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.starts_with(
+            "# HELP benchmark_ns_per_element Average nanoseconds spent per element.\n\
+             # TYPE benchmark_ns_per_element gauge\n\
+             benchmark_ns_per_element{scenario=\"dod \\\"soa\\\"\\\\\\n\"} 0.42\n"
+        ));
+        assert_eq!(text.matches("# TYPE").count(), 6);
+    }
+}
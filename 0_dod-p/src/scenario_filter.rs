@@ -0,0 +1,96 @@
+//! `--scenario-filter` pattern matching.
+//!
+//! A pattern without `*` matches any scenario name containing it; with `*` it
+//! is a glob anchored at both ends, where `*` matches any run of characters.
+
+#[derive(Debug)]
+pub struct ScenarioFilter {
+    pattern: String,
+}
+
+impl ScenarioFilter {
+    pub fn parse(pattern: &str) -> Result<Self, String> {
+        if pattern.is_empty() {
+            return Err("--scenario-filter pattern must not be empty".to_string());
+        }
+
+        if let Some(invalid) = pattern
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '*')))
+        {
+            return Err(format!(
+                "--scenario-filter pattern '{pattern}' contains '{invalid}'; \
+                 only letters, digits, '_', '-' and '*' are allowed"
+            ));
+        }
+
+        Ok(Self {
+            pattern: pattern.to_string(),
+        })
+    }
+
+    pub fn matches(&self, scenario: &str) -> bool {
+        if self.pattern.contains('*') {
+            glob_matches(self.pattern.as_bytes(), scenario.as_bytes())
+        } else {
+            scenario.contains(&self.pattern)
+        }
+    }
+}
+
+/// Iterative wildcard matching that backtracks only to the most recent `*`.
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut last_star: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            last_star = Some((p, t));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == text[t] {
+            p += 1;
+            t += 1;
+        } else if let Some((star_p, star_t)) = last_star {
+            p = star_p + 1;
+            t = star_t + 1;
+            last_star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_pattern_is_a_substring_match() {
+        let filter = ScenarioFilter::parse("tlb").unwrap();
+
+        assert!(filter.matches("dod_tlb_random"));
+        assert!(!filter.matches("dod_filter"));
+    }
+
+    #[test]
+    fn glob_pattern_is_anchored() {
+        let filter = ScenarioFilter::parse("dod_*_random").unwrap();
+
+        assert!(filter.matches("dod_tlb_random"));
+        assert!(!filter.matches("dod_tlb_random_x"));
+        assert!(
+            ScenarioFilter::parse("*filter*")
+                .unwrap()
+                .matches("dod_filter_nested")
+        );
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        assert!(ScenarioFilter::parse("").is_err());
+        assert!(ScenarioFilter::parse("dod|tlb").is_err());
+        assert!(ScenarioFilter::parse("[a-z]").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Portable SIMD scan.
+//!
+//! `std::simd` is nightly-only, so the vector kernel is behind the `simd`
+//! feature (`cargo +nightly run --features simd -- --simd`). Stable builds get
+//! the scalar `sum_active_balances` under the same name, so callers need no
+//! cfg of their own.
+//!
+//! The lane width is a const parameter, so `main` can time several widths from
+//! one build and show which suits the CPU. Current `std::simd` checks the
+//! width when the kernel is monomorphized, so no `LaneCount` bound is needed;
+//! widths up to 64 compile.
+
+use crate::UsersView;
+
+/// Whether `sum_active_balances_simd` is the vector kernel in this build.
+pub const SUPPORTED: bool = cfg!(feature = "simd");
+
+/// `sum_active_balances_simd` at one lane width, as a value.
+pub type SimdSum = fn(&UsersView, f32) -> f32;
+
+/// Loads `LANES` balances at a time, compares them against the splatted
+/// threshold, masks out inactive rows via their `active` bytes and adds the
+/// survivors lane-wise. The rows that do not fill a vector are summed by the
+/// scalar loop. Lane-wise partial sums change the f32 rounding, so the result
+/// only matches the scalar checksum within tolerance.
+#[cfg(feature = "simd")]
+#[inline(never)]
+pub fn sum_active_balances_simd<const LANES: usize>(
+    users_view: &UsersView,
+    minimum_balance: f32,
+) -> f32 {
+    use std::simd::prelude::*;
+
+    let balances = &users_view.balances[..users_view.count];
+    let active = &users_view.active[..users_view.count];
+
+    let threshold = Simd::<f32, LANES>::splat(minimum_balance);
+    let inactive = Simd::<u8, LANES>::splat(0);
+    let mut lanes = Simd::<f32, LANES>::splat(0.0);
+
+    let balance_chunks = balances.chunks_exact(LANES);
+    let active_chunks = active.chunks_exact(LANES);
+    let tail = UsersView {
+        ids: &[],
+        balances: balance_chunks.remainder(),
+        active: active_chunks.remainder(),
+        scores: &[],
+        count: balance_chunks.remainder().len(),
+    };
+
+    for (balance_chunk, active_chunk) in balance_chunks.zip(active_chunks) {
+        let balance = Simd::<f32, LANES>::from_slice(balance_chunk);
+        let is_active = Simd::<u8, LANES>::from_slice(active_chunk).simd_ne(inactive);
+        let take = balance.simd_ge(threshold) & is_active.cast::<i32>();
+        lanes += take.select(balance, Simd::splat(0.0));
+    }
+
+    lanes.reduce_sum() + crate::sum_active_balances(&tail, minimum_balance)
+}
+
+#[cfg(not(feature = "simd"))]
+pub fn sum_active_balances_simd<const LANES: usize>(
+    users_view: &UsersView,
+    minimum_balance: f32,
+) -> f32 {
+    crate::sum_active_balances(users_view, minimum_balance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::UsersColumns;
+
+    #[test]
+    fn every_width_matches_scalar_including_the_tail() {
+        // Whole balances add exactly in any order. The counts leave a tail at
+        // every width, or are too short to fill a single vector.
+        for count in [0, 3, 21, 37, 64] {
+            let rows: Vec<(i32, f32, bool)> = (0..count)
+                .map(|i| (i, i as f32 * 50.0, i % 3 != 0))
+                .collect();
+            let columns = UsersColumns::from_triples(&rows);
+            let users_view = columns.view();
+            let scalar = crate::sum_active_balances(&users_view, 250.0);
+
+            assert_eq!(sum_active_balances_simd::<4>(&users_view, 250.0), scalar);
+            assert_eq!(sum_active_balances_simd::<8>(&users_view, 250.0), scalar);
+            assert_eq!(sum_active_balances_simd::<16>(&users_view, 250.0), scalar);
+        }
+        assert_eq!(
+            sum_active_balances_simd::<8>(&UsersView::default(), 0.0),
+            0.0
+        );
+    }
+}
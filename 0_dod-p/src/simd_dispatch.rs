@@ -0,0 +1,292 @@
+//! `--simd-auto`: hand-written AVX2 and SSE kernels chosen at run time.
+//!
+//! `--simd` is compiled for one target and needs nightly. These kernels use
+//! the stable `std::arch` intrinsics and ask the CPU which ones it has, so a
+//! single binary runs the widest path on every machine it is copied to. SSE2
+//! is part of the x86_64 baseline, so only AVX2 needs the check; other
+//! architectures take the scalar loop.
+
+use crate::UsersView;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdPath {
+    Avx2,
+    Sse,
+    /// Only `detect`ed off x86_64.
+    #[cfg_attr(target_arch = "x86_64", allow(dead_code))]
+    Scalar,
+}
+
+impl SimdPath {
+    /// The widest path this CPU can run. `is_x86_feature_detected!` caches
+    /// its answer, so calling this per scan costs one load.
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                Self::Avx2
+            } else {
+                Self::Sse
+            }
+        }
+
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            Self::Scalar
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Avx2 => "avx2",
+            Self::Sse => "sse2",
+            Self::Scalar => "scalar",
+        }
+    }
+
+    pub fn is_available(self) -> bool {
+        match self {
+            #[cfg(target_arch = "x86_64")]
+            Self::Avx2 => is_x86_feature_detected!("avx2"),
+            Self::Sse => cfg!(target_arch = "x86_64"),
+            Self::Scalar => true,
+            #[cfg(not(target_arch = "x86_64"))]
+            Self::Avx2 => false,
+        }
+    }
+}
+
+/// `sum_active_balances` on the path `SimdPath::detect` picks. Lane-wise
+/// partial sums round differently from the serial loop, so the result only
+/// matches the scalar checksum within tolerance; `integer_checksum_auto`
+/// is the exact cross-check.
+#[inline(never)]
+pub fn sum_active_balances_auto(users_view: &UsersView, minimum_balance: f32) -> f32 {
+    sum_active_balances_on(SimdPath::detect(), users_view, minimum_balance)
+}
+
+/// `integer_checksum` through the same masking as `sum_active_balances_auto`.
+pub fn integer_checksum_auto(users_view: &UsersView, minimum_balance: f32) -> u64 {
+    integer_checksum_on(SimdPath::detect(), users_view, minimum_balance)
+}
+
+/// Panics if this CPU cannot run `path`.
+pub fn sum_active_balances_on(path: SimdPath, users_view: &UsersView, minimum_balance: f32) -> f32 {
+    assert!(path.is_available(), "{} is not available", path.name());
+
+    match path {
+        // SAFETY: `is_available` confirmed AVX2 above.
+        #[cfg(target_arch = "x86_64")]
+        SimdPath::Avx2 => unsafe { x86::sum_avx2(users_view, minimum_balance) },
+        // SAFETY: SSE2 is part of the x86_64 baseline.
+        #[cfg(target_arch = "x86_64")]
+        SimdPath::Sse => unsafe { x86::sum_sse(users_view, minimum_balance) },
+        _ => crate::sum_active_balances(users_view, minimum_balance),
+    }
+}
+
+/// Panics if this CPU cannot run `path`.
+pub fn integer_checksum_on(path: SimdPath, users_view: &UsersView, minimum_balance: f32) -> u64 {
+    assert!(path.is_available(), "{} is not available", path.name());
+
+    match path {
+        // SAFETY: `is_available` confirmed AVX2 above.
+        #[cfg(target_arch = "x86_64")]
+        SimdPath::Avx2 => unsafe { x86::integer_checksum_avx2(users_view, minimum_balance) },
+        // SAFETY: SSE2 is part of the x86_64 baseline.
+        #[cfg(target_arch = "x86_64")]
+        SimdPath::Sse => unsafe { x86::integer_checksum_sse(users_view, minimum_balance) },
+        _ => crate::integer_checksum(users_view, minimum_balance),
+    }
+}
+
+/// Each kernel masks a vector of balances and hands the masked lanes to its
+/// caller. A rejected lane becomes +0.0, whose bits are all zero, so it adds
+/// nothing to either the float sum or the integer checksum.
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    use crate::UsersView;
+
+    const AVX2_LANES: usize = 8;
+    const SSE_LANES: usize = 4;
+
+    /// The rows that do not fill a vector, for the scalar loops.
+    fn tail<'a>(balances: &'a [f32], active: &'a [u8]) -> UsersView<'a> {
+        UsersView {
+            ids: &[],
+            balances,
+            active,
+            scores: &[],
+            count: balances.len(),
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    fn masked_avx2(
+        balances: &[f32; AVX2_LANES],
+        active: &[u8; AVX2_LANES],
+        threshold: __m256,
+    ) -> __m256 {
+        // SAFETY: both arrays are exactly one vector long, and the unaligned
+        // loads have no alignment requirement.
+        let (balance, flags) = unsafe {
+            (
+                _mm256_loadu_ps(balances.as_ptr()),
+                _mm_loadl_epi64(active.as_ptr().cast()),
+            )
+        };
+        let is_active = _mm256_cmpgt_epi32(_mm256_cvtepu8_epi32(flags), _mm256_setzero_si256());
+        // Ordered compare: a NaN balance is rejected, as by the scalar `>=`.
+        let take = _mm256_and_ps(
+            _mm256_cmp_ps::<_CMP_GE_OQ>(balance, threshold),
+            _mm256_castsi256_ps(is_active),
+        );
+        _mm256_and_ps(balance, take)
+    }
+
+    #[target_feature(enable = "sse2")]
+    fn masked_sse(
+        balances: &[f32; SSE_LANES],
+        active: &[u8; SSE_LANES],
+        threshold: __m128,
+    ) -> __m128 {
+        // SAFETY: the array is exactly one vector long, and the unaligned load
+        // has no alignment requirement.
+        let balance = unsafe { _mm_loadu_ps(balances.as_ptr()) };
+        let zero = _mm_setzero_si128();
+        let flags = _mm_cvtsi32_si128(i32::from_le_bytes(*active));
+        let widened = _mm_unpacklo_epi16(_mm_unpacklo_epi8(flags, zero), zero);
+        let take = _mm_and_ps(
+            _mm_cmpge_ps(balance, threshold),
+            _mm_castsi128_ps(_mm_cmpgt_epi32(widened, zero)),
+        );
+        _mm_and_ps(balance, take)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub fn sum_avx2(users_view: &UsersView, minimum_balance: f32) -> f32 {
+        let (balances, balance_tail) = users_view.balances[..users_view.count].as_chunks();
+        let (active, active_tail) = users_view.active[..users_view.count].as_chunks();
+        let threshold = _mm256_set1_ps(minimum_balance);
+        let mut lanes = _mm256_setzero_ps();
+
+        for (balance_chunk, active_chunk) in balances.iter().zip(active) {
+            lanes = _mm256_add_ps(lanes, masked_avx2(balance_chunk, active_chunk, threshold));
+        }
+
+        // SAFETY: `__m256` and `[f32; 8]` have the same size and any bits are
+        // valid for both.
+        let lanes: [f32; AVX2_LANES] = unsafe { std::mem::transmute(lanes) };
+        lanes.iter().sum::<f32>()
+            + crate::sum_active_balances(&tail(balance_tail, active_tail), minimum_balance)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub fn integer_checksum_avx2(users_view: &UsersView, minimum_balance: f32) -> u64 {
+        let (balances, balance_tail) = users_view.balances[..users_view.count].as_chunks();
+        let (active, active_tail) = users_view.active[..users_view.count].as_chunks();
+        let threshold = _mm256_set1_ps(minimum_balance);
+        let mut checksum = 0u64;
+
+        for (balance_chunk, active_chunk) in balances.iter().zip(active) {
+            // SAFETY: as in `sum_avx2`, for `[u32; 8]`.
+            let bits: [u32; AVX2_LANES] =
+                unsafe { std::mem::transmute(masked_avx2(balance_chunk, active_chunk, threshold)) };
+            checksum = bits.iter().fold(checksum, |checksum, &bits| {
+                checksum.wrapping_add(bits.into())
+            });
+        }
+
+        checksum.wrapping_add(crate::integer_checksum(
+            &tail(balance_tail, active_tail),
+            minimum_balance,
+        ))
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub fn sum_sse(users_view: &UsersView, minimum_balance: f32) -> f32 {
+        let (balances, balance_tail) = users_view.balances[..users_view.count].as_chunks();
+        let (active, active_tail) = users_view.active[..users_view.count].as_chunks();
+        let threshold = _mm_set1_ps(minimum_balance);
+        let mut lanes = _mm_setzero_ps();
+
+        for (balance_chunk, active_chunk) in balances.iter().zip(active) {
+            lanes = _mm_add_ps(lanes, masked_sse(balance_chunk, active_chunk, threshold));
+        }
+
+        // SAFETY: `__m128` and `[f32; 4]` have the same size and any bits are
+        // valid for both.
+        let lanes: [f32; SSE_LANES] = unsafe { std::mem::transmute(lanes) };
+        lanes.iter().sum::<f32>()
+            + crate::sum_active_balances(&tail(balance_tail, active_tail), minimum_balance)
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub fn integer_checksum_sse(users_view: &UsersView, minimum_balance: f32) -> u64 {
+        let (balances, balance_tail) = users_view.balances[..users_view.count].as_chunks();
+        let (active, active_tail) = users_view.active[..users_view.count].as_chunks();
+        let threshold = _mm_set1_ps(minimum_balance);
+        let mut checksum = 0u64;
+
+        for (balance_chunk, active_chunk) in balances.iter().zip(active) {
+            // SAFETY: as in `sum_sse`, for `[u32; 4]`.
+            let bits: [u32; SSE_LANES] =
+                unsafe { std::mem::transmute(masked_sse(balance_chunk, active_chunk, threshold)) };
+            checksum = bits.iter().fold(checksum, |checksum, &bits| {
+                checksum.wrapping_add(bits.into())
+            });
+        }
+
+        checksum.wrapping_add(crate::integer_checksum(
+            &tail(balance_tail, active_tail),
+            minimum_balance,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pcg32, UsersColumns, generate_columns, integer_checksum};
+
+    const PATHS: [SimdPath; 3] = [SimdPath::Avx2, SimdPath::Sse, SimdPath::Scalar];
+
+    #[test]
+    fn every_available_path_selects_the_scalar_rows() {
+        for count in [0, 3, 8, 21, 1000, 10_007] {
+            let columns = generate_columns(count, 0.6, &mut Pcg32::seed_from_u64(17));
+            let users_view = columns.view();
+            let scalar = crate::sum_active_balances(&users_view, 250.0);
+
+            for path in PATHS.into_iter().filter(|path| path.is_available()) {
+                assert_eq!(
+                    integer_checksum_on(path, &users_view, 250.0),
+                    integer_checksum(&users_view, 250.0),
+                    "{} over {count} rows",
+                    path.name()
+                );
+                let sum = sum_active_balances_on(path, &users_view, 250.0);
+                assert!((sum - scalar).abs() <= scalar * 1e-5, "{}", path.name());
+            }
+        }
+    }
+
+    #[test]
+    fn exact_sums_match_bit_for_bit() {
+        // Whole balances well below 2^24 add exactly in any order.
+        let rows: Vec<(i32, f32, bool)> =
+            (0..21).map(|i| (i, (i * 50) as f32, i % 3 != 0)).collect();
+        let columns = UsersColumns::from_triples(&rows);
+        let users_view = columns.view();
+
+        for path in PATHS.into_iter().filter(|path| path.is_available()) {
+            assert_eq!(
+                sum_active_balances_on(path, &users_view, 250.0),
+                crate::sum_active_balances(&users_view, 250.0)
+            );
+        }
+        assert_eq!(sum_active_balances_auto(&UsersView::default(), 0.0), 0.0);
+    }
+}
@@ -0,0 +1,97 @@
+//! `--size-sweep`: the same scan over a geometric series of dataset sizes.
+//!
+//! Every size is a prefix of one large seed-17 dataset, so each row is exactly
+//! the dataset the generator would produce at that size. Iterations scale
+//! inversely with size to keep each row's measurement long enough to trust,
+//! and the output is plain CSV so it can be fed straight into a plotter.
+
+use std::io::{self, Write};
+
+use bench_core::measure_execution_time;
+use bench_core::timer::Timer;
+
+use crate::report::BenchResult;
+use crate::{UsersView, sum_active_balances};
+
+pub const MIN_ELEMENTS: usize = 1 << 10;
+
+/// 64 Mi elements, i.e. 320 MiB of balance and active columns.
+pub const MAX_ELEMENTS: usize = 1 << 26;
+
+/// Elements scanned per row, spread over as many iterations as that takes.
+const ELEMENTS_PER_ROW: usize = 1 << 26;
+
+/// `min, min * factor, ...` up to and including `max`.
+pub fn geometric_sizes(min: usize, max: usize, factor: usize) -> Vec<usize> {
+    std::iter::successors(Some(min), |&size| size.checked_mul(factor))
+        .take_while(|&size| size <= max)
+        .collect()
+}
+
+/// One measured size; `result.elements` is the swept variable.
+pub struct SweepRow {
+    pub iterations: usize,
+    pub result: BenchResult,
+}
+
+pub fn run<T: Timer>(timer: &mut T, users_view: &UsersView, minimum_balance: f32) -> Vec<SweepRow> {
+    geometric_sizes(MIN_ELEMENTS, MAX_ELEMENTS.min(users_view.count), 2)
+        .into_iter()
+        .map(|elements| {
+            let prefix = users_view.prefix(elements);
+            let iterations = (ELEMENTS_PER_ROW / elements).max(1);
+
+            // One untimed pass so every row starts from the same warm state.
+            let mut checksum = sum_active_balances(&prefix, minimum_balance);
+            let total_time_seconds = measure_execution_time(timer, iterations, || {
+                checksum = sum_active_balances(&prefix, minimum_balance);
+            });
+
+            SweepRow {
+                iterations,
+                result: BenchResult::new(
+                    "dod",
+                    "DoD",
+                    elements,
+                    iterations,
+                    checksum,
+                    total_time_seconds,
+                ),
+            }
+        })
+        .collect()
+}
+
+pub fn write_csv(out: &mut impl Write, rows: &[SweepRow]) -> io::Result<()> {
+    writeln!(
+        out,
+        "elements,bytes,iterations,ns_per_element,elements_per_second,checksum"
+    )?;
+
+    for row in rows {
+        writeln!(
+            out,
+            "{},{},{},{:.4},{:.0},{:.2}",
+            row.result.elements,
+            row.result.elements * (size_of::<f32>() + size_of::<u8>()),
+            row.iterations,
+            row.result.nanoseconds_per_element,
+            row.result.elements_per_second,
+            row.result.checksum
+        )?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geometric_sizes_include_both_ends() {
+        assert_eq!(geometric_sizes(1024, 8192, 2), [1024, 2048, 4096, 8192]);
+        assert_eq!(geometric_sizes(1000, 50_000, 10), [1000, 10_000]);
+        assert_eq!(geometric_sizes(MIN_ELEMENTS, MAX_ELEMENTS, 2).len(), 17);
+    }
+}
@@ -0,0 +1,74 @@
+//! Summation order experiment.
+//!
+//! Sums every balance of one column three ways: forward, reverse, and strided
+//! (every `STRIDE`-th element, then wrapping round to the next offset). Each
+//! order adds the same values, so the checksums differ only because `f32`
+//! addition is not associative, while the timings differ only by access
+//! pattern.
+//!
+//! Reverse iteration may defeat the hardware prefetcher: most streamers track
+//! ascending addresses best, and some older cores only detect forward streams.
+//! The strided order uses one balance per cache line it fetches: each pass over
+//! an offset touches a quarter of the column's lines, far more than the caches
+//! hold, so every line is fetched again by each of the 16 offsets it covers.
+
+/// 64 MiB of balances: large enough that no order is served from cache.
+pub const DEFAULT_COLUMN_BYTES: usize = 64 * 1024 * 1024;
+
+/// 64 balances apart, i.e. four 64-byte cache lines per step.
+pub const STRIDE: usize = 64;
+
+pub type ColumnSum = fn(&[f32]) -> f32;
+
+#[inline(never)]
+pub fn sum_forward(balances: &[f32]) -> f32 {
+    let mut accumulated_balance = 0.0f32;
+
+    for &balance in balances {
+        accumulated_balance += balance;
+    }
+
+    accumulated_balance
+}
+
+#[inline(never)]
+pub fn sum_reverse(balances: &[f32]) -> f32 {
+    let mut accumulated_balance = 0.0f32;
+
+    for &balance in balances.iter().rev() {
+        accumulated_balance += balance;
+    }
+
+    accumulated_balance
+}
+
+/// Visits offsets `0, stride, 2 * stride, ...`, then `1, 1 + stride, ...`, so
+/// every element is added exactly once.
+#[inline(never)]
+pub fn sum_strided(balances: &[f32], stride: usize) -> f32 {
+    let mut accumulated_balance = 0.0f32;
+
+    for offset in 0..stride.min(balances.len()) {
+        for &balance in balances[offset..].iter().step_by(stride) {
+            accumulated_balance += balance;
+        }
+    }
+
+    accumulated_balance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_order_adds_every_element() {
+        // Small integers are exact in f32, so all orders must agree.
+        let balances: Vec<f32> = (1..=1000).map(|value| value as f32).collect();
+
+        assert_eq!(sum_forward(&balances), 500_500.0);
+        assert_eq!(sum_reverse(&balances), 500_500.0);
+        assert_eq!(sum_strided(&balances, STRIDE), 500_500.0);
+        assert_eq!(sum_strided(&balances[..3], STRIDE), 6.0);
+    }
+}
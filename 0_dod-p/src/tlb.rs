@@ -0,0 +1,68 @@
+//! TLB pressure experiment.
+//!
+//! Reads exactly one balance per 4 KiB page of a column spanning far more pages
+//! than the TLB can map. Both orders touch the same pages and miss the data
+//! caches equally; only the random order also misses the TLB on almost every
+//! access, so the gap between them is the cost of page walks.
+
+use crate::rng::Pcg32;
+
+pub const PAGE_BYTES: usize = 4096;
+
+/// 256 MiB of balances, i.e. 65,536 pages.
+pub const DEFAULT_COLUMN_BYTES: usize = 256 * 1024 * 1024;
+
+const FLOATS_PER_PAGE: usize = PAGE_BYTES / size_of::<f32>();
+
+/// Repeats `balances` until the column spans `column_bytes`.
+pub fn scaled_column(balances: &[f32], column_bytes: usize) -> Vec<f32> {
+    balances
+        .iter()
+        .copied()
+        .cycle()
+        .take(column_bytes / size_of::<f32>())
+        .collect()
+}
+
+pub fn sequential_page_order(pages: usize) -> Vec<usize> {
+    (0..pages).collect()
+}
+
+/// Fisher-Yates shuffle of the page indices driven by the dataset RNG.
+pub fn random_page_order(pages: usize, rng: &mut Pcg32) -> Vec<usize> {
+    let mut order = sequential_page_order(pages);
+
+    for i in (1..pages).rev() {
+        let j = ((rng.next_u32() as u64 * (i as u64 + 1)) >> 32) as usize;
+        order.swap(i, j);
+    }
+
+    order
+}
+
+/// Sums the first balance of every page, visiting pages in `page_order`.
+#[inline(never)]
+pub fn sum_page_heads(column: &[f32], page_order: &[usize]) -> f32 {
+    let mut accumulated_balance = 0.0f32;
+
+    for &page in page_order {
+        accumulated_balance += column[page * FLOATS_PER_PAGE];
+    }
+
+    accumulated_balance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn random_order_is_a_permutation() {
+        let mut rng = Pcg32::seed_from_u64(17);
+        let mut order = random_page_order(1000, &mut rng);
+
+        assert_ne!(order, sequential_page_order(1000));
+        order.sort_unstable();
+        assert_eq!(order, sequential_page_order(1000));
+    }
+}
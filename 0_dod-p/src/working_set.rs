@@ -0,0 +1,56 @@
+//! Simulated working set.
+//!
+//! A real service touches plenty of other data between two scans, so the
+//! users are rarely still cached when the next scan starts. A background
+//! buffer is walked one cache line at a time between measured iterations,
+//! pushing the columns out the way that unrelated work would. Unlike the
+//! contention companion nothing runs concurrently with the timed scan.
+
+const CACHE_LINE_BYTES: usize = 64;
+
+pub struct BackgroundBuffer {
+    bytes: Vec<u8>,
+}
+
+impl BackgroundBuffer {
+    pub fn new(size_bytes: usize) -> Self {
+        Self {
+            bytes: vec![1; size_bytes],
+        }
+    }
+
+    /// Dirties one byte per cache line of the buffer.
+    pub fn touch(&mut self) {
+        for i in (0..self.bytes.len()).step_by(CACHE_LINE_BYTES) {
+            self.bytes[i] = self.bytes[i].wrapping_add(1);
+        }
+        std::hint::black_box(&self.bytes);
+    }
+}
+
+/// Parses `--working-set`'s comma-separated list of buffer sizes in MiB.
+pub fn parse_mebibytes(value: &str) -> Result<Vec<usize>, String> {
+    value
+        .split(',')
+        .map(|part| match part.trim().parse::<usize>() {
+            Ok(mebibytes) if mebibytes > 0 => Ok(mebibytes),
+            _ => Err(format!(
+                "--working-set expects positive MiB sizes separated by commas, got '{part}'"
+            )),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_mebibyte_lists() {
+        assert_eq!(parse_mebibytes("8"), Ok(vec![8]));
+        assert_eq!(parse_mebibytes("8, 32,128"), Ok(vec![8, 32, 128]));
+        assert!(parse_mebibytes("").is_err());
+        assert!(parse_mebibytes("8,0").is_err());
+        assert!(parse_mebibytes("8MiB").is_err());
+    }
+}
@@ -0,0 +1,20 @@
+//! `--json-out -` keeps stdout to the JSON Lines records alone.
+
+use std::process::Command;
+
+#[test]
+fn json_to_stdout_is_the_only_output() {
+    let output = Command::new(env!("CARGO_BIN_EXE_dod-p"))
+        .args(["--elements", "1000", "--iterations", "2", "--json-out", "-"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.starts_with("{\"label\":\"dod\","));
+    assert!(
+        stdout
+            .lines()
+            .all(|line| line.starts_with("{\"label\":") && line.ends_with('}'))
+    );
+}
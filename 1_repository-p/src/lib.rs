@@ -0,0 +1,1093 @@
+//! The repositories and their scans, as a library so the Criterion benches can
+//! call the same code the binary times.
+
+pub use bench_core::rng;
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+
+use bench_core::{Money, User};
+
+pub trait UserRepository {
+    /// Every user once, in the backend's own order.
+    fn iter(&self) -> Box<dyn Iterator<Item = &User> + '_>;
+    /// The users as one contiguous slice, for the positional scans that index
+    /// it directly. Backends that keep no slice, such as a `HashMap` or a
+    /// `BTreeMap`, leave the default `None`; code that only walks the users
+    /// should use `iter`.
+    fn as_slice(&self) -> Option<&[User]> {
+        None
+    }
+    fn find_by_id(&self, id: i32) -> Option<&User>;
+    /// `find_by_id` that reports duplicate ids instead of returning whichever
+    /// user comes first. Checks every user, so it is O(n) unless overridden.
+    fn find_unique_by_id(&self, id: i32) -> Result<&User, LookupError> {
+        let mut matches = self.iter().filter(|user| user.id == id);
+        let user = matches.next().ok_or(LookupError::NotFound(id))?;
+        match matches.count() {
+            0 => Ok(user),
+            others => Err(LookupError::Ambiguous {
+                id,
+                matches: others + 1,
+            }),
+        }
+    }
+    /// Position of user `id` as `get` takes it, for callers that look a user
+    /// up once and then go through `get`.
+    fn index_of(&self, id: i32) -> Option<usize>;
+    fn get(&self, index: usize) -> Option<&User>;
+    fn find_active(&self) -> Vec<&User>;
+    fn count(&self) -> usize;
+    /// Adds `delta` to the balance of user `id`; a missing id is a no-op.
+    fn update_balance(&mut self, id: i32, delta: Money);
+    /// A missing id is a no-op.
+    fn set_active(&mut self, id: i32, active: bool);
+}
+
+/// Why `find_unique_by_id` found no single user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookupError {
+    NotFound(i32),
+    /// `matches` users, two or more, share the id.
+    Ambiguous {
+        id: i32,
+        matches: usize,
+    },
+}
+
+impl fmt::Display for LookupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(id) => write!(f, "no user has id {id}"),
+            Self::Ambiguous { id, matches } => write!(f, "{matches} users share id {id}"),
+        }
+    }
+}
+
+impl std::error::Error for LookupError {}
+
+#[derive(Clone, Default)]
+pub struct VectorUserRepository {
+    users: Vec<User>,
+}
+
+impl VectorUserRepository {
+    pub fn new(users: Vec<User>) -> Self {
+        Self { users }
+    }
+
+    /// Builds a repository from `(id, balance, active)` rows, without the generator.
+    ///
+    /// ```
+    /// use bench_core::Money;
+    /// use repository_p::{VectorUserRepository, sum_active_balances};
+    ///
+    /// let repository =
+    ///     VectorUserRepository::from_triples(&[(0, 100.0, true), (1, 300.0, false), (2, 500.0, true)]);
+    /// assert_eq!(sum_active_balances(&repository, Money(250.0)), Money(500.0));
+    /// ```
+    pub fn from_triples(rows: &[(i32, f32, bool)]) -> Self {
+        Self::new(
+            rows.iter()
+                .map(|&(id, balance, active)| User {
+                    id,
+                    balance: Money(balance),
+                    active,
+                    score: 0.0,
+                })
+                .collect(),
+        )
+    }
+
+    /// Removes the user at `index` by shifting every later user down: O(n), keeps order.
+    pub fn remove_at(&mut self, index: usize) -> User {
+        self.users.remove(index)
+    }
+
+    /// Removes the user at `index` by moving the last user into its slot: O(1), reorders.
+    pub fn swap_remove_at(&mut self, index: usize) -> User {
+        self.users.swap_remove(index)
+    }
+
+    /// Removes user `id` the fast way, moving the last user into its slot.
+    ///
+    /// That user keeps its id at a new position, so ids no longer match
+    /// positions: code that reaches user `i` through index `i`, such as
+    /// `minimal/repository-p-op2` or the `0..count()` walk of
+    /// `sum_active_balances_by_id`, misses users afterwards.
+    pub fn remove(&mut self, id: i32) -> Option<User> {
+        let index = self.index_of(id)?;
+        Some(self.swap_remove_at(index))
+    }
+
+    /// Removes user `id` by shifting every later user down one slot. O(n).
+    ///
+    /// If every id equalled its position, as `generate_users` assigns them, the
+    /// later users are renumbered to their new positions, so ids stay `0..n`
+    /// in order and direct indexing keeps working. Any other ids, such as
+    /// sparse ones loaded from a file, belong to the caller and are left alone.
+    pub fn remove_preserving_order(&mut self, id: i32) -> Option<User> {
+        let index = self.index_of(id)?;
+        let positional = self
+            .users
+            .iter()
+            .enumerate()
+            .all(|(position, user)| user.id == position as i32);
+        let removed = self.remove_at(index);
+        if positional {
+            for user in &mut self.users[index..] {
+                user.id -= 1;
+            }
+        }
+        Some(removed)
+    }
+
+    /// Appends `user`, or replaces the user with the same id in place and
+    /// returns it, so ids stay unique. The duplicate check is the same linear
+    /// scan as `find_by_id`.
+    pub fn insert(&mut self, user: User) -> Option<User> {
+        match self.index_of(user.id) {
+            Some(index) => Some(std::mem::replace(&mut self.users[index], user)),
+            None => {
+                self.users.push(user);
+                None
+            }
+        }
+    }
+
+    /// Users the backing `Vec` holds before `insert` reallocates.
+    pub fn capacity(&self) -> usize {
+        self.users.capacity()
+    }
+}
+
+impl UserRepository for VectorUserRepository {
+    fn iter(&self) -> Box<dyn Iterator<Item = &User> + '_> {
+        Box::new(self.users.iter())
+    }
+
+    fn as_slice(&self) -> Option<&[User]> {
+        Some(&self.users)
+    }
+
+    fn find_by_id(&self, id: i32) -> Option<&User> {
+        self.users.iter().find(|user| user.id == id)
+    }
+
+    fn index_of(&self, id: i32) -> Option<usize> {
+        self.users.iter().position(|user| user.id == id)
+    }
+
+    fn get(&self, index: usize) -> Option<&User> {
+        self.users.get(index)
+    }
+
+    fn find_active(&self) -> Vec<&User> {
+        self.users.iter().filter(|user| user.active).collect()
+    }
+
+    fn count(&self) -> usize {
+        self.users.len()
+    }
+
+    fn update_balance(&mut self, id: i32, delta: Money) {
+        if let Some(user) = self.users.iter_mut().find(|user| user.id == id) {
+            user.balance += delta;
+        }
+    }
+
+    fn set_active(&mut self, id: i32, active: bool) {
+        if let Some(user) = self.users.iter_mut().find(|user| user.id == id) {
+            user.active = active;
+        }
+    }
+}
+
+/// Keeps users sorted by id so `find_by_id` is a binary search: O(log n)
+/// instead of the linear scan of `VectorUserRepository`.
+#[derive(Clone, Default)]
+pub struct SortedVectorUserRepository {
+    users: Vec<User>,
+}
+
+impl SortedVectorUserRepository {
+    /// Sorts `users` by id; presorted input, as the generator yields, is cheap.
+    pub fn new(mut users: Vec<User>) -> Self {
+        users.sort_unstable_by_key(|user| user.id);
+        Self { users }
+    }
+
+    /// Inserts `user` at its sorted position, or replaces the user with the
+    /// same id and returns it, like `VectorUserRepository::insert`. Finding
+    /// the slot is a binary search, but shifting the later users is O(n).
+    pub fn insert(&mut self, user: User) -> Option<User> {
+        match self.users.binary_search_by_key(&user.id, |user| user.id) {
+            Ok(position) => Some(std::mem::replace(&mut self.users[position], user)),
+            Err(position) => {
+                self.users.insert(position, user);
+                None
+            }
+        }
+    }
+
+    /// Users the backing `Vec` holds before `insert` reallocates.
+    pub fn capacity(&self) -> usize {
+        self.users.capacity()
+    }
+}
+
+impl UserRepository for SortedVectorUserRepository {
+    fn iter(&self) -> Box<dyn Iterator<Item = &User> + '_> {
+        Box::new(self.users.iter())
+    }
+
+    fn as_slice(&self) -> Option<&[User]> {
+        Some(&self.users)
+    }
+
+    fn find_by_id(&self, id: i32) -> Option<&User> {
+        self.users
+            .binary_search_by_key(&id, |user| user.id)
+            .ok()
+            .map(|position| &self.users[position])
+    }
+
+    /// Duplicates sort next to each other, so two binary searches bound the
+    /// run of users with `id`.
+    fn find_unique_by_id(&self, id: i32) -> Result<&User, LookupError> {
+        let start = self.users.partition_point(|user| user.id < id);
+        let end = self.users.partition_point(|user| user.id <= id);
+        match end - start {
+            0 => Err(LookupError::NotFound(id)),
+            1 => Ok(&self.users[start]),
+            matches => Err(LookupError::Ambiguous { id, matches }),
+        }
+    }
+
+    fn index_of(&self, id: i32) -> Option<usize> {
+        self.users.binary_search_by_key(&id, |user| user.id).ok()
+    }
+
+    fn get(&self, index: usize) -> Option<&User> {
+        self.users.get(index)
+    }
+
+    fn find_active(&self) -> Vec<&User> {
+        self.users.iter().filter(|user| user.active).collect()
+    }
+
+    fn count(&self) -> usize {
+        self.users.len()
+    }
+
+    fn update_balance(&mut self, id: i32, delta: Money) {
+        if let Ok(position) = self.users.binary_search_by_key(&id, |user| user.id) {
+            self.users[position].balance += delta;
+        }
+    }
+
+    fn set_active(&mut self, id: i32, active: bool) {
+        if let Ok(position) = self.users.binary_search_by_key(&id, |user| user.id) {
+            self.users[position].active = active;
+        }
+    }
+}
+
+/// Read-through cache over any repository: each `find_by_id` result is looked
+/// up once in the backend and then served from a `HashMap`.
+///
+/// The cache stores positions for the backend's `get` rather than references,
+/// so it needs no lifetime tied to the backend; a miss pays for the backend's
+/// `index_of`. Interior mutability keeps `find_by_id` on `&self`, as the trait
+/// requires.
+pub struct CachingRepository<R: UserRepository> {
+    inner: R,
+    positions: RefCell<HashMap<i32, Option<usize>>>,
+    hits: Cell<usize>,
+    misses: Cell<usize>,
+}
+
+impl<R: UserRepository> CachingRepository<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            positions: RefCell::new(HashMap::new()),
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+        }
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits.get() + self.misses.get();
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits.get() as f64 / lookups as f64
+        }
+    }
+}
+
+impl<R: UserRepository> UserRepository for CachingRepository<R> {
+    fn iter(&self) -> Box<dyn Iterator<Item = &User> + '_> {
+        self.inner.iter()
+    }
+
+    fn as_slice(&self) -> Option<&[User]> {
+        self.inner.as_slice()
+    }
+
+    fn find_by_id(&self, id: i32) -> Option<&User> {
+        self.index_of(id)
+            .and_then(|position| self.inner.get(position))
+    }
+
+    /// Not cached: it has to count every match, which the stored position
+    /// cannot tell.
+    fn find_unique_by_id(&self, id: i32) -> Result<&User, LookupError> {
+        self.inner.find_unique_by_id(id)
+    }
+
+    fn index_of(&self, id: i32) -> Option<usize> {
+        if let Some(&position) = self.positions.borrow().get(&id) {
+            self.hits.set(self.hits.get() + 1);
+            return position;
+        }
+
+        self.misses.set(self.misses.get() + 1);
+        let position = self.inner.index_of(id);
+        self.positions.borrow_mut().insert(id, position);
+        position
+    }
+
+    fn get(&self, index: usize) -> Option<&User> {
+        self.inner.get(index)
+    }
+
+    fn find_active(&self) -> Vec<&User> {
+        self.inner.find_active()
+    }
+
+    fn count(&self) -> usize {
+        self.inner.count()
+    }
+
+    /// Updates never move a user, so the cached positions stay valid.
+    fn update_balance(&mut self, id: i32, delta: Money) {
+        self.inner.update_balance(id, delta);
+    }
+
+    fn set_active(&mut self, id: i32, active: bool) {
+        self.inner.set_active(id, active);
+    }
+}
+
+/// The two plain repositories behind a closed enum: each call is a `match`
+/// on the variant instead of a vtable load, and both arms can be inlined.
+pub enum RepositoryKind {
+    Vector(VectorUserRepository),
+    Sorted(SortedVectorUserRepository),
+}
+
+impl RepositoryKind {
+    pub fn find_by_id(&self, id: i32) -> Option<&User> {
+        match self {
+            Self::Vector(repository) => repository.find_by_id(id),
+            Self::Sorted(repository) => repository.find_by_id(id),
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        match self {
+            Self::Vector(repository) => repository.count(),
+            Self::Sorted(repository) => repository.count(),
+        }
+    }
+}
+
+pub fn qualifies(user: &User, minimum_balance: Money) -> bool {
+    user.active && user.balance >= minimum_balance
+}
+
+/// Stable stand-in for the nightly `likely`/`unlikely` intrinsics: LLVM treats
+/// any path that calls a `#[cold]` function as rarely taken.
+#[cold]
+#[inline(never)]
+fn cold_path() {}
+
+#[inline(always)]
+pub fn likely(condition: bool) -> bool {
+    if !condition {
+        cold_path();
+    }
+    condition
+}
+
+#[inline(always)]
+pub fn unlikely(condition: bool) -> bool {
+    if condition {
+        cold_path();
+    }
+    condition
+}
+
+/// `sum_active_balances` with the `qualifies` branch marked as rarely taken.
+#[inline(never)]
+pub fn sum_qualifying_unlikely(repository: &dyn UserRepository, minimum_balance: Money) -> Money {
+    let mut accumulated_balance = Money::ZERO;
+
+    for user in repository.iter() {
+        if unlikely(qualifies(user, minimum_balance)) {
+            accumulated_balance += user.balance;
+        }
+    }
+
+    accumulated_balance
+}
+
+/// One pass over `iter`, in the backend's own order, so any backend can be
+/// summed and every user is visited whatever its id.
+#[inline(never)]
+pub fn sum_active_balances(repository: &dyn UserRepository, minimum_balance: Money) -> Money {
+    let mut accumulated_balance = Money::ZERO;
+
+    for user in repository.iter() {
+        if qualifies(user, minimum_balance) {
+            accumulated_balance += user.balance;
+        }
+    }
+
+    accumulated_balance
+}
+
+/// The lookup-bound scan the lookup and dispatch comparisons time: one
+/// `find_by_id` per id in `0..count()`. It only visits every user while ids
+/// are `0..count()`, as the generator assigns; generated repositories keep
+/// users in id order, so it then matches `sum_active_balances` bit for bit.
+#[inline(never)]
+pub fn sum_active_balances_by_id(repository: &dyn UserRepository, minimum_balance: Money) -> Money {
+    let mut accumulated_balance = Money::ZERO;
+
+    for i in 0..repository.count() {
+        if let Some(user) = repository.find_by_id(i as i32)
+            && qualifies(user, minimum_balance)
+        {
+            accumulated_balance += user.balance;
+        }
+    }
+
+    accumulated_balance
+}
+
+/// `sum_active_balances` with each balance widened to `f64` before it is
+/// added, the way `minimal/repository-p-op2` and `op3` store theirs. Both walk
+/// `iter()` in the same order and the widening is exact, so any difference
+/// from the `f32` sum is rounding in the narrower accumulator.
+#[inline(never)]
+pub fn sum_active_balances_f64(repository: &dyn UserRepository, minimum_balance: Money) -> f64 {
+    let mut accumulated_balance = 0.0;
+
+    for user in repository.iter() {
+        if qualifies(user, minimum_balance) {
+            accumulated_balance += f64::from(user.balance.raw());
+        }
+    }
+
+    accumulated_balance
+}
+
+/// `sum_active_balances_by_id` monomorphized per repository type, so `find_by_id`
+/// is a direct call the compiler may inline.
+#[inline(never)]
+pub fn sum_active_balances_generic<R: UserRepository>(
+    repository: &R,
+    minimum_balance: Money,
+) -> Money {
+    let mut accumulated_balance = Money::ZERO;
+
+    for i in 0..repository.count() {
+        if let Some(user) = repository.find_by_id(i as i32)
+            && qualifies(user, minimum_balance)
+        {
+            accumulated_balance += user.balance;
+        }
+    }
+
+    accumulated_balance
+}
+
+/// `sum_active_balances_by_id` through `RepositoryKind`: one non-virtual function
+/// whose lookups branch on the variant.
+#[inline(never)]
+pub fn sum_active_balances_enum(repository: &RepositoryKind, minimum_balance: Money) -> Money {
+    let mut accumulated_balance = Money::ZERO;
+
+    for i in 0..repository.count() {
+        if let Some(user) = repository.find_by_id(i as i32)
+            && qualifies(user, minimum_balance)
+        {
+            accumulated_balance += user.balance;
+        }
+    }
+
+    accumulated_balance
+}
+
+/// Same lookups as `sum_active_balances_by_id`, but the filter is folded into a
+/// 0/1 multiplier the way the DoD loop does it, so the only branch left is
+/// the `Option` from `find_by_id`.
+#[inline(never)]
+pub fn sum_active_balances_branchless(
+    repository: &dyn UserRepository,
+    minimum_balance: Money,
+) -> Money {
+    let mut accumulated_balance = Money::ZERO;
+
+    for i in 0..repository.count() {
+        if let Some(user) = repository.find_by_id(i as i32) {
+            let take_value = (user.active & (user.balance >= minimum_balance)) as u32 as f32;
+            accumulated_balance += user.balance * take_value;
+        }
+    }
+
+    accumulated_balance
+}
+
+/// Walks `0..count()` through the trait's `get`, so each user costs one
+/// indexed load instead of a `find_by_id` search: O(n) overall, where
+/// `sum_active_balances_by_id` is O(n^2) on a `VectorUserRepository`.
+#[inline(never)]
+pub fn sum_active_balances_indexed(
+    repository: &dyn UserRepository,
+    minimum_balance: Money,
+) -> Money {
+    let mut accumulated_balance = Money::ZERO;
+
+    for i in 0..repository.count() {
+        if let Some(user) = repository.get(i)
+            && qualifies(user, minimum_balance)
+        {
+            accumulated_balance += user.balance;
+        }
+    }
+
+    accumulated_balance
+}
+
+/// Looks each user up by position in `as_slice()` instead of `find_by_id`,
+/// keeping the `Option` the safe code has to check even though it is always
+/// `Some`.
+///
+/// # Panics
+///
+/// Panics if the repository keeps no slice.
+#[inline(never)]
+pub fn sum_active_balances_by_position(
+    repository: &dyn UserRepository,
+    minimum_balance: Money,
+) -> Money {
+    let users = repository
+        .as_slice()
+        .expect("positional scans need a slice-backed repository");
+    let mut accumulated_balance = Money::ZERO;
+
+    for i in 0..repository.count() {
+        if let Some(user) = users.get(i)
+            && qualifies(user, minimum_balance)
+        {
+            accumulated_balance += user.balance;
+        }
+    }
+
+    accumulated_balance
+}
+
+/// Same scan without the `Option`: the compiler cannot see that
+/// `count() == as_slice().len()` through the trait object, so that invariant
+/// is checked once up front and the loop then indexes without bounds checks.
+///
+/// Every slice-backed repository in this file keeps `count()` equal to the
+/// slice length, so this visits the same users as `sum_active_balances`.
+///
+/// # Panics
+///
+/// Panics if the repository keeps no slice, or if `repository.count()`
+/// differs from its length.
+#[cfg(feature = "unchecked-lookup")]
+#[inline(never)]
+pub fn sum_active_balances_unchecked(
+    repository: &dyn UserRepository,
+    minimum_balance: Money,
+) -> Money {
+    let users = repository
+        .as_slice()
+        .expect("positional scans need a slice-backed repository");
+    let count = repository.count();
+    assert_eq!(count, users.len(), "count() disagrees with as_slice()");
+    let mut accumulated_balance = Money::ZERO;
+
+    for i in 0..count {
+        // SAFETY: `i < count == users.len()`, asserted above.
+        let user = unsafe { users.get_unchecked(i) };
+        if qualifies(user, minimum_balance) {
+            accumulated_balance += user.balance;
+        }
+    }
+
+    accumulated_balance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bench_core::{BenchmarkConfig, generate_users};
+
+    #[test]
+    fn default_repository_is_empty() {
+        let repository = VectorUserRepository::default();
+
+        assert_eq!(repository.count(), 0);
+        assert_eq!(sum_active_balances(&repository, Money::ZERO), Money::ZERO);
+    }
+
+    #[test]
+    fn remove_at_keeps_order_and_swap_remove_moves_last() {
+        let rows = [
+            (0, 1.0, true),
+            (1, 2.0, true),
+            (2, 3.0, true),
+            (3, 4.0, true),
+        ];
+        let mut shifted = VectorUserRepository::from_triples(&rows);
+        let mut swapped = VectorUserRepository::from_triples(&rows);
+
+        assert_eq!(shifted.remove_at(1).id, 1);
+        assert_eq!(swapped.swap_remove_at(1).id, 1);
+
+        let ids = |repository: &VectorUserRepository| -> Vec<i32> {
+            repository.iter().map(|user| user.id).collect()
+        };
+        assert_eq!(ids(&shifted), [0, 2, 3]);
+        assert_eq!(ids(&swapped), [0, 3, 2]);
+    }
+
+    #[test]
+    fn remove_by_id_drops_the_user_and_the_count() {
+        let repository = VectorUserRepository::new(generate_users(5, 17, 0.6));
+        let mut swapped = repository.clone();
+        let mut shifted = repository.clone();
+
+        assert_eq!(swapped.remove(1), repository.find_by_id(1).copied());
+        assert_eq!(swapped.count(), 4);
+        assert_eq!(swapped.find_by_id(1), None);
+        assert_eq!(swapped.remove(1), None);
+        assert_eq!(swapped.count(), 4);
+
+        assert_eq!(
+            shifted.remove_preserving_order(1),
+            repository.find_by_id(1).copied()
+        );
+        assert_eq!(shifted.count(), 4);
+        // Users 2..5 moved down and were renumbered, so id still equals index.
+        let columns = |repository: &VectorUserRepository| -> (Vec<i32>, Vec<Money>) {
+            repository
+                .iter()
+                .map(|user| (user.id, user.balance))
+                .unzip()
+        };
+        let (_, mut expected_balances) = columns(&repository);
+        expected_balances.remove(1);
+        assert_eq!(columns(&shifted), (vec![0, 1, 2, 3], expected_balances));
+        assert_eq!(shifted.remove_preserving_order(4), None);
+
+        // Sparse ids are not positions, so they survive the shift unchanged.
+        let mut sparse = VectorUserRepository::from_triples(&[
+            (10, 1.0, true),
+            (20, 2.0, true),
+            (30, 3.0, true),
+            (40, 4.0, true),
+        ]);
+        assert_eq!(
+            sparse.remove_preserving_order(20).map(|user| user.id),
+            Some(20)
+        );
+        let ids: Vec<i32> = sparse.iter().map(|user| user.id).collect();
+        assert_eq!(ids, [10, 30, 40]);
+        assert_eq!(
+            sparse.find_by_id(30).map(|user| user.balance),
+            Some(Money(3.0))
+        );
+    }
+
+    #[test]
+    fn insert_appends_new_ids_and_replaces_duplicates() {
+        let user = |id, balance| User {
+            id,
+            balance: Money(balance),
+            active: true,
+            score: 0.0,
+        };
+        let mut vector = VectorUserRepository::from_triples(&[(0, 1.0, true), (2, 3.0, true)]);
+        let mut sorted = SortedVectorUserRepository::new(vector.iter().copied().collect());
+
+        assert_eq!(vector.insert(user(1, 2.0)), None);
+        assert_eq!(sorted.insert(user(1, 2.0)), None);
+        assert_eq!((vector.count(), sorted.count()), (3, 3));
+        let ids = |repository: &dyn UserRepository| -> Vec<i32> {
+            repository.iter().map(|user| user.id).collect()
+        };
+        assert_eq!(ids(&vector), [0, 2, 1]);
+        assert_eq!(ids(&sorted), [0, 1, 2]);
+
+        // A duplicate id replaces the stored user and leaves the count alone.
+        assert_eq!(vector.insert(user(2, 30.0)), Some(user(2, 3.0)));
+        assert_eq!(sorted.insert(user(2, 30.0)), Some(user(2, 3.0)));
+        assert_eq!((vector.count(), sorted.count()), (3, 3));
+        assert_eq!(vector.find_by_id(2), Some(&user(2, 30.0)));
+        assert_eq!(sorted.find_by_id(2), Some(&user(2, 30.0)));
+
+        assert!(vector.capacity() >= vector.count());
+        assert!(sorted.capacity() >= sorted.count());
+    }
+
+    #[test]
+    fn unique_lookup_separates_missing_from_shared_ids() {
+        let vector = VectorUserRepository::from_triples(&[
+            (0, 1.0, true),
+            (1, 2.0, true),
+            (0, 3.0, false),
+            (2, 4.0, true),
+        ]);
+        let sorted = SortedVectorUserRepository::new(vector.iter().copied().collect());
+        let repositories: [&dyn UserRepository; 2] = [&vector, &sorted];
+
+        for repository in repositories {
+            assert_eq!(
+                repository.find_unique_by_id(1).map(|user| user.balance),
+                Ok(Money(2.0))
+            );
+            assert_eq!(
+                repository.find_unique_by_id(7),
+                Err(LookupError::NotFound(7))
+            );
+            assert_eq!(
+                repository.find_unique_by_id(0),
+                Err(LookupError::Ambiguous { id: 0, matches: 2 })
+            );
+        }
+        // `find_by_id` still quietly picks one of the two.
+        assert!(vector.find_by_id(0).is_some());
+        assert_eq!(
+            LookupError::Ambiguous { id: 0, matches: 2 }.to_string(),
+            "2 users share id 0"
+        );
+    }
+
+    #[test]
+    fn f64_sum_only_differs_by_f32_rounding() {
+        let small = VectorUserRepository::from_triples(&[(0, 0.5, true), (1, 2.25, true)]);
+        assert_eq!(sum_active_balances_f64(&small, Money::ZERO), 2.75);
+        // Same walk as `sum_active_balances`, so ids need not match positions.
+        let sparse = VectorUserRepository::from_triples(&[(7, 0.5, true), (3, 2.25, true)]);
+        assert_eq!(sum_active_balances_f64(&sparse, Money::ZERO), 2.75);
+
+        let repository = VectorUserRepository::new(generate_users(1000, 17, 0.6));
+        let narrow = f64::from(sum_active_balances(&repository, Money(250.0)).raw());
+        let wide = sum_active_balances_f64(&repository, Money(250.0));
+
+        assert_ne!(narrow, wide);
+        // Each of the ~450 additions rounds by at most half an f32 ulp.
+        assert!((narrow - wide).abs() / wide < 1e-4);
+    }
+
+    #[test]
+    fn three_user_sum_from_triples() {
+        let repository = VectorUserRepository::from_triples(&[
+            (0, 100.0, true),
+            (1, 300.0, false),
+            (2, 500.0, true),
+        ]);
+
+        assert_eq!(repository.count(), 3);
+        assert_eq!(sum_active_balances(&repository, Money(250.0)), Money(500.0));
+    }
+
+    #[test]
+    fn negative_balances_only_qualify_below_a_negative_minimum() {
+        let user = |balance, active| User {
+            id: 0,
+            balance: Money(balance),
+            active,
+            score: 0.0,
+        };
+
+        assert!(qualifies(&user(0.0, true), Money::ZERO));
+        assert!(qualifies(&user(-0.0, true), Money::ZERO));
+        assert!(!qualifies(&user(-0.01, true), Money::ZERO));
+        assert!(!qualifies(&user(-500.0, true), Money(250.0)));
+        assert!(qualifies(&user(-500.0, true), Money(-500.0)));
+        assert!(!qualifies(&user(-500.0, false), Money(-1000.0)));
+
+        let repository = VectorUserRepository::from_triples(&[
+            (0, -50.0, true),
+            (1, 0.0, true),
+            (2, 300.0, false),
+            (3, 40.0, true),
+        ]);
+        assert_eq!(sum_active_balances(&repository, Money::ZERO), Money(40.0));
+        assert_eq!(
+            sum_active_balances(&repository, Money(-100.0)),
+            Money(-10.0)
+        );
+    }
+
+    #[test]
+    fn hinted_scans_match_unhinted() {
+        let repository = VectorUserRepository::from_triples(&[
+            (0, 100.0, true),
+            (1, 300.0, false),
+            (2, 500.0, true),
+        ]);
+
+        assert!(likely(true) && !unlikely(false));
+        assert_eq!(sum_active_balances(&repository, Money(250.0)), Money(500.0));
+        assert_eq!(
+            sum_qualifying_unlikely(&repository, Money(250.0)),
+            Money(500.0)
+        );
+    }
+
+    #[test]
+    fn positional_scans_match_find_by_id() {
+        let repository = VectorUserRepository::new(generate_users(1000, 17, 0.6));
+        let expected = sum_active_balances(&repository, Money(250.0));
+
+        assert_eq!(
+            sum_active_balances_by_position(&repository, Money(250.0)),
+            expected
+        );
+        #[cfg(feature = "unchecked-lookup")]
+        assert_eq!(
+            sum_active_balances_unchecked(&repository, Money(250.0)),
+            expected
+        );
+    }
+
+    #[test]
+    fn iterator_sum_matches_find_by_id_until_ids_leave_their_positions() {
+        let users = generate_users(1000, 17, 0.6);
+        let linear = VectorUserRepository::new(users.clone());
+        let sorted = SortedVectorUserRepository::new(users);
+
+        for repository in [&linear as &dyn UserRepository, &sorted] {
+            assert_eq!(
+                sum_active_balances(repository, Money(250.0)),
+                sum_active_balances_by_id(repository, Money(250.0))
+            );
+        }
+
+        // Id 3 moves into slot 1, so `0..count()` asks for the removed id 1
+        // and never for 3, while `iter` still sees every remaining user.
+        let mut swapped = VectorUserRepository::from_triples(&[
+            (0, 1.0, true),
+            (1, 2.0, true),
+            (2, 4.0, true),
+            (3, 8.0, true),
+        ]);
+        swapped.remove(1);
+        assert_eq!(sum_active_balances(&swapped, Money::ZERO), Money(13.0));
+        assert_eq!(sum_active_balances_by_id(&swapped, Money::ZERO), Money(5.0));
+    }
+
+    #[test]
+    fn branchless_sum_matches_the_branchy_one() {
+        let repository = VectorUserRepository::new(generate_users(1000, 17, 0.6));
+
+        assert_eq!(
+            sum_active_balances_branchless(&repository, Money(250.0)),
+            sum_active_balances(&repository, Money(250.0))
+        );
+        assert_eq!(
+            sum_active_balances_branchless(&VectorUserRepository::default(), Money::ZERO),
+            Money::ZERO
+        );
+    }
+
+    #[test]
+    fn index_of_points_get_at_the_same_user_as_find_by_id() {
+        let users = generate_users(100, 17, 0.6);
+        let linear = VectorUserRepository::new(users.clone());
+        let sorted = SortedVectorUserRepository::new(users.clone());
+        let cached = CachingRepository::new(VectorUserRepository::new(users));
+        let repositories: [&dyn UserRepository; 3] = [&linear, &sorted, &cached];
+
+        for repository in repositories {
+            for id in [0, 42, 99] {
+                let index = repository.index_of(id).unwrap();
+                assert_eq!(repository.get(index), repository.find_by_id(id));
+            }
+            assert_eq!(repository.index_of(100), None);
+            assert_eq!(repository.get(100), None);
+            assert_eq!(
+                sum_active_balances_indexed(repository, Money(250.0)),
+                sum_active_balances(repository, Money(250.0))
+            );
+        }
+    }
+
+    #[test]
+    fn every_dispatch_path_sums_the_same_users() {
+        let users = generate_users(1000, 17, 0.6);
+        let linear = VectorUserRepository::new(users.clone());
+        let sorted = SortedVectorUserRepository::new(users);
+        let expected = sum_active_balances(&linear, Money(250.0));
+
+        assert_eq!(sum_active_balances_generic(&linear, Money(250.0)), expected);
+        assert_eq!(sum_active_balances_generic(&sorted, Money(250.0)), expected);
+        assert_eq!(
+            sum_active_balances_enum(&RepositoryKind::Sorted(sorted), Money(250.0)),
+            expected
+        );
+        assert_eq!(
+            sum_active_balances_enum(&RepositoryKind::Vector(linear), Money(250.0)),
+            expected
+        );
+    }
+
+    #[test]
+    fn find_active_returns_only_active_users() {
+        let ids = |repository: &VectorUserRepository| -> Vec<i32> {
+            repository
+                .find_active()
+                .iter()
+                .map(|user| user.id)
+                .collect()
+        };
+
+        let mixed = VectorUserRepository::from_triples(&[
+            (0, 100.0, true),
+            (1, 300.0, false),
+            (2, 500.0, true),
+        ]);
+        let all_active = VectorUserRepository::from_triples(&[(0, 1.0, true), (1, 2.0, true)]);
+        let none_active = VectorUserRepository::from_triples(&[(0, 1.0, false), (1, 2.0, false)]);
+
+        assert_eq!(ids(&mixed), [0, 2]);
+        assert_eq!(ids(&all_active), [0, 1]);
+        assert!(ids(&none_active).is_empty());
+        assert!(ids(&VectorUserRepository::default()).is_empty());
+    }
+
+    #[test]
+    fn updates_mutate_existing_ids_and_ignore_missing_ones() {
+        let rows = [(0, 100.0, true), (1, 300.0, false), (2, 500.0, true)];
+        let mut repository = VectorUserRepository::from_triples(&rows);
+
+        repository.update_balance(1, Money(50.0));
+        repository.set_active(1, true);
+        repository.update_balance(7, Money(50.0));
+        repository.set_active(7, false);
+
+        let user = repository.find_by_id(1).unwrap();
+        assert_eq!((user.balance, user.active), (Money(350.0), true));
+        assert_eq!(sum_active_balances(&repository, Money::ZERO), Money(950.0));
+        assert_eq!(repository.count(), 3);
+
+        let mut sorted = SortedVectorUserRepository::new(repository.iter().copied().collect());
+        sorted.update_balance(2, Money(-500.0));
+        sorted.set_active(0, false);
+        sorted.update_balance(-1, Money(1.0));
+        assert_eq!(sum_active_balances(&sorted, Money::ZERO), Money(350.0));
+    }
+
+    #[test]
+    fn canonical_dataset_matches_the_shared_checksum() {
+        let config = BenchmarkConfig::default();
+        let users = generate_users(config.elements_count, config.random_seed, 0.6);
+
+        assert_eq!(
+            sum_active_balances(
+                &VectorUserRepository::new(users),
+                Money(config.minimum_balance)
+            )
+            .raw(),
+            bench_core::CANONICAL_CHECKSUM
+        );
+    }
+
+    #[test]
+    fn sorted_repository_finds_present_ids_and_misses_absent_ones() {
+        let repository = SortedVectorUserRepository::new(vec![
+            User {
+                id: 7,
+                balance: Money(700.0),
+                active: true,
+                score: 0.0,
+            },
+            User {
+                id: 2,
+                balance: Money(200.0),
+                active: false,
+                score: 0.0,
+            },
+            User {
+                id: 5,
+                balance: Money(500.0),
+                active: true,
+                score: 0.0,
+            },
+        ]);
+
+        let ids: Vec<i32> = repository.iter().map(|user| user.id).collect();
+        assert_eq!(ids, [2, 5, 7]);
+        assert_eq!(
+            repository.find_by_id(5).map(|user| user.balance),
+            Some(Money(500.0))
+        );
+        assert!(repository.find_by_id(3).is_none());
+        assert!(repository.find_by_id(8).is_none());
+        assert!(
+            SortedVectorUserRepository::default()
+                .find_by_id(0)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn iter_walks_every_backend_like_its_slice() {
+        let rows = [(2, 300.0, true), (0, 100.0, false), (1, 500.0, true)];
+        let vector = VectorUserRepository::from_triples(&rows);
+        let sorted = SortedVectorUserRepository::new(vector.iter().copied().collect());
+        let cached = CachingRepository::new(SortedVectorUserRepository::new(
+            vector.iter().copied().collect(),
+        ));
+        let repositories: [&dyn UserRepository; 3] = [&vector, &sorted, &cached];
+
+        for repository in repositories {
+            assert!(repository.iter().eq(repository.as_slice().unwrap()));
+            assert_eq!(repository.iter().count(), repository.count());
+        }
+        assert_eq!(
+            cached.find_unique_by_id(1).map(|user| user.balance),
+            Ok(Money(500.0))
+        );
+        assert_eq!(VectorUserRepository::default().iter().next(), None);
+    }
+
+    #[test]
+    fn cached_and_uncached_lookups_agree() {
+        let rows = [(0, 100.0, true), (1, 300.0, false), (2, 500.0, true)];
+        let uncached = VectorUserRepository::from_triples(&rows);
+        let cached = CachingRepository::new(VectorUserRepository::from_triples(&rows));
+
+        for id in [2, 0, 7, 2, 7, 1, 0] {
+            assert_eq!(
+                cached.find_by_id(id).map(|user| (user.id, user.balance)),
+                uncached.find_by_id(id).map(|user| (user.id, user.balance))
+            );
+        }
+        assert_eq!(cached.hits.get(), 3);
+        assert_eq!(cached.misses.get(), 4);
+        assert_eq!(sum_active_balances(&cached, Money(250.0)), Money(500.0));
+    }
+}
@@ -1,134 +1,630 @@
 use std::time::Instant;
-use rand::prelude::*;
 
-#[derive(Debug, Clone)]
-struct User {
-    id: i32,
-    balance: f32,
-    active: bool,
-}
+use bench_core::csv::{self, CsvRecord};
+use bench_core::timer::InstantTimer;
+use bench_core::{
+    BenchmarkConfig, COLD_CACHE_SCRATCH_BYTES, Money, User, affinity, evict_cache, generate_users,
+    generate_users_allowing_negative, generate_users_allowing_negative_with_rng,
+    generate_users_with_rng, json, measure_execution_time, measure_execution_time_detailed_with,
+    print_results, sanitize_balances, sweep,
+};
+use repository_p::rng::Pcg32;
+#[cfg(feature = "unchecked-lookup")]
+use repository_p::sum_active_balances_unchecked;
+use repository_p::{
+    CachingRepository, RepositoryKind, SortedVectorUserRepository, UserRepository,
+    VectorUserRepository, sum_active_balances, sum_active_balances_branchless,
+    sum_active_balances_by_id, sum_active_balances_by_position, sum_active_balances_enum,
+    sum_active_balances_f64, sum_active_balances_generic, sum_active_balances_indexed,
+    sum_qualifying_unlikely,
+};
 
-trait UserRepository {
-    fn get_all(&self) -> &Vec<User>;
-    fn find_by_id(&self, id: i32) -> Option<&User>;
-    fn count(&self) -> usize;
-}
+/// Times removing the users at `positions` (each taken modulo the current
+/// count) from a fresh copy of `repository`.
+fn measure_removals(
+    repository: &VectorUserRepository,
+    positions: &[usize],
+    remove: fn(&mut VectorUserRepository, usize) -> User,
+) -> f64 {
+    let mut working_copy = repository.clone();
 
-struct VectorUserRepository {
-    users: Vec<User>,
+    let start = Instant::now();
+    for &position in positions {
+        let index = position % working_copy.count();
+        remove(&mut working_copy, index);
+    }
+    start.elapsed().as_secs_f64()
 }
 
-impl VectorUserRepository {
-    fn new(users: Vec<User>) -> Self {
-        Self { users }
-    }
+struct Options {
+    benchmark: BenchmarkConfig,
+    csv_out: Option<String>,
+    json_out: Option<String>,
+    /// Users read from this file instead of the generator.
+    dataset_csv: Option<String>,
+    pin_core: Option<usize>,
+    /// Run `evict_cache` between the main scan's iterations.
+    cold_cache: bool,
+    /// Generate with `generate_users_allowing_negative`.
+    allow_negative: bool,
+    /// Sizes to regenerate and rerun the main scan at, see `bench_core::sweep`.
+    sweep: Option<Vec<usize>>,
 }
 
-impl UserRepository for VectorUserRepository {
-    fn get_all(&self) -> &Vec<User> {
-        &self.users
-    }
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut benchmark = BenchmarkConfig::builder();
+    let mut csv_out = None;
+    let mut json_out = None;
+    let mut dataset_csv = None;
+    let mut pin_core = None;
+    let mut cold_cache = false;
+    let mut allow_negative = false;
+    let mut sweep = None;
 
-    fn find_by_id(&self, id: i32) -> Option<&User> {
-        self.users.iter().find(|user| user.id == id)
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--active-prob" => {
+                let value = args.next().ok_or("--active-prob requires a value")?;
+                let probability = value
+                    .parse()
+                    .map_err(|_| format!("--active-prob expects a number, got '{value}'"))?;
+                benchmark.active_prob(probability);
+            }
+            "--csv-out" => csv_out = Some(args.next().ok_or("--csv-out requires a file")?),
+            "--json-out" => {
+                json_out = Some(args.next().ok_or("--json-out requires a file or '-'")?);
+            }
+            "--dataset-csv" => {
+                dataset_csv = Some(args.next().ok_or("--dataset-csv requires a file")?);
+            }
+            "--pin-core" => {
+                let value = args.next().ok_or("--pin-core requires a core index")?;
+                let core = value
+                    .parse()
+                    .map_err(|_| format!("--pin-core expects a core index, got '{value}'"))?;
+                pin_core = Some(core);
+            }
+            "--cold-cache" => cold_cache = true,
+            "--allow-negative" => allow_negative = true,
+            "--sweep" => {
+                let value = args
+                    .next()
+                    .ok_or("--sweep requires comma-separated sizes")?;
+                sweep = Some(sweep::parse_sizes(&value)?);
+            }
+            other => return Err(format!("unknown argument '{other}'")),
+        }
     }
 
-    fn count(&self) -> usize {
-        self.users.len()
+    if dataset_csv.is_some() && allow_negative {
+        return Err(
+            "--dataset-csv replaces the generator, so it cannot --allow-negative".to_string(),
+        );
+    }
+    if dataset_csv.is_some() && sweep.is_some() {
+        return Err("--dataset-csv fixes the element count, so it cannot sweep".to_string());
     }
-}
 
-fn qualifies(user: &User, minimum_balance: f32) -> bool {
-    user.active && user.balance >= minimum_balance
+    Ok(Options {
+        benchmark: benchmark.build().map_err(|error| error.to_string())?,
+        csv_out,
+        json_out,
+        dataset_csv,
+        pin_core,
+        cold_cache,
+        allow_negative,
+        sweep,
+    })
 }
 
-#[inline(never)]
-fn sum_active_balances(repository: &dyn UserRepository, minimum_balance: f32) -> f32 {
-    let mut accumulated_balance = 0.0;
+fn main() {
+    let Options {
+        benchmark,
+        csv_out,
+        json_out,
+        dataset_csv,
+        pin_core,
+        cold_cache,
+        allow_negative,
+        sweep,
+    } = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {message}");
+            std::process::exit(2);
+        }
+    };
+    let BenchmarkConfig {
+        elements_count,
+        minimum_balance,
+        random_seed,
+        warmup_iterations,
+        iterations,
+        active_probability,
+    } = benchmark;
+    const HOT_IDS_COUNT: usize = 64;
+    const LOOKUPS_COUNT: usize = 100_000;
+    const LOW_ACTIVE_RATIO: f64 = 0.05;
+    const HINT_ITERATIONS: usize = 1_000;
+
+    let implementation = if cold_cache {
+        "repository-p-cold"
+    } else {
+        "repository-p"
+    };
+
+    // Empty unless --cold-cache, so the hot run sweeps nothing.
+    let mut scratch = if cold_cache {
+        vec![0u8; COLD_CACHE_SCRATCH_BYTES]
+    } else {
+        Vec::new()
+    };
+
+    let mut timer = InstantTimer::default();
+
+    // The sweep replaces the fixed-size run; it times the main `iter()` scan.
+    if let Some(sizes) = &sweep {
+        affinity::pin_or_warn(pin_core);
+        let minimum_balance = Money(minimum_balance);
+        let generate: fn(usize, u64, f64) -> Vec<User> = if allow_negative {
+            generate_users_allowing_negative
+        } else {
+            generate_users
+        };
+        let rows = sweep::run(
+            &mut timer,
+            sizes,
+            &benchmark,
+            generate,
+            || evict_cache(&mut scratch),
+            VectorUserRepository::new,
+            |repository| sum_active_balances(repository, minimum_balance).raw(),
+        );
+        sweep::print_table("Repository", &rows);
+        if let Some(path) = &csv_out
+            && let Err(message) = sweep::append_csv(path, implementation, random_seed, &rows)
+        {
+            eprintln!("error: {message}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // A dataset file replaces the generator and fixes the element count. The
+    // `find_by_id` scans only visit ids `0..count`, as the generator assigns.
+    let dataset = dataset_csv
+        .as_deref()
+        .map(|path| match csv::load_users_csv(path) {
+            Ok(mut users) if !users.is_empty() => {
+                let replaced = sanitize_balances(users.iter_mut().map(|user| &mut user.balance.0));
+                if replaced > 0 {
+                    eprintln!(
+                        "warning: replaced {replaced} non-finite balances in '{path}' with 0.0"
+                    );
+                }
+                users
+            }
+            Ok(_) => {
+                eprintln!("error: dataset '{path}' is empty");
+                std::process::exit(1);
+            }
+            Err(error) => {
+                eprintln!("error: cannot load dataset '{path}': {error}");
+                std::process::exit(1);
+            }
+        });
+    let elements_count = dataset.as_ref().map_or(elements_count, Vec::len);
+
+    // `--json-out -` leaves stdout to the JSON record alone.
+    let verbose = !json::writes_to_stdout(json_out.as_deref());
+
+    if verbose {
+        println!();
+        println!("[ Repository Benchmark ]");
+        println!("Elements Count    : {}", elements_count);
+        println!("Minimum Balance   : {:.2}", minimum_balance);
+        println!("Random Seed       : {}", random_seed);
+        println!("Warmup Iterations : {}", warmup_iterations);
+        println!("Iterations        : {}", iterations);
+        println!("Active Prob       : {}", active_probability);
+        println!("Cold Cache        : {}", cold_cache);
+        println!("Allow Negative    : {}", allow_negative);
+        println!(
+            "Dataset           : {}",
+            dataset_csv.as_deref().unwrap_or("generated")
+        );
+    }
+
+    let mut rng = Pcg32::seed_from_u64(random_seed);
 
-    for i in 0..repository.count() {
-        if let Some(user) = repository.find_by_id(i as i32) {
-            if qualifies(user, minimum_balance) {
-                accumulated_balance += user.balance;
+    let repository = VectorUserRepository::new(match dataset {
+        Some(users) => users,
+        None => {
+            if verbose {
+                println!();
+                println!("Generating elements...");
             }
+            let generate: fn(usize, f64, &mut Pcg32) -> Vec<User> = if allow_negative {
+                generate_users_allowing_negative_with_rng
+            } else {
+                generate_users_with_rng
+            };
+            generate(elements_count, active_probability, &mut rng)
         }
+    });
+
+    let minimum_balance = Money(minimum_balance);
+
+    affinity::pin_or_warn(pin_core);
+
+    if verbose {
+        println!();
+        println!("Warming up...");
     }
 
-    accumulated_balance
-}
+    let mut checksum = Money::ZERO;
+    for _ in 0..warmup_iterations {
+        checksum = sum_active_balances(&repository, minimum_balance);
+    }
 
-fn measure_execution_time<F, R>(iterations: usize, mut f: F) -> f64
-where
-    F: FnMut() -> R,
-{
-    let start = Instant::now();
+    if verbose {
+        println!();
+        println!("Benchmarking...");
+    }
+
+    let stats = measure_execution_time_detailed_with(
+        &mut timer,
+        iterations,
+        || evict_cache(&mut scratch),
+        || sum_active_balances(&repository, minimum_balance),
+    );
+    let total_time_seconds = stats.mean * iterations as f64;
 
-    for _ in 0..iterations {
-        let _ = f();
+    if verbose {
+        print_results(
+            "Repository",
+            checksum.raw() as f64,
+            total_time_seconds,
+            iterations,
+            elements_count,
+        );
+        stats.print();
+        println!();
     }
 
-    start.elapsed().as_secs_f64()
-}
+    if let Some(path) = &csv_out {
+        let record = CsvRecord {
+            implementation,
+            elements: elements_count,
+            seed: random_seed,
+            iterations,
+            total_time_seconds,
+            checksum: checksum.raw(),
+        };
+        if let Err(message) = csv::append(path, &[record]) {
+            eprintln!("error: {message}");
+            std::process::exit(1);
+        }
+    }
 
-fn main() {
-    const ELEMENTS_COUNT: usize = 10_000;
-    const MINIMUM_BALANCE: f32 = 250.0;
-    const RANDOM_SEED: u64 = 17;
-    const WARMUP_ITERATIONS: usize = 2;
-    const ITERATIONS: usize = 8;
+    if let Some(path) = &json_out
+        && let Err(message) = json::write_results(
+            path,
+            implementation,
+            &stats,
+            elements_count,
+            checksum.raw() as f64,
+        )
+    {
+        eprintln!("error: {message}");
+        std::process::exit(1);
+    }
+
+    // The comparisons below are not part of the record.
+    if !verbose {
+        return;
+    }
+
+    println!("Comparing f32 and f64 sums...");
+
+    let f64_checksum = sum_active_balances_f64(&repository, minimum_balance);
+    let absolute_difference = (f64_checksum - checksum.raw() as f64).abs();
 
     println!();
-    println!("[ Repository Benchmark ]");
-    println!("Elements Count    : {}", ELEMENTS_COUNT);
-    println!("Minimum Balance   : {:.2}", MINIMUM_BALANCE);
-    println!("Random Seed       : {}", RANDOM_SEED);
-    println!("Warmup Iterations : {}", WARMUP_ITERATIONS);
-    println!("Iterations        : {}", ITERATIONS);
+    println!("[ Precision Results ]");
+    println!("f32 Checksum               : {:.8}", checksum);
+    println!("f64 Checksum               : {:.8}", f64_checksum);
+    println!("Absolute Difference        : {:.8}", absolute_difference);
+    println!(
+        "Relative Difference        : {:.3e}",
+        absolute_difference / f64_checksum.abs()
+    );
+    println!();
 
-    let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
-    let balance_dist = rand::distributions::Uniform::new(0.0f32, 1000.0f32);
-    let active_dist = rand::distributions::Bernoulli::new(0.6).unwrap();
+    println!("Benchmarking sorted lookups...");
 
+    // `sum_active_balances` walks `iter()`; these time a `find_by_id` per id.
+    let mut linear_checksum = Money::ZERO;
+    let linear_time_seconds = measure_execution_time(&mut timer, iterations, || {
+        linear_checksum = sum_active_balances_by_id(&repository, minimum_balance);
+    });
+    let average_time_seconds = linear_time_seconds / iterations as f64;
+    let sorted_repository = SortedVectorUserRepository::new(repository.iter().copied().collect());
+    let mut sorted_checksum = Money::ZERO;
+    let sorted_time_seconds = measure_execution_time(&mut timer, iterations, || {
+        sorted_checksum = sum_active_balances_by_id(&sorted_repository, minimum_balance);
+    });
+    let sorted_average_time_seconds = sorted_time_seconds / iterations as f64;
+
+    println!();
+    println!("[ Sorted Lookup Results ]");
+    println!("Linear Checksum            : {:.8}", linear_checksum);
+    println!("Sorted Checksum            : {:.8}", sorted_checksum);
+    println!(
+        "Linear find_by_id Time     : {:.3} ms",
+        average_time_seconds * 1e3
+    );
+    println!(
+        "Binary Search Time         : {:.3} ms",
+        sorted_average_time_seconds * 1e3
+    );
+    println!(
+        "Speedup                    : {:.2}x",
+        average_time_seconds / sorted_average_time_seconds
+    );
     println!();
-    println!("Generating elements...");
 
-    let mut users = Vec::with_capacity(ELEMENTS_COUNT);
-    for i in 0..ELEMENTS_COUNT {
-        let user = User {
-            id: i as i32,
-            balance: rng.sample(balance_dist),
-            active: rng.sample(active_dist),
-        };
-        users.push(user);
-    }
+    println!("Benchmarking dispatch...");
+
+    // The binary-search repository keeps each lookup short, so the call
+    // overhead is a visible share of the time.
+    let mut dyn_checksum = Money::ZERO;
+    let dyn_time_seconds = measure_execution_time(&mut timer, HINT_ITERATIONS, || {
+        dyn_checksum =
+            sum_active_balances_by_id(std::hint::black_box(&sorted_repository), minimum_balance);
+    });
+    let mut generic_checksum = Money::ZERO;
+    let generic_time_seconds = measure_execution_time(&mut timer, HINT_ITERATIONS, || {
+        generic_checksum =
+            sum_active_balances_generic(std::hint::black_box(&sorted_repository), minimum_balance);
+    });
+    let kind_repository = RepositoryKind::Sorted(sorted_repository);
+    let mut enum_checksum = Money::ZERO;
+    let enum_time_seconds = measure_execution_time(&mut timer, HINT_ITERATIONS, || {
+        enum_checksum =
+            sum_active_balances_enum(std::hint::black_box(&kind_repository), minimum_balance);
+    });
 
-    let repository = VectorUserRepository::new(users);
+    let dispatch_runs = HINT_ITERATIONS as f64;
 
     println!();
-    println!("Warming up...");
+    println!("[ Dispatch Results ]");
+    println!("Dyn Checksum               : {:.8}", dyn_checksum);
+    println!("Generic Checksum           : {:.8}", generic_checksum);
+    println!("Enum Checksum              : {:.8}", enum_checksum);
+    println!(
+        "Dyn Time                   : {:.3} us",
+        dyn_time_seconds / dispatch_runs * 1e6
+    );
+    println!(
+        "Generic Time               : {:.3} us",
+        generic_time_seconds / dispatch_runs * 1e6
+    );
+    println!(
+        "Enum Time                  : {:.3} us",
+        enum_time_seconds / dispatch_runs * 1e6
+    );
+    println!(
+        "Vtable Cost vs Generic     : {:.2} %",
+        (dyn_time_seconds / generic_time_seconds - 1.0) * 100.0
+    );
+    println!(
+        "Vtable Cost vs Enum        : {:.2} %",
+        (dyn_time_seconds / enum_time_seconds - 1.0) * 100.0
+    );
+    println!();
 
-    let mut checksum = 0.0f32;
-    for _ in 0..WARMUP_ITERATIONS {
-        checksum = sum_active_balances(&repository, MINIMUM_BALANCE);
-    }
+    println!("Benchmarking branchless lookups...");
+
+    let mut branchless_checksum = Money::ZERO;
+    let branchless_time_seconds = measure_execution_time(&mut timer, iterations, || {
+        branchless_checksum = sum_active_balances_branchless(&repository, minimum_balance);
+    });
+    let branchless_average_time_seconds = branchless_time_seconds / iterations as f64;
+
+    println!();
+    println!("[ Branchless Lookup Results ]");
+    println!("Branchy Checksum           : {:.8}", linear_checksum);
+    println!("Branchless Checksum        : {:.8}", branchless_checksum);
+    println!(
+        "Branchy Time               : {:.3} ms",
+        average_time_seconds * 1e3
+    );
+    println!(
+        "Branchless Time            : {:.3} ms",
+        branchless_average_time_seconds * 1e3
+    );
+    println!(
+        "Branch Cost                : {:.2} %",
+        (average_time_seconds / branchless_average_time_seconds - 1.0) * 100.0
+    );
+    println!();
+
+    println!("Benchmarking removals...");
+
+    let positions: Vec<usize> = (0..elements_count / 10)
+        .map(|_| rng.next_u32() as usize)
+        .collect();
+    let shift_time_seconds =
+        measure_removals(&repository, &positions, VectorUserRepository::remove_at);
+    let swap_time_seconds = measure_removals(
+        &repository,
+        &positions,
+        VectorUserRepository::swap_remove_at,
+    );
 
     println!();
-    println!("Benchmarking...");
+    println!("[ Removal Results ]");
+    println!("Removed Users              : {}", positions.len());
+    println!(
+        "Shift Remove Time          : {:.3} ms",
+        shift_time_seconds * 1e3
+    );
+    println!(
+        "Swap Remove Time           : {:.3} ms (reorders users)",
+        swap_time_seconds * 1e3
+    );
+    println!(
+        "Shift / Swap Cost Ratio    : {:.2}x",
+        shift_time_seconds / swap_time_seconds
+    );
+    println!();
+
+    println!("Benchmarking cached lookups...");
 
-    let total_time_seconds = measure_execution_time(ITERATIONS, || {
-        sum_active_balances(&repository, MINIMUM_BALANCE)
+    let hot_ids: Vec<i32> = (0..HOT_IDS_COUNT)
+        .map(|_| (rng.next_u32() as usize % elements_count) as i32)
+        .collect();
+    let lookup = |repository: &dyn UserRepository| {
+        let mut found_balance = Money::ZERO;
+        for i in 0..LOOKUPS_COUNT {
+            if let Some(user) = repository.find_by_id(hot_ids[i % HOT_IDS_COUNT]) {
+                found_balance += user.balance;
+            }
+        }
+        found_balance
+    };
+
+    let start = Instant::now();
+    let uncached_checksum = lookup(&repository);
+    let uncached_time_seconds = start.elapsed().as_secs_f64();
+
+    let caching_repository = CachingRepository::new(repository);
+    let start = Instant::now();
+    let cached_checksum = lookup(&caching_repository);
+    let cached_time_seconds = start.elapsed().as_secs_f64();
+
+    println!();
+    println!("[ Cached Lookup Results ]");
+    println!(
+        "Lookups                    : {} over {} hot ids",
+        LOOKUPS_COUNT, HOT_IDS_COUNT
+    );
+    println!("Uncached Checksum          : {:.8}", uncached_checksum);
+    println!("Cached Checksum            : {:.8}", cached_checksum);
+    println!(
+        "Uncached Time              : {:.3} ms",
+        uncached_time_seconds * 1e3
+    );
+    println!(
+        "Cached Time                : {:.3} ms",
+        cached_time_seconds * 1e3
+    );
+    println!(
+        "Hit Rate                   : {:.2} %",
+        caching_repository.hit_rate() * 100.0
+    );
+    println!(
+        "Speedup                    : {:.2}x",
+        uncached_time_seconds / cached_time_seconds
+    );
+    println!();
+
+    println!("Benchmarking branch hints...");
+
+    let rare_repository = VectorUserRepository::new(generate_users(
+        elements_count,
+        random_seed,
+        LOW_ACTIVE_RATIO,
+    ));
+
+    let mut unhinted_checksum = Money::ZERO;
+    let unhinted_time_seconds = measure_execution_time(&mut timer, HINT_ITERATIONS, || {
+        unhinted_checksum =
+            sum_active_balances(std::hint::black_box(&rare_repository), minimum_balance);
+    });
+    let mut hinted_checksum = Money::ZERO;
+    let hinted_time_seconds = measure_execution_time(&mut timer, HINT_ITERATIONS, || {
+        hinted_checksum =
+            sum_qualifying_unlikely(std::hint::black_box(&rare_repository), minimum_balance);
     });
 
-    let average_time_seconds = total_time_seconds / ITERATIONS as f64;
-    let elements_per_second = ELEMENTS_COUNT as f64 / average_time_seconds;
-    let nanoseconds_per_element = (average_time_seconds * 1e9) / ELEMENTS_COUNT as f64;
+    let hint_elements = (elements_count * HINT_ITERATIONS) as f64;
 
     println!();
-    println!("[ Repository Results ]");
-    println!("Checksum                   : {:.8}", checksum);
-    println!("Total Time                 : {:.2} s", total_time_seconds);
-    println!("Average Time per Iteration : {:.2} s", average_time_seconds);
-    println!("Elements per Second        : {:.2} M", elements_per_second / 1e6);
-    println!("Nanoseconds per Element    : {:.2}", nanoseconds_per_element);
+    println!("[ Branch Hint Results ]");
+    println!("Active Ratio               : {:.2}", LOW_ACTIVE_RATIO);
+    println!("Unhinted Checksum          : {:.8}", unhinted_checksum);
+    println!("Hinted Checksum            : {:.8}", hinted_checksum);
+    println!(
+        "Unhinted Elements/s        : {:.2} M",
+        hint_elements / unhinted_time_seconds / 1e6
+    );
+    println!(
+        "Hinted Elements/s          : {:.2} M",
+        hint_elements / hinted_time_seconds / 1e6
+    );
+    println!(
+        "Unlikely Hint Speedup      : {:.2}x",
+        unhinted_time_seconds / hinted_time_seconds
+    );
+    println!();
+
+    println!("Benchmarking positional lookups...");
+
+    let mut checked_checksum = Money::ZERO;
+    let checked_time_seconds = measure_execution_time(&mut timer, HINT_ITERATIONS, || {
+        checked_checksum = sum_active_balances_by_position(
+            std::hint::black_box(&caching_repository),
+            minimum_balance,
+        );
+    });
+    let mut indexed_checksum = Money::ZERO;
+    let indexed_time_seconds = measure_execution_time(&mut timer, HINT_ITERATIONS, || {
+        indexed_checksum =
+            sum_active_balances_indexed(std::hint::black_box(&caching_repository), minimum_balance);
+    });
+
+    println!();
+    println!("[ Positional Lookup Results ]");
+    println!("Checked Checksum           : {:.8}", checked_checksum);
+    println!(
+        "Checked Elements/s         : {:.2} M",
+        hint_elements / checked_time_seconds / 1e6
+    );
+    println!("Indexed Checksum           : {:.8}", indexed_checksum);
+    println!(
+        "Indexed Elements/s         : {:.2} M",
+        hint_elements / indexed_time_seconds / 1e6
+    );
+    println!(
+        "Speedup over find_by_id    : {:.2}x",
+        average_time_seconds / (indexed_time_seconds / HINT_ITERATIONS as f64)
+    );
+
+    #[cfg(feature = "unchecked-lookup")]
+    {
+        let mut unchecked_checksum = Money::ZERO;
+        let unchecked_time_seconds = measure_execution_time(&mut timer, HINT_ITERATIONS, || {
+            unchecked_checksum = sum_active_balances_unchecked(
+                std::hint::black_box(&caching_repository),
+                minimum_balance,
+            );
+        });
+
+        println!("Unchecked Checksum         : {:.8}", unchecked_checksum);
+        println!(
+            "Unchecked Elements/s       : {:.2} M",
+            hint_elements / unchecked_time_seconds / 1e6
+        );
+        println!(
+            "Option Check Overhead      : {:.2} %",
+            (checked_time_seconds / unchecked_time_seconds - 1.0) * 100.0
+        );
+    }
+
+    #[cfg(not(feature = "unchecked-lookup"))]
+    println!("Enable the `unchecked-lookup` feature to time the unchecked variant.");
+
     println!();
 }
@@ -0,0 +1,254 @@
+//! Plain array-of-structs baseline: the `User` rows are scanned directly, with
+//! no repository in between, so the only difference from the DoD benchmark is
+//! the memory layout.
+
+use bench_core::csv::{self, CsvRecord};
+use bench_core::timer::InstantTimer;
+use bench_core::{
+    BenchmarkConfig, COLD_CACHE_SCRATCH_BYTES, CommonFlags, Entity, User, evict_cache,
+    generate_users, generate_users_allowing_negative, measure_execution_time_detailed_with,
+    parse_common_flags, print_bandwidth, print_results,
+};
+use bench_core::{affinity, json, sweep};
+
+/// Every field of every record is fetched, even though the sum only reads
+/// `metric` and `included` (`balance` and `active` for a `User`).
+#[inline(never)]
+fn sum_active_balances<T: Entity>(entities: &[T], minimum_balance: f32) -> f32 {
+    entities
+        .iter()
+        .filter(|entity| entity.included() && entity.metric() >= minimum_balance)
+        .map(Entity::metric)
+        .sum()
+}
+
+fn main() {
+    let config = BenchmarkConfig::default();
+    let BenchmarkConfig {
+        elements_count,
+        minimum_balance,
+        random_seed,
+        warmup_iterations,
+        iterations,
+        active_probability,
+    } = config;
+
+    let CommonFlags {
+        csv_out,
+        json_out,
+        pin_core,
+        cold_cache,
+        sweep,
+        allow_negative,
+    } = match parse_common_flags(std::env::args().skip(1)) {
+        Ok(flags) => flags,
+        Err(message) => {
+            eprintln!("error: {message}");
+            std::process::exit(2);
+        }
+    };
+
+    let implementation = if cold_cache { "aos-p-cold" } else { "aos-p" };
+
+    // Empty unless --cold-cache, so the hot run sweeps nothing.
+    let mut scratch = if cold_cache {
+        vec![0u8; COLD_CACHE_SCRATCH_BYTES]
+    } else {
+        Vec::new()
+    };
+
+    let generate: fn(usize, u64, f64) -> Vec<User> = if allow_negative {
+        generate_users_allowing_negative
+    } else {
+        generate_users
+    };
+
+    let mut timer = InstantTimer::default();
+
+    // The sweep replaces the fixed-size run.
+    if let Some(sizes) = &sweep {
+        affinity::pin_or_warn(pin_core);
+        let rows = sweep::run(
+            &mut timer,
+            sizes,
+            &config,
+            generate,
+            || evict_cache(&mut scratch),
+            |users| users,
+            |users| sum_active_balances(users, minimum_balance),
+        );
+        sweep::print_table("AoS", &rows);
+        if let Some(path) = &csv_out
+            && let Err(message) = sweep::append_csv(path, implementation, random_seed, &rows)
+        {
+            eprintln!("error: {message}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // `--json-out -` leaves stdout to the JSON record alone.
+    let verbose = !json::writes_to_stdout(json_out.as_deref());
+
+    if verbose {
+        println!();
+        println!("[ AoS Benchmark ]");
+        println!("Elements Count    : {}", elements_count);
+        println!("Minimum Balance   : {:.2}", minimum_balance);
+        println!("Random Seed       : {}", random_seed);
+        println!("Warmup Iterations : {}", warmup_iterations);
+        println!("Iterations        : {}", iterations);
+        println!("Cold Cache        : {}", cold_cache);
+        println!("Allow Negative    : {}", allow_negative);
+        println!("Record Size       : {} bytes", size_of::<User>());
+
+        println!();
+        println!("Generating elements...");
+    }
+
+    let users = generate(elements_count, random_seed, active_probability);
+
+    affinity::pin_or_warn(pin_core);
+
+    if verbose {
+        println!();
+        println!("Warming up...");
+    }
+
+    let mut checksum = 0.0f32;
+    for _ in 0..warmup_iterations {
+        checksum = sum_active_balances(&users, minimum_balance);
+    }
+
+    if verbose {
+        println!();
+        println!("Benchmarking...");
+    }
+
+    let stats = measure_execution_time_detailed_with(
+        &mut timer,
+        iterations,
+        || evict_cache(&mut scratch),
+        || sum_active_balances(&users, minimum_balance),
+    );
+    let total_time_seconds = stats.mean * iterations as f64;
+
+    if verbose {
+        print_results(
+            "AoS",
+            checksum as f64,
+            total_time_seconds,
+            iterations,
+            elements_count,
+        );
+        print_bandwidth(elements_count * size_of::<User>(), stats.mean);
+        stats.print();
+        println!();
+    }
+
+    if let Some(path) = &csv_out {
+        let record = CsvRecord {
+            implementation,
+            elements: elements_count,
+            seed: random_seed,
+            iterations,
+            total_time_seconds,
+            checksum,
+        };
+        if let Err(message) = csv::append(path, &[record]) {
+            eprintln!("error: {message}");
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(path) = &json_out
+        && let Err(message) = json::write_results(
+            path,
+            implementation,
+            &stats,
+            elements_count,
+            checksum as f64,
+        )
+    {
+        eprintln!("error: {message}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bench_core::Money;
+
+    #[test]
+    fn three_user_sum() {
+        let users = [
+            User {
+                id: 0,
+                balance: Money(100.0),
+                active: true,
+                score: 0.0,
+            },
+            User {
+                id: 1,
+                balance: Money(300.0),
+                active: false,
+                score: 0.0,
+            },
+            User {
+                id: 2,
+                balance: Money(500.0),
+                active: true,
+                score: 0.0,
+            },
+        ];
+
+        assert_eq!(sum_active_balances(&users, 250.0), 500.0);
+        assert_eq!(sum_active_balances::<User>(&[], 0.0), 0.0);
+    }
+
+    #[test]
+    fn any_entity_reuses_the_scan() {
+        struct Product {
+            sku: i32,
+            price: f32,
+            in_stock: bool,
+        }
+
+        impl Entity for Product {
+            fn id(&self) -> i32 {
+                self.sku
+            }
+
+            fn metric(&self) -> f32 {
+                self.price
+            }
+
+            fn included(&self) -> bool {
+                self.in_stock
+            }
+        }
+
+        let products: Vec<Product> = (0..4)
+            .map(|sku| Product {
+                sku,
+                price: sku as f32 * 10.0,
+                in_stock: sku != 2,
+            })
+            .collect();
+
+        assert_eq!(products[3].id(), 3);
+        assert_eq!(sum_active_balances(&products, 10.0), 40.0);
+    }
+
+    #[test]
+    fn canonical_dataset_matches_the_shared_checksum() {
+        let config = BenchmarkConfig::default();
+        let users = generate_users(config.elements_count, config.random_seed, 0.6);
+
+        assert_eq!(
+            sum_active_balances(&users, config.minimum_balance),
+            bench_core::CANONICAL_CHECKSUM
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! JSON ingest scenario: a service receiving the users as a JSON array has to
+//! deserialize them before it can run the scan, so both phases are timed
+//! separately to show which one dominates.
+
+use std::time::Instant;
+
+use bench_core::Money;
+
+use crate::{User, VectorUserRepository, sum_active_balances};
+
+pub fn to_json(users: &[User]) -> String {
+    serde_json::to_string(users).expect("users always serialize to JSON")
+}
+
+pub fn from_json(payload: &str) -> Vec<User> {
+    serde_json::from_str(payload).expect("payload was produced by to_json")
+}
+
+pub fn run_json_ingest(payload: &str, minimum_balance: Money, iterations: usize) {
+    let mut deserialize_seconds = 0.0;
+    let mut scan_seconds = 0.0;
+    let mut checksum = Money::ZERO;
+
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let users = from_json(payload);
+        deserialize_seconds += start.elapsed().as_secs_f64();
+
+        let repository = VectorUserRepository::new(users);
+
+        let start = Instant::now();
+        checksum = sum_active_balances(&repository, minimum_balance);
+        scan_seconds += start.elapsed().as_secs_f64();
+    }
+
+    let average_deserialize_ms = deserialize_seconds * 1e3 / iterations as f64;
+    let average_scan_ms = scan_seconds * 1e3 / iterations as f64;
+    let deserialize_share = deserialize_seconds / (deserialize_seconds + scan_seconds) * 100.0;
+
+    println!("[ JSON Ingest Results ]");
+    println!("Payload Size               : {} bytes", payload.len());
+    println!("Checksum                   : {:.8}", checksum);
+    println!(
+        "Average Deserialize Time   : {:.3} ms",
+        average_deserialize_ms
+    );
+    println!("Average Scan Time          : {:.3} ms", average_scan_ms);
+    println!("Deserialize Share          : {:.1} %", deserialize_share);
+    println!();
+}
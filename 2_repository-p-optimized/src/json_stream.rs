@@ -0,0 +1,176 @@
+//! Streaming vs fully-buffered JSON ingest.
+//!
+//! Both variants read the same JSON Lines file (one user object per line).
+//! The buffered one reads the whole file, deserializes every user into a
+//! repository and then scans it; the streaming one feeds `StreamDeserializer`
+//! from a `BufReader` and folds each user into the sum as it is parsed, so it
+//! never holds more than one user. A counting global allocator reports the
+//! peak heap growth of each pass.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use bench_core::Money;
+
+use crate::{User, VectorUserRepository, sum_active_balances};
+
+/// Forwards to the system allocator while tracking live and peak heap bytes.
+struct PeakAllocator;
+
+static CURRENT_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+impl PeakAllocator {
+    fn record_growth(bytes: usize) {
+        let current = CURRENT_BYTES.fetch_add(bytes, Ordering::Relaxed) + bytes;
+        PEAK_BYTES.fetch_max(current, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: every call is forwarded unchanged to `System`; only counters are added.
+unsafe impl GlobalAlloc for PeakAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            Self::record_growth(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        CURRENT_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            CURRENT_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            Self::record_growth(new_size);
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAllocator = PeakAllocator;
+
+/// Runs `f` and returns its result with the peak heap growth it caused.
+fn with_peak_bytes<R>(f: impl FnOnce() -> R) -> (R, usize) {
+    let baseline = CURRENT_BYTES.load(Ordering::Relaxed);
+    PEAK_BYTES.store(baseline, Ordering::Relaxed);
+
+    let result = f();
+
+    (result, PEAK_BYTES.load(Ordering::Relaxed) - baseline)
+}
+
+fn write_json_lines(path: &Path, users: &[User]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for user in users {
+        serde_json::to_writer(&mut writer, user)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Parses every user before scanning, like `run_json_ingest` does.
+pub fn scan_buffered(mut reader: impl Read, minimum_balance: Money) -> Money {
+    let mut payload = String::new();
+    reader
+        .read_to_string(&mut payload)
+        .expect("failed to read JSON lines");
+
+    let users = serde_json::Deserializer::from_str(&payload)
+        .into_iter::<User>()
+        .collect::<Result<Vec<_>, _>>()
+        .expect("payload was produced by write_json_lines");
+
+    sum_active_balances(&VectorUserRepository::new(users), minimum_balance)
+}
+
+/// Scans each user as soon as it is parsed; only one user is alive at a time.
+pub fn scan_streaming(reader: impl Read, minimum_balance: Money) -> Money {
+    let mut accumulated_balance = Money::ZERO;
+
+    for user in serde_json::Deserializer::from_reader(BufReader::new(reader)).into_iter::<User>() {
+        let user = user.expect("payload was produced by write_json_lines");
+        if user.active && user.balance >= minimum_balance {
+            accumulated_balance += user.balance;
+        }
+    }
+
+    accumulated_balance
+}
+
+pub fn run_streaming_ingest(users: &[User], minimum_balance: Money, iterations: usize) {
+    let path = std::env::temp_dir().join(format!("repository-p-{}.jsonl", std::process::id()));
+    write_json_lines(&path, users).expect("failed to write the JSON lines file");
+    let file_bytes = std::fs::metadata(&path).map_or(0, |metadata| metadata.len());
+
+    let mut variants = Vec::new();
+    for (label, scan) in [
+        ("Buffered", scan_buffered as fn(File, Money) -> Money),
+        ("Streaming", scan_streaming),
+    ] {
+        let mut checksum = Money::ZERO;
+        let mut peak_bytes = 0;
+
+        let start = Instant::now();
+        for _ in 0..iterations {
+            let file = File::open(&path).expect("JSON lines file was just written");
+            let (pass_checksum, pass_peak_bytes) = with_peak_bytes(|| scan(file, minimum_balance));
+            checksum = pass_checksum;
+            peak_bytes = peak_bytes.max(pass_peak_bytes);
+        }
+        let average_ms = start.elapsed().as_secs_f64() * 1e3 / iterations as f64;
+
+        variants.push((label, checksum, peak_bytes, average_ms));
+    }
+
+    let _ = std::fs::remove_file(&path);
+
+    println!("[ JSON Streaming Ingest Results ]");
+    println!("Users                      : {}", users.len());
+    println!(
+        "File Size                  : {:.2} MiB",
+        file_bytes as f64 / (1024.0 * 1024.0)
+    );
+    for (label, checksum, peak_bytes, average_ms) in &variants {
+        println!("{:<9} Checksum         : {:.8}", label, checksum);
+        println!("{:<9} Average Time     : {:.3} ms", label, average_ms);
+        println!(
+            "{:<9} Peak Heap Growth : {:.2} MiB",
+            label,
+            *peak_bytes as f64 / (1024.0 * 1024.0)
+        );
+    }
+    println!(
+        "Checksums Match            : {}",
+        variants[0].1 == variants[1].1
+    );
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaming_and_buffered_agree() {
+        let mut payload = Vec::new();
+        for user in crate::generate_users(1000, 17, 0.6) {
+            serde_json::to_writer(&mut payload, &user).unwrap();
+            payload.push(b'\n');
+        }
+
+        assert_eq!(
+            scan_streaming(payload.as_slice(), Money(250.0)),
+            scan_buffered(payload.as_slice(), Money(250.0))
+        );
+    }
+}
@@ -1,23 +1,29 @@
-use std::time::Instant;
-use rand::prelude::*;
-
-#[derive(Debug, Clone)]
-struct User {
-    id: i32,
-    balance: f32,
-    active: bool,
-}
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "serde")]
+mod json_stream;
+mod self_test;
+
+use bench_core::csv::{self, CsvRecord};
+use bench_core::timer::InstantTimer;
+use bench_core::{
+    BenchmarkConfig, COLD_CACHE_SCRATCH_BYTES, Money, User, affinity, contention, evict_cache,
+    generate_users, measure_execution_time, measure_execution_time_detailed_with, print_bandwidth,
+    print_results, sweep,
+};
 
+#[allow(dead_code)]
 trait UserRepository {
     fn find_by_id(&self, id: i32) -> Option<&User>;
 
     fn count(&self) -> usize;
 
-    fn sum_active_balances(&self, minimum_balance: f32) -> f32;
+    fn sum_active_balances(&self, minimum_balance: Money) -> Money;
 
-    fn get_active_users_above_balance(&self, minimum_balance: f32) -> Vec<&User>;
+    fn get_active_users_above_balance(&self, minimum_balance: Money) -> Vec<&User>;
 }
 
+#[derive(Default)]
 struct VectorUserRepository {
     users: Vec<User>,
 }
@@ -26,6 +32,33 @@ impl VectorUserRepository {
     fn new(users: Vec<User>) -> Self {
         Self { users }
     }
+
+    /// Builds a repository from `(id, balance, active)` rows, without the generator.
+    ///
+    /// Doctests do not run for a binary crate; the unit test
+    /// `three_user_sum_from_triples` runs this example instead.
+    ///
+    /// ```ignore
+    /// let repository = VectorUserRepository::from_triples(&[
+    ///     (0, 100.0, true),
+    ///     (1, 300.0, false),
+    ///     (2, 500.0, true),
+    /// ]);
+    /// assert_eq!(sum_active_balances(&repository, Money(250.0)), Money(500.0));
+    /// ```
+    #[allow(dead_code)]
+    fn from_triples(rows: &[(i32, f32, bool)]) -> Self {
+        Self::new(
+            rows.iter()
+                .map(|&(id, balance, active)| User {
+                    id,
+                    balance: Money(balance),
+                    active,
+                    score: 0.0,
+                })
+                .collect(),
+        )
+    }
 }
 
 impl UserRepository for VectorUserRepository {
@@ -38,7 +71,7 @@ impl UserRepository for VectorUserRepository {
     }
 
     /// Optimization 2
-    fn sum_active_balances(&self, minimum_balance: f32) -> f32 {
+    fn sum_active_balances(&self, minimum_balance: Money) -> Money {
         self.users
             .iter()
             .filter(|user| user.active && user.balance >= minimum_balance)
@@ -47,7 +80,44 @@ impl UserRepository for VectorUserRepository {
     }
 
     /// Optimization 3
-    fn get_active_users_above_balance(&self, minimum_balance: f32) -> Vec<&User> {
+    fn get_active_users_above_balance(&self, minimum_balance: Money) -> Vec<&User> {
+        self.users
+            .iter()
+            .filter(|user| user.active && user.balance >= minimum_balance)
+            .collect()
+    }
+}
+
+/// Finalized repository: `Box<[User]>` has no capacity field and never holds
+/// spare allocation, but is otherwise scanned exactly like the vector.
+struct BoxedUserRepository {
+    users: Box<[User]>,
+}
+
+impl BoxedUserRepository {
+    fn new(users: Box<[User]>) -> Self {
+        Self { users }
+    }
+}
+
+impl UserRepository for BoxedUserRepository {
+    fn find_by_id(&self, id: i32) -> Option<&User> {
+        self.users.iter().find(|user| user.id == id)
+    }
+
+    fn count(&self) -> usize {
+        self.users.len()
+    }
+
+    fn sum_active_balances(&self, minimum_balance: Money) -> Money {
+        self.users
+            .iter()
+            .filter(|user| user.active && user.balance >= minimum_balance)
+            .map(|user| user.balance)
+            .sum()
+    }
+
+    fn get_active_users_above_balance(&self, minimum_balance: Money) -> Vec<&User> {
         self.users
             .iter()
             .filter(|user| user.active && user.balance >= minimum_balance)
@@ -56,80 +126,447 @@ impl UserRepository for VectorUserRepository {
 }
 
 #[inline(never)]
-fn sum_active_balances(repository: &dyn UserRepository, minimum_balance: f32) -> f32 {
+fn sum_active_balances(repository: &dyn UserRepository, minimum_balance: Money) -> Money {
     repository.sum_active_balances(minimum_balance)
 }
 
-fn measure_execution_time<F, R>(iterations: usize, mut f: F) -> f64
-where
-    F: FnMut() -> R,
-{
-    let start = Instant::now();
-    for _ in 0..iterations {
-        let _ = f();
+#[derive(Debug, PartialEq)]
+enum Command {
+    Benchmark {
+        contention: bool,
+        /// Size of the companion's buffer, `contention::DEFAULT_BUFFER_BYTES` if unset.
+        contention_bytes: Option<usize>,
+        csv_out: Option<String>,
+        json_out: Option<String>,
+        pin_core: Option<usize>,
+        cold_cache: bool,
+        sweep: Option<Vec<usize>>,
+    },
+    SelfTest,
+}
+
+/// `self-test` is only recognised as the first argument; anything else is
+/// parsed as benchmark flags.
+fn parse_command(args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut contention = false;
+    let mut contention_bytes = None;
+    let mut csv_out = None;
+    let mut json_out = None;
+    let mut pin_core = None;
+    let mut cold_cache = false;
+    let mut sweep = None;
+
+    let mut args = args.peekable();
+    if args.peek().map(String::as_str) == Some("self-test") {
+        args.next();
+        return match args.next() {
+            Some(other) => Err(format!("unknown argument '{other}'")),
+            None => Ok(Command::SelfTest),
+        };
     }
-    start.elapsed().as_secs_f64()
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--contention" => contention = true,
+            "--contention-bytes" => {
+                let value = args
+                    .next()
+                    .ok_or("--contention-bytes requires a byte count")?;
+                let bytes = value.parse().map_err(|_| {
+                    format!("--contention-bytes expects a byte count, got '{value}'")
+                })?;
+                if bytes == 0 {
+                    return Err("--contention-bytes must be nonzero".to_string());
+                }
+                contention_bytes = Some(bytes);
+            }
+            "--csv-out" => csv_out = Some(args.next().ok_or("--csv-out requires a file")?),
+            "--json-out" => {
+                json_out = Some(args.next().ok_or("--json-out requires a file or '-'")?);
+            }
+            "--pin-core" => {
+                let value = args.next().ok_or("--pin-core requires a core index")?;
+                let core = value
+                    .parse()
+                    .map_err(|_| format!("--pin-core expects a core index, got '{value}'"))?;
+                pin_core = Some(core);
+            }
+            "--cold-cache" => cold_cache = true,
+            "--sweep" => {
+                let value = args
+                    .next()
+                    .ok_or("--sweep requires comma-separated sizes")?;
+                sweep = Some(sweep::parse_sizes(&value)?);
+            }
+            other => return Err(format!("unknown argument '{other}'")),
+        }
+    }
+
+    if contention_bytes.is_some() && !contention {
+        return Err("--contention-bytes sizes the --contention companion".to_string());
+    }
+    if contention && bench_core::json::writes_to_stdout(json_out.as_deref()) {
+        return Err(
+            "--json-out - prints only the JSON record, not the --contention run".to_string(),
+        );
+    }
+    if contention && sweep.is_some() {
+        return Err("--contention runs one fixed-size dataset, so it cannot sweep".to_string());
+    }
+
+    Ok(Command::Benchmark {
+        contention,
+        contention_bytes,
+        csv_out,
+        json_out,
+        pin_core,
+        cold_cache,
+        sweep,
+    })
 }
 
 fn main() {
-    const ELEMENTS_COUNT: usize = 10_000;
-    const MINIMUM_BALANCE: f32 = 250.0;
-    const RANDOM_SEED: u64 = 17;
-    const WARMUP_ITERATIONS: usize = 2;
-    const ITERATIONS: usize = 8;
+    let config = BenchmarkConfig::default();
+    let BenchmarkConfig {
+        elements_count,
+        minimum_balance,
+        random_seed,
+        warmup_iterations,
+        iterations,
+        active_probability,
+    } = config;
+    let minimum_balance = Money(minimum_balance);
+    #[cfg(feature = "serde")]
+    const STREAMING_ELEMENTS_COUNT: usize = 100_000;
 
-    println!();
-    println!("[ Proper Repository Benchmark ]");
-    println!("Elements Count    : {}", ELEMENTS_COUNT);
-    println!("Minimum Balance   : {:.2}", MINIMUM_BALANCE);
-    println!("Random Seed       : {}", RANDOM_SEED);
-    println!("Warmup Iterations : {}", WARMUP_ITERATIONS);
-    println!("Iterations        : {}", ITERATIONS);
+    let (contention_enabled, contention_bytes, csv_out, json_out, pin_core, cold_cache, sweep) =
+        match parse_command(std::env::args().skip(1)) {
+            Ok(Command::Benchmark {
+                contention,
+                contention_bytes,
+                csv_out,
+                json_out,
+                pin_core,
+                cold_cache,
+                sweep,
+            }) => (
+                contention,
+                contention_bytes,
+                csv_out,
+                json_out,
+                pin_core,
+                cold_cache,
+                sweep,
+            ),
+            Ok(Command::SelfTest) => {
+                let users = generate_users(elements_count, random_seed, active_probability);
+                if !self_test::run(&users, minimum_balance) {
+                    std::process::exit(1);
+                }
+                return;
+            }
+            Err(message) => {
+                eprintln!("error: {message}");
+                std::process::exit(2);
+            }
+        };
 
-    let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
-    let balance_dist = rand::distributions::Uniform::new(0.0f32, 1000.0f32);
-    let active_dist = rand::distributions::Bernoulli::new(0.6).unwrap();
+    let implementation = if cold_cache {
+        "repository-p-optimized-cold"
+    } else {
+        "repository-p-optimized"
+    };
 
-    println!();
-    println!("Generating elements...");
-
-    let mut users = Vec::with_capacity(ELEMENTS_COUNT);
-    for i in 0..ELEMENTS_COUNT {
-        let user = User {
-            id: i as i32,
-            balance: rng.sample(balance_dist),
-            active: rng.sample(active_dist),
-        };
-        users.push(user);
+    // Empty unless --cold-cache, so the hot run sweeps nothing.
+    let mut scratch = if cold_cache {
+        vec![0u8; COLD_CACHE_SCRATCH_BYTES]
+    } else {
+        Vec::new()
+    };
+
+    let mut timer = InstantTimer::default();
+
+    // The sweep replaces the fixed-size run.
+    if let Some(sizes) = &sweep {
+        affinity::pin_or_warn(pin_core);
+        let rows = sweep::run(
+            &mut timer,
+            sizes,
+            &config,
+            generate_users,
+            || evict_cache(&mut scratch),
+            VectorUserRepository::new,
+            |repository| sum_active_balances(repository, minimum_balance).raw(),
+        );
+        sweep::print_table("Proper Repository", &rows);
+        if let Some(path) = &csv_out
+            && let Err(message) = sweep::append_csv(path, implementation, random_seed, &rows)
+        {
+            eprintln!("error: {message}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // `--json-out -` leaves stdout to the JSON record alone.
+    let verbose = !bench_core::json::writes_to_stdout(json_out.as_deref());
+
+    if verbose {
+        println!();
+        println!("[ Proper Repository Benchmark ]");
+        println!("Elements Count    : {}", elements_count);
+        println!("Minimum Balance   : {:.2}", minimum_balance);
+        println!("Random Seed       : {}", random_seed);
+        println!("Warmup Iterations : {}", warmup_iterations);
+        println!("Iterations        : {}", iterations);
+        println!("Contention        : {}", contention_enabled);
+        println!("Cold Cache        : {}", cold_cache);
+
+        println!();
+        println!("Generating elements...");
     }
 
+    let users = generate_users(elements_count, random_seed, active_probability);
+
+    #[cfg(feature = "serde")]
+    let json_payload = json::to_json(&users);
+
     let repository = VectorUserRepository::new(users);
 
-    println!();
-    println!("Warming up...");
+    affinity::pin_or_warn(pin_core);
 
-    let mut checksum = 0.0f32;
-    for _ in 0..WARMUP_ITERATIONS {
-        checksum = sum_active_balances(&repository, MINIMUM_BALANCE);
+    if verbose {
+        println!();
+        println!("Warming up...");
     }
 
-    println!();
-    println!("Benchmarking...");
+    let mut checksum = Money::ZERO;
+    for _ in 0..warmup_iterations {
+        checksum = sum_active_balances(&repository, minimum_balance);
+    }
 
-    let total_time_seconds = measure_execution_time(ITERATIONS, || {
-        sum_active_balances(&repository, MINIMUM_BALANCE)
-    });
+    if verbose {
+        println!();
+        println!("Benchmarking...");
+    }
+
+    let stats = measure_execution_time_detailed_with(
+        &mut timer,
+        iterations,
+        || evict_cache(&mut scratch),
+        || sum_active_balances(&repository, minimum_balance),
+    );
+    let total_time_seconds = stats.mean * iterations as f64;
+
+    let elements_per_second = elements_count as f64 / stats.mean;
+
+    if verbose {
+        print_results(
+            "Proper Repository",
+            checksum.raw() as f64,
+            total_time_seconds,
+            iterations,
+            elements_count,
+        );
+        print_bandwidth(elements_count * size_of::<User>(), stats.mean);
+        stats.print();
+        println!();
+    }
+
+    if let Some(path) = &csv_out {
+        let record = CsvRecord {
+            implementation,
+            elements: elements_count,
+            seed: random_seed,
+            iterations,
+            total_time_seconds,
+            checksum: checksum.raw(),
+        };
+        if let Err(message) = csv::append(path, &[record]) {
+            eprintln!("error: {message}");
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(path) = &json_out
+        && let Err(message) = bench_core::json::write_results(
+            path,
+            implementation,
+            &stats,
+            elements_count,
+            checksum.raw() as f64,
+        )
+    {
+        eprintln!("error: {message}");
+        std::process::exit(1);
+    }
+
+    // The comparisons below are not part of the record.
+    if !verbose {
+        return;
+    }
 
-    let average_time_seconds = total_time_seconds / ITERATIONS as f64;
-    let elements_per_second = ELEMENTS_COUNT as f64 / average_time_seconds;
-    let nanoseconds_per_element = (average_time_seconds * 1e9) / ELEMENTS_COUNT as f64;
+    println!("Benchmarking Box<[User]> repository...");
+
+    let boxed_repository = BoxedUserRepository::new(repository.users.clone().into_boxed_slice());
+    let mut boxed_checksum = Money::ZERO;
+    let boxed_time_seconds = measure_execution_time(&mut timer, iterations, || {
+        boxed_checksum = sum_active_balances(&boxed_repository, minimum_balance);
+    });
+    let boxed_elements_per_second =
+        elements_count as f64 / (boxed_time_seconds / iterations as f64);
 
     println!();
-    println!("[ Proper Repository Results ]");
-    println!("Checksum                   : {:.8}", checksum);
-    println!("Total Time                 : {:.2} s", total_time_seconds);
-    println!("Average Time per Iteration : {:.2} s", average_time_seconds);
-    println!("Elements per Second        : {:.2} M", elements_per_second / 1e6);
-    println!("Nanoseconds per Element    : {:.2}", nanoseconds_per_element);
+    println!("[ Box<[User]> vs Vec<User> Results ]");
+    println!("Box Checksum               : {:.8}", boxed_checksum);
+    println!(
+        "Vec Elements per Second    : {:.2} M",
+        elements_per_second / 1e6
+    );
+    println!(
+        "Box Elements per Second    : {:.2} M",
+        boxed_elements_per_second / 1e6
+    );
+    println!(
+        "Box / Vec Throughput       : {:.2}x",
+        boxed_elements_per_second / elements_per_second
+    );
     println!();
+
+    if contention_enabled {
+        println!("Benchmarking with cache-thrashing companion...");
+
+        let thrasher = contention::CacheThrasher::start(
+            contention_bytes.unwrap_or(contention::DEFAULT_BUFFER_BYTES),
+            pin_core,
+        );
+        let contended_time_seconds = measure_execution_time(&mut timer, iterations, || {
+            sum_active_balances(&repository, minimum_balance)
+        });
+        thrasher.stop();
+
+        let contended_elements_per_second =
+            elements_count as f64 / (contended_time_seconds / iterations as f64);
+        let degradation = (1.0 - contended_elements_per_second / elements_per_second) * 100.0;
+
+        println!();
+        println!("[ Proper Repository Results (contended) ]");
+        println!(
+            "Total Time                 : {:.2} s",
+            contended_time_seconds
+        );
+        println!(
+            "Elements per Second        : {:.2} M",
+            contended_elements_per_second / 1e6
+        );
+        println!("Throughput Degradation     : {:.1} %", degradation);
+        println!();
+    }
+
+    #[cfg(feature = "serde")]
+    json::run_json_ingest(&json_payload, minimum_balance, iterations);
+
+    #[cfg(feature = "serde")]
+    json_stream::run_streaming_ingest(
+        &generate_users(STREAMING_ELEMENTS_COUNT, random_seed, active_probability),
+        minimum_balance,
+        iterations,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_repository_is_empty() {
+        let repository = VectorUserRepository::default();
+
+        assert_eq!(repository.count(), 0);
+        assert_eq!(sum_active_balances(&repository, Money::ZERO), Money::ZERO);
+    }
+
+    #[test]
+    fn three_user_sum_from_triples() {
+        let repository = VectorUserRepository::from_triples(&[
+            (0, 100.0, true),
+            (1, 300.0, false),
+            (2, 500.0, true),
+        ]);
+
+        assert_eq!(repository.count(), 3);
+        assert_eq!(sum_active_balances(&repository, Money(250.0)), Money(500.0));
+    }
+
+    #[test]
+    fn boxed_and_vector_repositories_agree() {
+        let users = generate_users(1000, 17, 0.6);
+        let boxed = BoxedUserRepository::new(users.clone().into_boxed_slice());
+        let vector = VectorUserRepository::new(users);
+
+        assert_eq!(
+            sum_active_balances(&boxed, Money(250.0)),
+            sum_active_balances(&vector, Money(250.0))
+        );
+    }
+
+    #[test]
+    fn self_test_is_a_leading_subcommand() {
+        let parse = |list: &[&str]| parse_command(list.iter().map(|arg| arg.to_string()));
+
+        assert_eq!(parse(&["self-test"]), Ok(Command::SelfTest));
+        assert_eq!(
+            parse(&["--contention", "--csv-out", "runs.csv"]),
+            Ok(Command::Benchmark {
+                contention: true,
+                contention_bytes: None,
+                csv_out: Some("runs.csv".to_string()),
+                json_out: None,
+                pin_core: None,
+                cold_cache: false,
+                sweep: None,
+            })
+        );
+        assert_eq!(
+            parse(&["--json-out", "-", "--pin-core", "3", "--cold-cache"]),
+            Ok(Command::Benchmark {
+                contention: false,
+                contention_bytes: None,
+                csv_out: None,
+                json_out: Some("-".to_string()),
+                pin_core: Some(3),
+                cold_cache: true,
+                sweep: None,
+            })
+        );
+        assert_eq!(
+            parse(&["--sweep", "1000,10000"]),
+            Ok(Command::Benchmark {
+                contention: false,
+                contention_bytes: None,
+                csv_out: None,
+                json_out: None,
+                pin_core: None,
+                cold_cache: false,
+                sweep: Some(vec![1_000, 10_000]),
+            })
+        );
+        assert!(parse(&["--contention", "--sweep", "1000"]).is_err());
+        assert!(parse(&["--contention", "--json-out", "-"]).is_err());
+        assert!(parse(&["--contention", "--json-out", "runs.jsonl"]).is_ok());
+        assert!(matches!(
+            parse(&["--contention", "--contention-bytes", "4096"]),
+            Ok(Command::Benchmark {
+                contention: true,
+                contention_bytes: Some(4096),
+                ..
+            })
+        ));
+        assert!(parse(&["--contention-bytes", "4096"]).is_err());
+        assert!(parse(&["--contention", "--contention-bytes", "0"]).is_err());
+        assert!(parse(&["--csv-out"]).is_err());
+        assert!(parse(&["--pin-core", "first"]).is_err());
+        assert!(parse(&["self-test", "--contention"]).is_err());
+        assert!(parse(&["--contention", "self-test"]).is_err());
+    }
 }
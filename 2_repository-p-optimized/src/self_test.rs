@@ -0,0 +1,63 @@
+//! `self-test` subcommand: every compiled-in backend loads the same seed-17
+//! dataset and must reproduce the canonical checksum, so a backend that drifts
+//! (a lossy round trip, a changed filter) is caught with one command.
+
+use bench_core::{CANONICAL_CHECKSUM, Money};
+
+use crate::{User, VectorUserRepository, sum_active_balances};
+
+type LoadBackend = fn(&[User]) -> VectorUserRepository;
+
+fn backends() -> Vec<(&'static str, LoadBackend)> {
+    vec![
+        ("in-memory", |users| {
+            VectorUserRepository::new(users.to_vec())
+        }),
+        #[cfg(feature = "serde")]
+        ("json", |users| {
+            VectorUserRepository::new(crate::json::from_json(&crate::json::to_json(users)))
+        }),
+    ]
+}
+
+/// Prints the pass/fail matrix and returns whether every backend passed.
+pub fn run(users: &[User], minimum_balance: Money) -> bool {
+    let mut all_passed = true;
+
+    println!();
+    println!("[ Self-Test ]");
+    println!("Canonical Checksum : {:.2}", CANONICAL_CHECKSUM);
+    println!();
+    println!("Backend    | Checksum        | Status");
+
+    for (name, load) in backends() {
+        let checksum = sum_active_balances(&load(users), minimum_balance).raw();
+        let passed = checksum == CANONICAL_CHECKSUM;
+        all_passed &= passed;
+
+        println!(
+            "{:<10} | {:>15.2} | {}",
+            name,
+            checksum,
+            if passed { "pass" } else { "FAIL" }
+        );
+    }
+
+    #[cfg(not(feature = "serde"))]
+    println!("Enable the `serde` feature to also check the JSON backend.");
+
+    println!();
+    all_passed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compiled_in_backends_match_canonical_checksum() {
+        let users = crate::generate_users(10_000, 17, 0.6);
+
+        assert!(run(&users, Money(250.0)));
+    }
+}
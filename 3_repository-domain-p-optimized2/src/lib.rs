@@ -0,0 +1,486 @@
+//! The clean-architecture layers, as a library so the Criterion benches can
+//! call the same code the binary times.
+
+use bench_core::{Metric, Money, User};
+
+/// PURE Repository - Only data access concerns
+pub trait UserRepository {
+    /// Find by ID
+    fn find_by_id(&self, id: i32) -> Option<&User>;
+
+    /// Get all users (iterator for memory efficiency)
+    fn find_all(&self) -> std::slice::Iter<'_, User>;
+
+    /// Get `limit` users starting at `offset`, clamped to the stored users
+    fn find_page(&self, offset: usize, limit: usize) -> &[User];
+
+    /// Count total users
+    fn count(&self) -> usize;
+}
+
+/// PURE Repository Implementation - No business logic
+#[derive(Default)]
+pub struct VectorUserRepository {
+    users: Vec<User>,
+}
+
+impl VectorUserRepository {
+    pub fn new(users: Vec<User>) -> Self {
+        Self { users }
+    }
+
+    /// Builds a repository from `(id, balance, active)` rows, without the generator.
+    ///
+    /// ```
+    /// use bench_core::Money;
+    /// use repository_domain_p_optimized2::{UserService, VectorUserRepository};
+    ///
+    /// let repository =
+    ///     VectorUserRepository::from_triples(&[(0, 100.0, true), (1, 300.0, false), (2, 500.0, true)]);
+    /// assert_eq!(UserService::new(repository).sum_active_balances(Money(250.0)), Money(500.0));
+    /// ```
+    pub fn from_triples(rows: &[(i32, f32, bool)]) -> Self {
+        Self::new(
+            rows.iter()
+                .map(|&(id, balance, active)| User {
+                    id,
+                    balance: Money(balance),
+                    active,
+                    score: 0.0,
+                })
+                .collect(),
+        )
+    }
+}
+
+impl UserRepository for VectorUserRepository {
+    fn find_by_id(&self, id: i32) -> Option<&User> {
+        self.users.iter().find(|user| user.id == id)
+    }
+
+    fn find_all(&self) -> std::slice::Iter<'_, User> {
+        self.users.iter()
+    }
+
+    fn find_page(&self, offset: usize, limit: usize) -> &[User] {
+        let start = offset.min(self.users.len());
+        let end = start.saturating_add(limit).min(self.users.len());
+        &self.users[start..end]
+    }
+
+    fn count(&self) -> usize {
+        self.users.len()
+    }
+}
+
+/// DOMAIN SERVICE - Contains business logic
+pub struct UserService<R: UserRepository> {
+    repository: R,
+}
+
+impl<R: UserRepository> UserService<R> {
+    pub fn new(repository: R) -> Self {
+        Self { repository }
+    }
+
+    /// PROPER: Business logic in service layer
+    pub fn sum_active_balances(&self, minimum_balance: Money) -> Money {
+        self.sum_where(|user| self.qualifies_for_sum(user, minimum_balance))
+    }
+
+    /// Same business rule, applied to one page of users only
+    pub fn sum_active_balances_page(
+        &self,
+        offset: usize,
+        limit: usize,
+        minimum_balance: Money,
+    ) -> Money {
+        self.repository
+            .find_page(offset, limit)
+            .iter()
+            .filter(|user| self.qualifies_for_sum(user, minimum_balance))
+            .map(|user| user.balance)
+            .sum()
+    }
+
+    /// How many users the sum rule selects
+    pub fn count_active_above(&self, minimum_balance: Money) -> usize {
+        self.high_value_iter(minimum_balance).count()
+    }
+
+    /// Mean balance of the users the sum rule selects; `None` when there are none
+    pub fn average_active_balance(&self, minimum_balance: Money) -> Option<Money> {
+        match self.count_active_above(minimum_balance) {
+            0 => None,
+            count => Some(Money(
+                self.sum_active_balances(minimum_balance).raw() / count as f32,
+            )),
+        }
+    }
+
+    /// `(active_sum, inactive_sum)` of the balances at or above
+    /// `minimum_balance`, in one pass, to compare the two cohorts. The active
+    /// half adds the same balances in the same order as `sum_active_balances`
+    pub fn sum_by_active(&self, minimum_balance: Money) -> (Money, Money) {
+        self.repository
+            .find_all()
+            .filter(|user| user.balance >= minimum_balance)
+            .fold(
+                (Money::ZERO, Money::ZERO),
+                |(active_sum, inactive_sum), user| {
+                    if user.active {
+                        (active_sum + user.balance, inactive_sum)
+                    } else {
+                        (active_sum, inactive_sum + user.balance)
+                    }
+                },
+            )
+    }
+
+    /// The sum rule applied to any numeric field: the `select`ed values of the
+    /// active users whose value is at least `minimum`. For `Metric::Balance`
+    /// this adds the same balances, in the same order, as `sum_active_balances`
+    pub fn sum_metric(&self, select: Metric, minimum: f32) -> f32 {
+        self.repository
+            .find_all()
+            .filter(|user| user.active)
+            .map(|user| user.value(select))
+            .filter(|&value| value >= minimum)
+            .sum()
+    }
+
+    /// Sums the balances of the users matching an arbitrary business rule, so
+    /// other rules can be benchmarked through the same hot loop.
+    pub fn sum_where<F: Fn(&User) -> bool>(&self, predicate: F) -> Money {
+        self.repository
+            .find_all()
+            .filter(|user| predicate(user))
+            .map(|user| user.balance)
+            .sum()
+    }
+
+    /// PROPER: Business rules encapsulated in domain service
+    fn qualifies_for_sum(&self, user: &User, minimum_balance: Money) -> bool {
+        user.active && user.balance >= minimum_balance
+    }
+
+    /// Additional business operations
+    pub fn get_high_value_users(&self, minimum_balance: Money) -> Vec<&User> {
+        self.high_value_iter(minimum_balance).collect()
+    }
+
+    /// The users the sum rule selects, lazily: `.take(n)` or `.count()`
+    /// without allocating a `Vec<&User>`
+    pub fn high_value_iter(&self, minimum_balance: Money) -> impl Iterator<Item = &User> {
+        self.repository
+            .find_all()
+            .filter(move |user| self.qualifies_for_sum(user, minimum_balance))
+    }
+
+    /// Users whose balance is at or above the `percentile` (0 to 100) of all
+    /// balances, active or not, in repository order: 0 selects everyone and
+    /// 100 only the users holding the maximum. Panics outside 0 to 100.
+    pub fn get_top_percentile_users(&self, percentile: f32) -> Vec<&User> {
+        assert!(
+            (0.0..=100.0).contains(&percentile),
+            "percentile must be between 0 and 100, got {percentile}"
+        );
+
+        let mut balances: Vec<f32> = self
+            .repository
+            .find_all()
+            .map(|user| user.balance.raw())
+            .collect();
+        if balances.is_empty() {
+            return Vec::new();
+        }
+        balances.sort_unstable_by(f32::total_cmp);
+        let rank = percentile_rank(percentile, balances.len());
+        let threshold = Money(balances[rank]);
+
+        self.repository
+            .find_all()
+            .filter(|user| user.balance >= threshold)
+            .collect()
+    }
+}
+
+/// Index of the `percentile` in `len` sorted values. Computed in `f64`: `f32`
+/// rounds `len - 1` up to `len` from 16,777,220 values on, which would put 100
+/// one past the end.
+fn percentile_rank(percentile: f32, len: usize) -> usize {
+    let rank = (f64::from(percentile) / 100.0 * (len - 1) as f64).ceil() as usize;
+    rank.min(len - 1)
+}
+
+/// APPLICATION LAYER - Orchestrates the flow
+#[inline(never)]
+pub fn sum_active_balances<R: UserRepository>(
+    service: &UserService<R>,
+    minimum_balance: Money,
+) -> Money {
+    service.sum_active_balances(minimum_balance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bench_core::{BenchmarkConfig, generate_users, generate_users_allowing_negative};
+
+    #[test]
+    fn default_repository_is_empty() {
+        let service = UserService::new(VectorUserRepository::default());
+
+        assert_eq!(service.repository.count(), 0);
+        assert_eq!(service.sum_active_balances(Money(0.0)), Money::ZERO);
+    }
+
+    #[test]
+    fn three_user_sum_from_triples() {
+        let repository = VectorUserRepository::from_triples(&[
+            (0, 100.0, true),
+            (1, 300.0, false),
+            (2, 500.0, true),
+        ]);
+        let service = UserService::new(repository);
+
+        assert_eq!(service.repository.count(), 3);
+        assert_eq!(service.sum_active_balances(Money(250.0)), Money(500.0));
+    }
+
+    #[test]
+    fn sum_where_with_a_trivial_predicate_sums_every_balance() {
+        let users = generate_users(1_000, 17, 0.6);
+        let expected: Money = users.iter().map(|user| user.balance).sum();
+        let service = UserService::new(VectorUserRepository::new(users));
+
+        assert_eq!(service.sum_where(|_| true), expected);
+        assert_eq!(service.sum_where(|_| false), Money::ZERO);
+    }
+
+    #[test]
+    fn qualifying_count_and_average_follow_the_sum_rule() {
+        let service = UserService::new(VectorUserRepository::from_triples(&[
+            (0, 100.0, true),
+            (1, 300.0, false),
+            (2, 500.0, true),
+            (3, 700.0, true),
+        ]));
+
+        assert_eq!(service.count_active_above(Money(250.0)), 2);
+        assert_eq!(
+            service.average_active_balance(Money(250.0)),
+            Some(Money(600.0))
+        );
+    }
+
+    #[test]
+    fn overdrawn_accounts_only_qualify_below_a_negative_minimum() {
+        let service = UserService::new(VectorUserRepository::from_triples(&[
+            (0, -50.0, true),
+            (1, 0.0, true),
+            (2, 300.0, true),
+            (3, -0.0, true),
+            (4, -200.0, false),
+        ]));
+
+        // A zero minimum takes every active user with a non-negative balance,
+        // including the negative zero.
+        assert_eq!(service.count_active_above(Money(0.0)), 3);
+        assert_eq!(service.count_active_above(Money(250.0)), 1);
+        assert_eq!(service.sum_active_balances(Money(-100.0)), Money(250.0));
+        assert_eq!(service.count_active_above(Money(-1000.0)), 4);
+    }
+
+    #[test]
+    fn zero_minimum_takes_every_active_user_of_a_generated_dataset() {
+        let users = generate_users(1_000, 17, 0.6);
+        let active = users.iter().filter(|user| user.active).count();
+        let overdrawn = UserService::new(VectorUserRepository::new(
+            generate_users_allowing_negative(1_000, 17, 0.6),
+        ));
+        let service = UserService::new(VectorUserRepository::new(users));
+
+        assert_eq!(service.count_active_above(Money::ZERO), active);
+        assert!(overdrawn.count_active_above(Money::ZERO) < active);
+        assert!(
+            overdrawn
+                .get_high_value_users(Money(1.0))
+                .iter()
+                .all(|user| user.balance > Money::ZERO)
+        );
+    }
+
+    #[test]
+    fn cohort_sums_split_every_balance_above_the_threshold() {
+        let service = UserService::new(VectorUserRepository::from_triples(&[
+            (0, 100.0, true),
+            (1, 300.0, false),
+            (2, 500.0, true),
+            (3, 250.0, false),
+            (4, 50.0, false),
+        ]));
+        let (active_sum, inactive_sum) = service.sum_by_active(Money(250.0));
+
+        assert_eq!((active_sum, inactive_sum), (Money(500.0), Money(550.0)));
+        assert_eq!(
+            active_sum + inactive_sum,
+            service.sum_where(|user| user.balance >= Money(250.0))
+        );
+
+        let service = UserService::new(VectorUserRepository::new(generate_users(1_000, 17, 0.6)));
+        let (active_sum, inactive_sum) = service.sum_by_active(Money(250.0));
+        let above = service.sum_where(|user| user.balance >= Money(250.0)).raw();
+
+        assert_eq!(active_sum, service.sum_active_balances(Money(250.0)));
+        assert!(((active_sum + inactive_sum).raw() - above).abs() <= above * 1e-5);
+    }
+
+    #[test]
+    fn metric_sums_apply_the_rule_to_the_selected_field() {
+        let users = generate_users(1_000, 17, 0.6);
+        let expected_scores: f32 = users
+            .iter()
+            .filter(|user| user.active && user.score >= 50.0)
+            .map(|user| user.score)
+            .sum();
+        let service = UserService::new(VectorUserRepository::new(users));
+
+        assert_eq!(
+            service.sum_metric(Metric::Balance, 250.0),
+            service.sum_active_balances(Money(250.0)).raw()
+        );
+        assert_eq!(service.sum_metric(Metric::Score, 50.0), expected_scores);
+        assert_eq!(
+            UserService::new(VectorUserRepository::from_triples(&[(0, 300.0, true)]))
+                .sum_metric(Metric::Score, 0.0),
+            0.0
+        );
+    }
+
+    #[test]
+    fn average_is_none_without_qualifying_users() {
+        let service = UserService::new(VectorUserRepository::from_triples(&[
+            (0, 100.0, true),
+            (1, 300.0, false),
+        ]));
+
+        assert_eq!(service.count_active_above(Money(250.0)), 0);
+        assert_eq!(service.average_active_balance(Money(250.0)), None);
+        assert_eq!(
+            UserService::new(VectorUserRepository::default()).average_active_balance(Money::ZERO),
+            None
+        );
+    }
+
+    #[test]
+    fn high_value_iter_yields_what_the_collecting_version_returns() {
+        let service = UserService::new(VectorUserRepository::new(generate_users(1_000, 17, 0.6)));
+        let collected = service.get_high_value_users(Money(250.0));
+
+        assert_eq!(
+            service.high_value_iter(Money(250.0)).count(),
+            collected.len()
+        );
+        assert!(
+            service
+                .high_value_iter(Money(250.0))
+                .take(10)
+                .eq(collected[..10].iter().copied())
+        );
+    }
+
+    #[test]
+    fn top_percentile_boundaries_select_everyone_and_only_the_max() {
+        let service = UserService::new(VectorUserRepository::from_triples(&[
+            (0, 300.0, true),
+            (1, 900.0, false),
+            (2, 100.0, true),
+            (3, 500.0, false),
+        ]));
+        let ids = |users: Vec<&User>| -> Vec<i32> { users.iter().map(|user| user.id).collect() };
+
+        assert_eq!(ids(service.get_top_percentile_users(0.0)), [0, 1, 2, 3]);
+        assert_eq!(ids(service.get_top_percentile_users(100.0)), [1]);
+        assert_eq!(ids(service.get_top_percentile_users(50.0)), [1, 3]);
+        assert!(
+            UserService::new(VectorUserRepository::default())
+                .get_top_percentile_users(100.0)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn percentile_rank_stays_in_bounds_past_f32_integer_precision() {
+        // 16_777_219 is not an f32; it rounds up to 16_777_220.
+        assert_eq!((16_777_220usize - 1) as f32 as usize, 16_777_220);
+
+        assert_eq!(percentile_rank(100.0, 16_777_220), 16_777_219);
+        assert_eq!(percentile_rank(0.0, 16_777_220), 0);
+        assert_eq!(percentile_rank(50.0, 16_777_221), 8_388_610);
+        assert_eq!(percentile_rank(100.0, 1), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "between 0 and 100")]
+    fn top_percentile_rejects_out_of_range_percentiles() {
+        UserService::new(VectorUserRepository::default()).get_top_percentile_users(100.5);
+    }
+
+    #[test]
+    fn pages_are_clamped_to_the_stored_users() {
+        let repository = VectorUserRepository::from_triples(&[
+            (0, 100.0, true),
+            (1, 300.0, false),
+            (2, 500.0, true),
+        ]);
+
+        let ids = |page: &[User]| -> Vec<i32> { page.iter().map(|user| user.id).collect() };
+        assert_eq!(ids(repository.find_page(1, 1)), [1]);
+        assert_eq!(ids(repository.find_page(1, 10)), [1, 2]);
+        assert_eq!(ids(repository.find_page(0, usize::MAX)), [0, 1, 2]);
+        assert!(repository.find_page(3, 1).is_empty());
+        assert!(repository.find_page(10, 1).is_empty());
+    }
+
+    #[test]
+    fn page_sums_add_up_to_the_full_sum() {
+        let service = UserService::new(VectorUserRepository::new(generate_users(1_000, 17, 0.6)));
+        let minimum_balance = Money(250.0);
+        let full: Money = (0..1_000)
+            .step_by(100)
+            .map(|offset| service.sum_active_balances_page(offset, 100, minimum_balance))
+            .sum();
+        let full = full.raw();
+
+        assert!((full - service.sum_active_balances(minimum_balance).raw()).abs() <= full * 1e-6);
+        assert_eq!(
+            service.sum_active_balances_page(0, 1_000, minimum_balance),
+            service.sum_active_balances(minimum_balance)
+        );
+        assert_eq!(
+            service.sum_active_balances_page(900, 500, minimum_balance),
+            service.sum_active_balances_page(900, 100, minimum_balance)
+        );
+        assert_eq!(
+            service.sum_active_balances_page(1_000, 10, minimum_balance),
+            Money::ZERO
+        );
+    }
+
+    #[test]
+    fn canonical_dataset_matches_the_shared_checksum() {
+        let config = BenchmarkConfig::default();
+        let repository = VectorUserRepository::new(generate_users(
+            config.elements_count,
+            config.random_seed,
+            0.6,
+        ));
+        let service = UserService::new(repository);
+
+        assert_eq!(
+            sum_active_balances(&service, Money(config.minimum_balance)).raw(),
+            bench_core::CANONICAL_CHECKSUM
+        );
+    }
+}
@@ -1,161 +1,179 @@
-use std::time::Instant;
-use rand::prelude::*;
-
-#[derive(Debug, Clone)]
-struct User {
-    id: i32,
-    balance: f32,
-    active: bool,
-}
-
-/// PURE Repository - Only data access concerns
-trait UserRepository {
-    /// Find by ID
-    fn find_by_id(&self, id: i32) -> Option<&User>;
-
-    /// Get all users (iterator for memory efficiency)
-    fn find_all(&self) -> std::slice::Iter<User>;
-
-    /// Count total users
-    fn count(&self) -> usize;
-}
-
-/// PURE Repository Implementation - No business logic
-struct VectorUserRepository {
-    users: Vec<User>,
-}
+use bench_core::csv::{self, CsvRecord};
+use bench_core::timer::InstantTimer;
+use bench_core::{
+    BenchmarkConfig, COLD_CACHE_SCRATCH_BYTES, CommonFlags, Money, User, evict_cache,
+    generate_users, generate_users_allowing_negative, measure_execution_time_detailed_with,
+    parse_common_flags, print_bandwidth, print_results,
+};
+use bench_core::{affinity, json, sweep};
+use repository_domain_p_optimized2::{UserService, VectorUserRepository, sum_active_balances};
 
-impl VectorUserRepository {
-    fn new(users: Vec<User>) -> Self {
-        Self { users }
-    }
-}
-
-impl UserRepository for VectorUserRepository {
-    fn find_by_id(&self, id: i32) -> Option<&User> {
-        self.users.iter().find(|user| user.id == id)
+fn main() {
+    let config = BenchmarkConfig::default();
+    let BenchmarkConfig {
+        elements_count,
+        minimum_balance,
+        random_seed,
+        warmup_iterations,
+        iterations,
+        active_probability,
+    } = config;
+
+    let CommonFlags {
+        csv_out,
+        json_out,
+        pin_core,
+        cold_cache,
+        sweep,
+        allow_negative,
+    } = match parse_common_flags(std::env::args().skip(1)) {
+        Ok(flags) => flags,
+        Err(message) => {
+            eprintln!("error: {message}");
+            std::process::exit(2);
+        }
+    };
+
+    let implementation = if cold_cache {
+        "repository-domain-p-optimized2-cold"
+    } else {
+        "repository-domain-p-optimized2"
+    };
+
+    // Empty unless --cold-cache, so the hot run sweeps nothing.
+    let mut scratch = if cold_cache {
+        vec![0u8; COLD_CACHE_SCRATCH_BYTES]
+    } else {
+        Vec::new()
+    };
+
+    let generate: fn(usize, u64, f64) -> Vec<User> = if allow_negative {
+        generate_users_allowing_negative
+    } else {
+        generate_users
+    };
+
+    let mut timer = InstantTimer::default();
+
+    // The sweep replaces the fixed-size run.
+    if let Some(sizes) = &sweep {
+        affinity::pin_or_warn(pin_core);
+        let minimum_balance = Money(minimum_balance);
+        let rows = sweep::run(
+            &mut timer,
+            sizes,
+            &config,
+            generate,
+            || evict_cache(&mut scratch),
+            |users| UserService::new(VectorUserRepository::new(users)),
+            |service| sum_active_balances(service, minimum_balance).raw(),
+        );
+        sweep::print_table("Clean Architecture", &rows);
+        if let Some(path) = &csv_out
+            && let Err(message) = sweep::append_csv(path, implementation, random_seed, &rows)
+        {
+            eprintln!("error: {message}");
+            std::process::exit(1);
+        }
+        return;
     }
 
-    fn find_all(&self) -> std::slice::Iter<User> {
-        self.users.iter()
+    // `--json-out -` leaves stdout to the JSON record alone.
+    let verbose = !json::writes_to_stdout(json_out.as_deref());
+
+    if verbose {
+        println!();
+        println!("[ Clean Architecture Repository Benchmark ]");
+        println!("Elements Count    : {}", elements_count);
+        println!("Minimum Balance   : {:.2}", minimum_balance);
+        println!("Random Seed       : {}", random_seed);
+        println!("Warmup Iterations : {}", warmup_iterations);
+        println!("Iterations        : {}", iterations);
+        println!("Cold Cache        : {}", cold_cache);
+        println!("Allow Negative    : {}", allow_negative);
+
+        println!();
+        println!("Generating elements...");
     }
 
-    fn count(&self) -> usize {
-        self.users.len()
-    }
-}
+    // Clean Architecture Layers
+    let repository =
+        VectorUserRepository::new(generate(elements_count, random_seed, active_probability));
+    let service = UserService::new(repository);
 
-/// DOMAIN SERVICE - Contains business logic
-struct UserService<R: UserRepository> {
-    repository: R,
-}
+    let minimum_balance = Money(minimum_balance);
 
-impl<R: UserRepository> UserService<R> {
-    fn new(repository: R) -> Self {
-        Self { repository }
-    }
+    affinity::pin_or_warn(pin_core);
 
-    /// PROPER: Business logic in service layer
-    fn sum_active_balances(&self, minimum_balance: f32) -> f32 {
-        self.repository
-            .find_all()
-            .filter(|user| self.qualifies_for_sum(user, minimum_balance))
-            .map(|user| user.balance)
-            .sum()
+    if verbose {
+        println!();
+        println!("Warming up...");
     }
 
-    /// PROPER: Business rules encapsulated in domain service
-    fn qualifies_for_sum(&self, user: &User, minimum_balance: f32) -> bool {
-        user.active && user.balance >= minimum_balance
+    let mut checksum = Money::ZERO;
+    for _ in 0..warmup_iterations {
+        checksum = sum_active_balances(&service, minimum_balance);
     }
 
-    /// Additional business operations
-    fn get_high_value_users(&self, minimum_balance: f32) -> Vec<&User> {
-        self.repository
-            .find_all()
-            .filter(|user| self.qualifies_for_sum(user, minimum_balance))
-            .collect()
+    if verbose {
+        println!();
+        println!("Benchmarking...");
     }
-}
 
-/// APPLICATION LAYER - Orchestrates the flow
-#[inline(never)]
-fn sum_active_balances<R: UserRepository>(service: &UserService<R>, minimum_balance: f32) -> f32 {
-    service.sum_active_balances(minimum_balance)
-}
-
-fn measure_execution_time<F, R>(iterations: usize, mut f: F) -> f64
-where
-    F: FnMut() -> R,
-{
-    let start = Instant::now();
-    for _ in 0..iterations {
-        let _ = f();
+    let stats = measure_execution_time_detailed_with(
+        &mut timer,
+        iterations,
+        || evict_cache(&mut scratch),
+        || sum_active_balances(&service, minimum_balance),
+    );
+    let total_time_seconds = stats.mean * iterations as f64;
+
+    if verbose {
+        print_results(
+            "Clean Architecture",
+            checksum.raw() as f64,
+            total_time_seconds,
+            iterations,
+            elements_count,
+        );
+        print_bandwidth(elements_count * size_of::<User>(), stats.mean);
+        stats.print();
+        println!(
+            "Qualifying Users           : {}",
+            service.count_active_above(minimum_balance)
+        );
+        match service.average_active_balance(minimum_balance) {
+            Some(average) => println!("Average Qualifying Balance : {:.2}", average),
+            None => println!("Average Qualifying Balance : n/a"),
+        }
+        println!();
     }
-    start.elapsed().as_secs_f64()
-}
 
-fn main() {
-    const ELEMENTS_COUNT: usize = 10_000;
-    const MINIMUM_BALANCE: f32 = 250.0;
-    const RANDOM_SEED: u64 = 17;
-    const WARMUP_ITERATIONS: usize = 2;
-    const ITERATIONS: usize = 8;
-
-    println!();
-    println!("[ Clean Architecture Repository Benchmark ]");
-    println!("Elements Count    : {}", ELEMENTS_COUNT);
-    println!("Minimum Balance   : {:.2}", MINIMUM_BALANCE);
-    println!("Random Seed       : {}", RANDOM_SEED);
-    println!("Warmup Iterations : {}", WARMUP_ITERATIONS);
-    println!("Iterations        : {}", ITERATIONS);
-
-    let mut rng = StdRng::seed_from_u64(RANDOM_SEED);
-    let balance_dist = rand::distributions::Uniform::new(0.0f32, 1000.0f32);
-    let active_dist = rand::distributions::Bernoulli::new(0.6).unwrap();
-
-    println!();
-    println!("Generating elements...");
-
-    let mut users = Vec::with_capacity(ELEMENTS_COUNT);
-    for i in 0..ELEMENTS_COUNT {
-        let user = User {
-            id: i as i32,
-            balance: rng.sample(balance_dist),
-            active: rng.sample(active_dist),
+    if let Some(path) = &csv_out {
+        let record = CsvRecord {
+            implementation,
+            elements: elements_count,
+            seed: random_seed,
+            iterations,
+            total_time_seconds,
+            checksum: checksum.raw(),
         };
-        users.push(user);
+        if let Err(message) = csv::append(path, &[record]) {
+            eprintln!("error: {message}");
+            std::process::exit(1);
+        }
     }
 
-    // Clean Architecture Layers
-    let repository = VectorUserRepository::new(users);
-    let service = UserService::new(repository);
-
-    println!();
-    println!("Warming up...");
-
-    let mut checksum = 0.0f32;
-    for _ in 0..WARMUP_ITERATIONS {
-        checksum = sum_active_balances(&service, MINIMUM_BALANCE);
+    if let Some(path) = &json_out
+        && let Err(message) = json::write_results(
+            path,
+            implementation,
+            &stats,
+            elements_count,
+            checksum.raw() as f64,
+        )
+    {
+        eprintln!("error: {message}");
+        std::process::exit(1);
     }
-
-    println!();
-    println!("Benchmarking...");
-
-    let total_time_seconds = measure_execution_time(ITERATIONS, || {
-        sum_active_balances(&service, MINIMUM_BALANCE)
-    });
-
-    let average_time_seconds = total_time_seconds / ITERATIONS as f64;
-    let elements_per_second = ELEMENTS_COUNT as f64 / average_time_seconds;
-    let nanoseconds_per_element = (average_time_seconds * 1e9) / ELEMENTS_COUNT as f64;
-
-    println!();
-    println!("[ Clean Architecture Results ]");
-    println!("Checksum                   : {:.8}", checksum);
-    println!("Total Time                 : {:.2} s", total_time_seconds);
-    println!("Average Time per Iteration : {:.2} s", average_time_seconds);
-    println!("Elements per Second        : {:.2} M", elements_per_second / 1e6);
-    println!("Nanoseconds per Element    : {:.2}", nanoseconds_per_element);
-    println!();
 }
@@ -0,0 +1,113 @@
+//! `--pin-core <n>`: keeps the benchmark thread on one CPU so the scheduler
+//! cannot migrate it mid-run and throw away its warm caches.
+//!
+//! Only Linux is supported (`sched_setaffinity`); elsewhere pinning reports
+//! `Unsupported` and the binaries run unpinned.
+
+use std::io;
+
+/// Restricts the calling thread to `core`. Threads it spawns afterwards
+/// inherit the restriction.
+#[cfg(target_os = "linux")]
+pub fn pin_to_core(core: usize) -> io::Result<()> {
+    if core >= libc::CPU_SETSIZE as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("core {core} is beyond CPU_SETSIZE ({})", libc::CPU_SETSIZE),
+        ));
+    }
+
+    // SAFETY: `cpu_set_t` is a plain bitmask, so all zeroes is the empty set;
+    // `core` was checked against its capacity above, and pid 0 is the calling
+    // thread.
+    let result = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(core, &mut set);
+        libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set)
+    };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Lets the calling thread run on every CPU except `core`, so a companion
+/// spawned by a pinned benchmark thread does not share its core. CPUs the
+/// process may not use are dropped by the kernel; if `core` was the only one
+/// left, this fails and the restriction stays as it was.
+#[cfg(target_os = "linux")]
+pub fn avoid_core(core: usize) -> io::Result<()> {
+    if core >= libc::CPU_SETSIZE as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("core {core} is beyond CPU_SETSIZE ({})", libc::CPU_SETSIZE),
+        ));
+    }
+
+    // SAFETY: as in `pin_to_core`; every index set is below `CPU_SETSIZE`.
+    let result = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for cpu in (0..libc::CPU_SETSIZE as usize).filter(|&cpu| cpu != core) {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set)
+    };
+
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_to_core(_core: usize) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "thread pinning is only supported on Linux",
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn avoid_core(_core: usize) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "thread pinning is only supported on Linux",
+    ))
+}
+
+/// Pins to `core` if one was requested. A failure is only a warning: an
+/// unpinned run is noisier, not wrong.
+pub fn pin_or_warn(core: Option<usize>) {
+    if let Some(core) = core
+        && let Err(error) = pin_to_core(core)
+    {
+        eprintln!("warning: cannot pin to core {core}, running unpinned: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_core_that_cannot_exist_is_an_error() {
+        // On a spawned thread, so a bug that did pin would not pin the harness.
+        let result = std::thread::spawn(|| pin_to_core(usize::MAX))
+            .join()
+            .unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn avoiding_a_core_that_cannot_exist_is_an_error() {
+        let result = std::thread::spawn(|| avoid_core(usize::MAX))
+            .join()
+            .unwrap();
+
+        assert!(result.is_err());
+    }
+}
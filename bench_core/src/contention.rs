@@ -0,0 +1,71 @@
+//! Noisy-neighbour companion thread.
+//!
+//! While running, the thread keeps dirtying one byte per cache line of a buffer
+//! much larger than the last-level cache, so the benchmark's data is evicted
+//! continuously and the scan has to compete for memory bandwidth.
+//!
+//! The buffer size is `--contention-bytes`; the default is large enough to
+//! exceed the last-level cache of common desktop and server parts.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+
+use crate::affinity;
+
+pub const DEFAULT_BUFFER_BYTES: usize = 64 * 1024 * 1024;
+
+const CACHE_LINE_BYTES: usize = 64;
+
+pub struct CacheThrasher {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<u64>,
+}
+
+impl CacheThrasher {
+    /// Spawns the companion and returns once it has completed its first pass,
+    /// so measurements taken afterwards already run under contention.
+    ///
+    /// Pass the benchmark's `--pin-core` as `pinned_core`: the companion would
+    /// otherwise inherit that pin and time-slice the benchmark's core instead
+    /// of running beside it, so it moves to every other core first.
+    pub fn start(buffer_bytes: usize, pinned_core: Option<usize>) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thrashing = Arc::new(AtomicBool::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let thread_thrashing = Arc::clone(&thrashing);
+
+        let handle = thread::spawn(move || {
+            if let Some(core) = pinned_core
+                && let Err(error) = affinity::avoid_core(core)
+            {
+                eprintln!("warning: cache thrasher shares pinned core {core}: {error}");
+            }
+            let mut buffer = vec![0u8; buffer_bytes];
+            let mut passes = 0u64;
+
+            while !thread_stop.load(Ordering::Relaxed) {
+                for i in (0..buffer.len()).step_by(CACHE_LINE_BYTES) {
+                    buffer[i] = buffer[i].wrapping_add(1);
+                }
+                passes += 1;
+                thread_thrashing.store(true, Ordering::Release);
+            }
+
+            std::hint::black_box(&buffer);
+            passes
+        });
+
+        while !thrashing.load(Ordering::Acquire) {
+            thread::yield_now();
+        }
+
+        Self { stop, handle }
+    }
+
+    /// Stops the companion and returns how many full passes it made.
+    pub fn stop(self) -> u64 {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.join().expect("cache thrasher thread panicked")
+    }
+}
@@ -0,0 +1,272 @@
+//! `--csv-out <file>`: one row per benchmarked implementation, appended to a
+//! file shared by every binary so runs of all variants land in one sheet.
+//! Under `minimal/` only `aos-layout-p` and `layout-switch-p` write rows: the
+//! other programs time single updates or lookups, not an iterated scan with a
+//! checksum, so they have nothing to fill a row with.
+//!
+//! `--dataset-csv <file>`: users produced elsewhere, as `id,balance,active`
+//! rows, in place of the generator.
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+
+use crate::{Money, User};
+
+pub const HEADER: &str = "implementation,elements,seed,iterations,total_time_s,average_time_s,\
+                          elements_per_second,ns_per_element,checksum";
+
+/// The measured figures of one run; the per-iteration and per-element
+/// columns are derived from them.
+#[derive(Debug, Clone, Copy)]
+pub struct CsvRecord<'a> {
+    pub implementation: &'a str,
+    pub elements: usize,
+    pub seed: u64,
+    pub iterations: usize,
+    pub total_time_seconds: f64,
+    pub checksum: f32,
+}
+
+fn write_row(w: &mut impl Write, record: &CsvRecord) -> io::Result<()> {
+    let average_time_seconds = record.total_time_seconds / record.iterations as f64;
+
+    writeln!(
+        w,
+        "{},{},{},{},{:.9},{:.9},{:.2},{:.4},{:.2}",
+        record.implementation,
+        record.elements,
+        record.seed,
+        record.iterations,
+        record.total_time_seconds,
+        average_time_seconds,
+        record.elements as f64 / average_time_seconds,
+        average_time_seconds * 1e9 / record.elements as f64,
+        record.checksum
+    )
+}
+
+/// Appends `records` to `path`, writing the header first if the file is new
+/// or empty.
+pub fn append(path: &str, records: &[CsvRecord]) -> Result<(), String> {
+    let write = || -> io::Result<()> {
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        if file.metadata()?.len() == 0 {
+            writeln!(file, "{HEADER}")?;
+        }
+        for record in records {
+            write_row(&mut file, record)?;
+        }
+        Ok(())
+    };
+
+    write().map_err(|error| format!("cannot append results to '{path}': {error}"))
+}
+
+/// Why a users CSV, or one of its rows, could not be read.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CsvError {
+    Io(String),
+    MissingHeader,
+    MissingColumn(&'static str),
+    /// A malformed row; `line` counts from 1, header included.
+    Row {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(message) => write!(f, "{message}"),
+            Self::MissingHeader => write!(f, "no header row"),
+            Self::MissingColumn(name) => write!(f, "header has no '{name}' column"),
+            Self::Row { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+/// Parses users from CSV text whose header names the `id`, `balance` and
+/// `active` columns, and optionally `score`, in any order; other columns are
+/// ignored and a missing score is zero. Fields are plain, unquoted values,
+/// and `active` takes `0`, `1`, `true` or `false`.
+///
+/// Malformed rows are skipped and returned next to the users, so the caller
+/// decides how to report them.
+pub fn parse_users_csv(text: &str) -> Result<(Vec<User>, Vec<CsvError>), CsvError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let (_, header) = lines.next().ok_or(CsvError::MissingHeader)?;
+    let columns: Vec<String> = header
+        .split(',')
+        .map(|name| name.trim().to_ascii_lowercase())
+        .collect();
+    let column = |name: &'static str| {
+        columns
+            .iter()
+            .position(|column| column == name)
+            .ok_or(CsvError::MissingColumn(name))
+    };
+    let positions = [column("id")?, column("balance")?, column("active")?];
+    let score_position = column("score").ok();
+
+    let mut users = Vec::new();
+    let mut skipped = Vec::new();
+    for (line, row) in lines {
+        let fields: Vec<&str> = row.split(',').map(str::trim).collect();
+        match parse_user(&fields, columns.len(), positions, score_position) {
+            Ok(user) => users.push(user),
+            Err(message) => skipped.push(CsvError::Row { line, message }),
+        }
+    }
+
+    Ok((users, skipped))
+}
+
+/// `positions` holds the `id`, `balance` and `active` column indices.
+fn parse_user(
+    fields: &[&str],
+    width: usize,
+    positions: [usize; 3],
+    score_position: Option<usize>,
+) -> Result<User, String> {
+    if fields.len() != width {
+        return Err(format!("expected {width} fields, got {}", fields.len()));
+    }
+
+    let [id, balance, active] = positions.map(|position| fields[position]);
+    Ok(User {
+        id: id.parse().map_err(|_| format!("invalid id '{id}'"))?,
+        balance: Money(
+            balance
+                .parse()
+                .map_err(|_| format!("invalid balance '{balance}'"))?,
+        ),
+        active: parse_active(active)
+            .ok_or_else(|| format!("invalid active '{active}', expected 0/1/true/false"))?,
+        score: match score_position.map(|position| fields[position]) {
+            Some(score) => score
+                .parse()
+                .map_err(|_| format!("invalid score '{score}'"))?,
+            None => 0.0,
+        },
+    })
+}
+
+fn parse_active(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "1" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Reads `path` with `parse_users_csv`, printing a warning for every
+/// skipped row.
+pub fn load_users_csv(path: &str) -> Result<Vec<User>, CsvError> {
+    let text = std::fs::read_to_string(path).map_err(|error| CsvError::Io(error.to_string()))?;
+    let (users, skipped) = parse_users_csv(&text)?;
+    for error in &skipped {
+        eprintln!("warning: {path}: skipped {error}");
+    }
+    Ok(users)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn users_csv_skips_malformed_rows_with_their_line_numbers() {
+        let text = "\
+active, id ,balance
+1,0,100.5
+true,1,abc
+
+false,2,300
+yes,3,400
+0,4
+";
+
+        let (users, skipped) = parse_users_csv(text).unwrap();
+
+        assert_eq!(
+            users,
+            [
+                User {
+                    id: 0,
+                    balance: Money(100.5),
+                    active: true,
+                    score: 0.0,
+                },
+                User {
+                    id: 2,
+                    balance: Money(300.0),
+                    active: false,
+                    score: 0.0,
+                },
+            ]
+        );
+        let lines: Vec<String> = skipped.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            [
+                "line 3: invalid balance 'abc'",
+                "line 6: invalid active 'yes', expected 0/1/true/false",
+                "line 7: expected 3 fields, got 2",
+            ]
+        );
+        assert_eq!(
+            parse_users_csv("id,active\n0,1\n"),
+            Err(CsvError::MissingColumn("balance"))
+        );
+        assert_eq!(parse_users_csv("\n"), Err(CsvError::MissingHeader));
+
+        let (users, skipped) =
+            parse_users_csv("id,balance,active,score\n0,1,1,42.5\n1,2,0,x\n").unwrap();
+        assert_eq!(users[0].score, 42.5);
+        assert_eq!(skipped[0].to_string(), "line 3: invalid score 'x'");
+    }
+
+    #[test]
+    fn header_is_written_once_and_rows_are_appended() {
+        let path = std::env::temp_dir().join(format!("bench-core-{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        let record = CsvRecord {
+            implementation: "dod",
+            elements: 1000,
+            seed: 17,
+            iterations: 4,
+            total_time_seconds: 0.000_008,
+            checksum: 2.5,
+        };
+
+        append(path, &[record]).unwrap();
+        append(
+            path,
+            &[CsvRecord {
+                implementation: "repository",
+                ..record
+            }],
+        )
+        .unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], HEADER);
+        assert_eq!(
+            lines[1],
+            "dod,1000,17,4,0.000008000,0.000002000,500000000.00,2.0000,2.50"
+        );
+        assert!(lines[2].starts_with("repository,1000,"));
+    }
+}
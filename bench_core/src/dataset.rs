@@ -0,0 +1,59 @@
+//! `--dataset <file.json>`: a fixed user list saved to disk, so runs can be
+//! reproduced without relying on the generator and its seed.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+
+use crate::User;
+
+/// Reads a JSON array of users written by `save_users_json`.
+pub fn load_users_json(path: &str) -> io::Result<Vec<User>> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_json::from_reader(reader)?)
+}
+
+pub fn save_users_json(users: &[User], path: &str) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(&mut writer, users)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Money;
+
+    #[test]
+    fn users_round_trip_through_a_file() {
+        let path = std::env::temp_dir().join(format!("bench-core-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let users = [
+            User {
+                id: 0,
+                balance: Money(100.25),
+                active: true,
+                score: 12.5,
+            },
+            User {
+                id: 1,
+                balance: Money(999.9),
+                active: false,
+                score: 0.0,
+            },
+        ];
+
+        save_users_json(&users, path).unwrap();
+        let loaded = load_users_json(path);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded.unwrap(), users);
+    }
+
+    #[test]
+    fn files_without_scores_load_with_zero() {
+        let users: Vec<User> =
+            serde_json::from_str(r#"[{"id":0,"balance":1.5,"active":true}]"#).unwrap();
+
+        assert_eq!(users[0].score, 0.0);
+    }
+}
@@ -0,0 +1,120 @@
+//! `--json-out <file|->`: one JSON object per benchmarked implementation, so
+//! a script can diff results between commits without parsing the text block.
+//!
+//! Objects are written one per line (JSON Lines) and appended like the CSV
+//! rows, so every binary can share one file. With `-` the objects are all a
+//! binary writes to stdout: its text report is dropped, see `writes_to_stdout`.
+
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+
+use crate::Stats;
+
+/// Writes one JSON object with the measured figures, followed by a newline.
+pub fn emit_json_results(
+    w: &mut impl Write,
+    label: &str,
+    stats: &Stats,
+    elements: usize,
+    checksum: f64,
+) -> io::Result<()> {
+    writeln!(
+        w,
+        "{{\"label\":{},\"elements\":{},\"checksum\":{},\"mean_s\":{},\"median_s\":{},\
+         \"min_s\":{},\"max_s\":{},\"stddev_s\":{},\"elements_per_second\":{},\
+         \"ns_per_element\":{}}}",
+        string(label),
+        elements,
+        number(checksum),
+        number(stats.mean),
+        number(stats.median),
+        number(stats.min),
+        number(stats.max),
+        number(stats.stddev),
+        number(elements as f64 / stats.mean),
+        number(stats.mean * 1e9 / elements as f64)
+    )
+}
+
+/// Whether `--json-out <path>` sends the objects to stdout. A binary must
+/// then print nothing else there, or the stream no longer parses as JSON Lines.
+pub fn writes_to_stdout(path: Option<&str>) -> bool {
+    path == Some("-")
+}
+
+/// Writes the object to stdout when `path` is `-`, else appends it to `path`.
+pub fn write_results(
+    path: &str,
+    label: &str,
+    stats: &Stats,
+    elements: usize,
+    checksum: f64,
+) -> Result<(), String> {
+    let write = || -> io::Result<()> {
+        if path == "-" {
+            emit_json_results(&mut io::stdout().lock(), label, stats, elements, checksum)
+        } else {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            emit_json_results(&mut file, label, stats, elements, checksum)
+        }
+    };
+
+    write().map_err(|error| format!("cannot write JSON results to '{path}': {error}"))
+}
+
+/// JSON has no NaN or infinity, so those become `null`.
+fn number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        "null".to_string()
+    }
+}
+
+fn string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_are_one_json_object_per_line() {
+        let stats = Stats::from_samples(&[0.000_002, 0.000_002]);
+        let mut out = Vec::new();
+
+        emit_json_results(&mut out, "DoD \"f32\"\n", &stats, 1000, 2.5).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"label\":\"DoD \\\"f32\\\"\\u000a\",\"elements\":1000,\"checksum\":2.5,\
+             \"mean_s\":0.000002,\"median_s\":0.000002,\"min_s\":0.000002,\"max_s\":0.000002,\
+             \"stddev_s\":0,\"elements_per_second\":500000000,\"ns_per_element\":2}\n"
+        );
+    }
+
+    #[test]
+    fn non_finite_figures_become_null() {
+        let stats = Stats::from_samples(&[0.0]);
+        let mut out = Vec::new();
+
+        emit_json_results(&mut out, "empty", &stats, 0, f64::NAN).unwrap();
+        let text = String::from_utf8(out).unwrap();
+
+        assert!(text.contains("\"checksum\":null"));
+        assert!(text.contains("\"elements_per_second\":null"));
+        assert!(text.contains("\"ns_per_element\":null"));
+    }
+}
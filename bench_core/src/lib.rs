@@ -0,0 +1,1021 @@
+//! Pieces every benchmark binary shares: the canonical `User` row and its
+//! generator, the default benchmark parameters and the timing loop.
+
+pub mod affinity;
+pub mod contention;
+pub mod csv;
+#[cfg(feature = "serde")]
+pub mod dataset;
+pub mod json;
+pub mod rng;
+pub mod sweep;
+pub mod timer;
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, MulAssign};
+use std::time::{Duration, Instant};
+
+use rng::{Bernoulli, Pcg32, Uniform};
+use timer::Timer;
+
+/// One user row. The AoS benchmarks store these directly; the DoD ones split
+/// the same fields into columns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct User {
+    pub id: i32,
+    pub balance: Money,
+    pub active: bool,
+    /// A second figure per user, unrelated to the balance. Dataset files
+    /// written before it existed load with a score of zero.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub score: f32,
+}
+
+/// The numeric fields of a `User` a scan can select.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Balance,
+    Score,
+}
+
+impl User {
+    pub fn value(&self, metric: Metric) -> f32 {
+        match metric {
+            Metric::Balance => self.balance.raw(),
+            Metric::Score => self.score,
+        }
+    }
+}
+
+/// A balance or a threshold on balances, kept apart from the other `f32`s so
+/// they cannot be swapped at a call site. It is `repr(transparent)` and
+/// serializes as the bare number, so rows and dataset files are laid out
+/// exactly as with a plain `f32`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+#[repr(transparent)]
+pub struct Money(pub f32);
+
+impl Money {
+    pub const ZERO: Self = Self(0.0);
+
+    /// The bare amount, for the benchmark math: checksums, rates and columns.
+    pub fn raw(self) -> f32 {
+        self.0
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl Add for Money {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
+
+/// Scaling by a plain factor, e.g. the 0/1 mask of a branchless filter.
+impl Mul<f32> for Money {
+    type Output = Self;
+
+    fn mul(self, factor: f32) -> Self {
+        Self(self.0 * factor)
+    }
+}
+
+impl MulAssign<f32> for Money {
+    fn mul_assign(&mut self, factor: f32) {
+        self.0 *= factor;
+    }
+}
+
+/// Adds in iteration order from zero, like the scans' own loops.
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Self>>(amounts: I) -> Self {
+        amounts.fold(Self::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Money>>(amounts: I) -> Self {
+        amounts.copied().sum()
+    }
+}
+
+/// What the scans need from a record, so a record type other than `User`
+/// can reuse them along with `measure_execution_time` and `print_results`.
+pub trait Entity {
+    fn id(&self) -> i32;
+    /// The value the scans add up, e.g. a balance.
+    fn metric(&self) -> f32;
+    /// Whether the record takes part at all, e.g. an active user.
+    fn included(&self) -> bool;
+}
+
+impl Entity for User {
+    fn id(&self) -> i32 {
+        self.id
+    }
+
+    fn metric(&self) -> f32 {
+        self.balance.raw()
+    }
+
+    fn included(&self) -> bool {
+        self.active
+    }
+}
+
+/// PCG stream the scores are drawn from, so adding them left the balance and
+/// active draws, and with them `CANONICAL_CHECKSUM`, unchanged.
+const SCORE_STREAM: u64 = 0x5c0e_5c0e_5c0e_5c0e;
+
+/// Most users a generator can number: ids are `i32`, so user `i32::MAX` is
+/// the last one whose id does not wrap negative.
+pub const MAX_USERS: usize = i32::MAX as usize + 1;
+
+/// The dataset every binary benchmarks: user `i` has id `i`, a balance drawn
+/// uniformly from `[0, 1000)`, is active with `active_probability` and has a
+/// score drawn uniformly from `[0, 100)`.
+pub fn generate_users(count: usize, seed: u64, active_probability: f64) -> Vec<User> {
+    generate_users_with_rng(count, active_probability, &mut Pcg32::seed_from_u64(seed))
+}
+
+/// `generate_users` with balances drawn from `[-1000, 1000)`, so about half
+/// the accounts are overdrawn. It makes the same draws in the same order, so
+/// ids and active flags match the standard dataset of the same seed.
+pub fn generate_users_allowing_negative(
+    count: usize,
+    seed: u64,
+    active_probability: f64,
+) -> Vec<User> {
+    generate_users_allowing_negative_with_rng(
+        count,
+        active_probability,
+        &mut Pcg32::seed_from_u64(seed),
+    )
+}
+
+/// `generate_users_allowing_negative` drawing from `rng`; it advances `rng`
+/// exactly as `generate_users_with_rng` does.
+pub fn generate_users_allowing_negative_with_rng(
+    count: usize,
+    active_probability: f64,
+    rng: &mut Pcg32,
+) -> Vec<User> {
+    generate_users_from(
+        count,
+        active_probability,
+        Uniform::new(-1000.0f32, 1000.0f32),
+        rng,
+    )
+}
+
+/// `generate_users` that refuses counts above `MAX_USERS` instead of handing
+/// out wrapped, duplicate ids.
+pub fn try_generate_users(
+    count: usize,
+    seed: u64,
+    active_probability: f64,
+) -> Result<Vec<User>, ConfigError> {
+    if count > MAX_USERS {
+        return Err(ConfigError::TooManyElements(count));
+    }
+    Ok(generate_users(count, seed, active_probability))
+}
+
+/// `generate_users` drawing from `rng`, for callers that keep sampling from
+/// the same stream afterwards. The scores come from a stream forked off
+/// `rng`, which only advances by the balance and active draws.
+pub fn generate_users_with_rng(
+    count: usize,
+    active_probability: f64,
+    rng: &mut Pcg32,
+) -> Vec<User> {
+    generate_users_from(
+        count,
+        active_probability,
+        Uniform::new(0.0f32, 1000.0f32),
+        rng,
+    )
+}
+
+fn generate_users_from(
+    count: usize,
+    active_probability: f64,
+    balance_dist: Uniform,
+    rng: &mut Pcg32,
+) -> Vec<User> {
+    let active_dist = Bernoulli::new(active_probability).unwrap();
+    let score_dist = Uniform::new(0.0f32, 100.0f32);
+    let mut score_rng = rng.fork(SCORE_STREAM);
+    debug_assert!(
+        count <= MAX_USERS,
+        "{count} users do not fit i32 ids; use try_generate_users"
+    );
+
+    let mut users = Vec::with_capacity(count);
+    for i in 0..count {
+        let user = User {
+            id: i as i32,
+            balance: Money(rng.sample(balance_dist)),
+            active: rng.sample(active_dist),
+            score: score_rng.sample(score_dist),
+        };
+        users.push(user);
+    }
+    users
+}
+
+/// Replaces NaN and infinite balances with 0.0 and returns how many it
+/// replaced. Generated balances are always finite, but a loaded dataset may
+/// not be, and the scans disagree on a NaN row: the repository `>=` skips
+/// it, while the DoD `balance * take` turns the whole sum into NaN. Takes
+/// anything that yields balances, e.g. a `&mut [f32]` column or
+/// `users.iter_mut().map(|user| &mut user.balance.0)`.
+pub fn sanitize_balances<'a>(balances: impl IntoIterator<Item = &'a mut f32>) -> usize {
+    let mut replaced = 0;
+    for balance in balances {
+        if !balance.is_finite() {
+            *balance = 0.0;
+            replaced += 1;
+        }
+    }
+    replaced
+}
+
+/// Parameters of a benchmark run. The defaults produce the seed-17 dataset
+/// whose checksum every binary prints.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchmarkConfig {
+    pub elements_count: usize,
+    pub minimum_balance: f32,
+    pub random_seed: u64,
+    pub warmup_iterations: usize,
+    pub iterations: usize,
+    /// Chance that a generated user is active.
+    pub active_probability: f64,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        Self {
+            elements_count: 10_000,
+            minimum_balance: 250.0,
+            random_seed: 17,
+            warmup_iterations: 2,
+            iterations: 8,
+            active_probability: 0.6,
+        }
+    }
+}
+
+impl BenchmarkConfig {
+    /// Starts from the defaults; only the changed parameters need setting.
+    pub fn builder() -> BenchmarkConfigBuilder {
+        BenchmarkConfigBuilder {
+            config: Self::default(),
+        }
+    }
+}
+
+/// A parameter `BenchmarkConfigBuilder::build` rejects.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigError {
+    ZeroElements,
+    /// More users than `MAX_USERS`, so ids would wrap.
+    TooManyElements(usize),
+    NonFiniteMinBalance(f32),
+    ZeroIterations,
+    /// The generators' Bernoulli distribution needs a probability in `[0, 1]`.
+    ActiveProbOutOfRange(f64),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ZeroElements => write!(f, "elements must be nonzero"),
+            Self::TooManyElements(count) => {
+                write!(
+                    f,
+                    "elements must be at most {MAX_USERS} for i32 ids, got {count}"
+                )
+            }
+            Self::NonFiniteMinBalance(value) => {
+                write!(f, "min-balance must be finite, got {value}")
+            }
+            Self::ZeroIterations => write!(f, "iterations must be nonzero"),
+            Self::ActiveProbOutOfRange(value) => {
+                write!(f, "active-prob must be between 0 and 1, got {value}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Validating builder for `BenchmarkConfig`. The setters take `&mut self`, so
+/// they chain on a fresh builder and also work one at a time from a flag loop.
+#[derive(Debug, Clone)]
+pub struct BenchmarkConfigBuilder {
+    config: BenchmarkConfig,
+}
+
+impl BenchmarkConfigBuilder {
+    pub fn elements(&mut self, count: usize) -> &mut Self {
+        self.config.elements_count = count;
+        self
+    }
+
+    pub fn min_balance(&mut self, balance: f32) -> &mut Self {
+        self.config.minimum_balance = balance;
+        self
+    }
+
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.config.random_seed = seed;
+        self
+    }
+
+    pub fn warmup(&mut self, iterations: usize) -> &mut Self {
+        self.config.warmup_iterations = iterations;
+        self
+    }
+
+    pub fn iterations(&mut self, iterations: usize) -> &mut Self {
+        self.config.iterations = iterations;
+        self
+    }
+
+    pub fn active_prob(&mut self, probability: f64) -> &mut Self {
+        self.config.active_probability = probability;
+        self
+    }
+
+    pub fn build(&self) -> Result<BenchmarkConfig, ConfigError> {
+        let config = self.config;
+        if config.elements_count == 0 {
+            return Err(ConfigError::ZeroElements);
+        }
+        if config.elements_count > MAX_USERS {
+            return Err(ConfigError::TooManyElements(config.elements_count));
+        }
+        if !config.minimum_balance.is_finite() {
+            return Err(ConfigError::NonFiniteMinBalance(config.minimum_balance));
+        }
+        if config.iterations == 0 {
+            return Err(ConfigError::ZeroIterations);
+        }
+        if !(0.0..=1.0).contains(&config.active_probability) {
+            return Err(ConfigError::ActiveProbOutOfRange(config.active_probability));
+        }
+        Ok(config)
+    }
+}
+
+/// Flags of the binaries without their own option parser: where results go
+/// besides the text block, and how the timed loop runs.
+#[derive(Debug, Default, PartialEq)]
+pub struct CommonFlags {
+    pub csv_out: Option<String>,
+    /// A file, or `-` for stdout.
+    pub json_out: Option<String>,
+    pub pin_core: Option<usize>,
+    /// Run `evict_cache` between iterations.
+    pub cold_cache: bool,
+    /// Dataset sizes to run instead of the configured one, see `sweep`.
+    pub sweep: Option<Vec<usize>>,
+    /// Generate with `generate_users_allowing_negative`.
+    pub allow_negative: bool,
+}
+
+/// Parses `--csv-out <file>`, `--json-out <file|->`, `--pin-core <n>`,
+/// `--cold-cache`, `--sweep <sizes>` and `--allow-negative`.
+pub fn parse_common_flags(mut args: impl Iterator<Item = String>) -> Result<CommonFlags, String> {
+    let mut flags = CommonFlags::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--csv-out" => flags.csv_out = Some(args.next().ok_or("--csv-out requires a file")?),
+            "--json-out" => {
+                flags.json_out = Some(args.next().ok_or("--json-out requires a file or '-'")?);
+            }
+            "--pin-core" => {
+                let value = args.next().ok_or("--pin-core requires a core index")?;
+                let core = value
+                    .parse()
+                    .map_err(|_| format!("invalid value '{value}' for --pin-core"))?;
+                flags.pin_core = Some(core);
+            }
+            "--cold-cache" => flags.cold_cache = true,
+            "--sweep" => {
+                let value = args
+                    .next()
+                    .ok_or("--sweep requires comma-separated sizes")?;
+                flags.sweep = Some(sweep::parse_sizes(&value)?);
+            }
+            "--allow-negative" => flags.allow_negative = true,
+            other => return Err(format!("unknown argument '{other}'")),
+        }
+    }
+
+    Ok(flags)
+}
+
+/// `parse_common_flags` for binaries whose only output besides the text block
+/// is `--csv-out`: any other common flag is an error rather than ignored.
+pub fn parse_csv_out_flag(args: impl Iterator<Item = String>) -> Result<Option<String>, String> {
+    let flags = parse_common_flags(args)?;
+    let csv_out = flags.csv_out.clone();
+
+    if flags
+        != (CommonFlags {
+            csv_out: csv_out.clone(),
+            ..CommonFlags::default()
+        })
+    {
+        return Err("only --csv-out is supported by this binary".to_string());
+    }
+
+    Ok(csv_out)
+}
+
+/// Sum of active balances of at least `minimum_balance` over the default
+/// `BenchmarkConfig` dataset (60 % active, balances uniform in [0, 1000)).
+///
+/// Every binary's kernel is pinned to this value, bit for bit, by a test in
+/// its own crate, so crates outside `bench_suite` are cross-checked through
+/// this one constant. `bench_suite/tests/cross_validate.rs` also compares the
+/// library crates against each other directly.
+pub const CANONICAL_CHECKSUM: f32 = 2_818_796.0;
+
+/// Runs `f` `iterations` times and returns the total seconds `timer` reports.
+pub fn measure_execution_time<T, F, R>(timer: &mut T, iterations: usize, mut f: F) -> f64
+where
+    T: Timer,
+    F: FnMut() -> R,
+{
+    timer.start();
+
+    for _ in 0..iterations {
+        let _ = f();
+    }
+
+    timer.stop().seconds
+}
+
+/// `measure_execution_time` running `between` before every iteration,
+/// outside the timed region: the returned seconds cover only `f`.
+pub fn measure_execution_time_with<T, B, F, R>(
+    timer: &mut T,
+    iterations: usize,
+    mut between: B,
+    mut f: F,
+) -> f64
+where
+    T: Timer,
+    B: FnMut(),
+    F: FnMut() -> R,
+{
+    let mut total_time_seconds = 0.0;
+
+    for _ in 0..iterations {
+        between();
+        timer.start();
+        let _ = f();
+        total_time_seconds += timer.stop().seconds;
+    }
+
+    total_time_seconds
+}
+
+/// Summary of per-iteration times, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub min: f64,
+    pub median: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Sample standard deviation (n - 1 denominator); zero for one sample.
+    pub stddev: f64,
+}
+
+impl Stats {
+    /// Summarizes `samples`, which must not be empty.
+    pub fn from_samples(samples: &[f64]) -> Self {
+        assert!(!samples.is_empty(), "no samples to summarize");
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+
+        let count = sorted.len();
+        let middle = count / 2;
+        // Even counts interpolate halfway between the two middle samples.
+        let median = if count.is_multiple_of(2) {
+            (sorted[middle - 1] + sorted[middle]) / 2.0
+        } else {
+            sorted[middle]
+        };
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let variance = if count > 1 {
+            sorted
+                .iter()
+                .map(|sample| (sample - mean).powi(2))
+                .sum::<f64>()
+                / (count - 1) as f64
+        } else {
+            0.0
+        };
+
+        Self {
+            min: sorted[0],
+            median,
+            max: sorted[count - 1],
+            mean,
+            stddev: variance.sqrt(),
+        }
+    }
+
+    /// Prints the five figures as lines of a results block, in microseconds.
+    pub fn print(&self) {
+        for (label, seconds) in [
+            ("Min Time per Iteration", self.min),
+            ("Median Time per Iteration", self.median),
+            ("Max Time per Iteration", self.max),
+            ("Mean Time per Iteration", self.mean),
+            ("Stddev per Iteration", self.stddev),
+        ] {
+            println!("{:<26} : {:.2} us", label, seconds * 1e6);
+        }
+    }
+}
+
+/// Like `measure_execution_time`, but times every iteration on its own and
+/// summarizes them, so outliers show up instead of vanishing in the average.
+pub fn measure_execution_time_detailed<T, F, R>(timer: &mut T, iterations: usize, f: F) -> Stats
+where
+    T: Timer,
+    F: FnMut() -> R,
+{
+    measure_execution_time_detailed_with(timer, iterations, || {}, f)
+}
+
+/// `measure_execution_time_detailed` running `between` before every
+/// iteration, outside the timed region.
+pub fn measure_execution_time_detailed_with<T, B, F, R>(
+    timer: &mut T,
+    iterations: usize,
+    mut between: B,
+    mut f: F,
+) -> Stats
+where
+    T: Timer,
+    B: FnMut(),
+    F: FnMut() -> R,
+{
+    let samples: Vec<f64> = (0..iterations)
+        .map(|_| {
+            between();
+            timer.start();
+            let _ = f();
+            timer.stop().seconds
+        })
+        .collect();
+
+    Stats::from_samples(&samples)
+}
+
+/// Scratch bytes `--cold-cache` sweeps between iterations: four times a
+/// 32 MiB last-level cache.
+pub const COLD_CACHE_SCRATCH_BYTES: usize = 128 * 1024 * 1024;
+
+/// Dirties one byte per cache line of `scratch`, so a buffer several times
+/// the last-level cache evicts whatever the previous iteration left there.
+/// Unlike `clflush` this works on every target, but it only evicts as well as
+/// the cache's replacement policy allows.
+pub fn evict_cache(scratch: &mut [u8]) {
+    const CACHE_LINE_BYTES: usize = 64;
+
+    for byte in scratch.iter_mut().step_by(CACHE_LINE_BYTES) {
+        *byte = byte.wrapping_add(1);
+    }
+    std::hint::black_box(scratch);
+}
+
+/// Prints the results block of one benchmarked implementation, deriving the
+/// per-iteration and per-element figures so every binary formats them alike.
+pub fn print_results(
+    label: &str,
+    checksum: f64,
+    total_time_seconds: f64,
+    iterations: usize,
+    elements: usize,
+) {
+    let average_time_seconds = total_time_seconds / iterations as f64;
+    let elements_per_second = elements as f64 / average_time_seconds;
+    let nanoseconds_per_element = (average_time_seconds * 1e9) / elements as f64;
+
+    println!();
+    println!("[ {label} Results ]");
+    println!("Checksum                   : {checksum:.8}");
+    println!("Total Time                 : {total_time_seconds:.2} s");
+    println!("Average Time per Iteration : {average_time_seconds:.2} s");
+    println!(
+        "Elements per Second        : {:.2} M",
+        elements_per_second / 1e6
+    );
+    println!("Nanoseconds per Element    : {nanoseconds_per_element:.2}");
+}
+
+/// Bytes per second in GiB/s.
+pub fn gibibytes_per_second(bytes_per_iteration: usize, average_time_seconds: f64) -> f64 {
+    bytes_per_iteration as f64 / average_time_seconds / (1u64 << 30) as f64
+}
+
+/// Prints the memory throughput a scan achieves, from the bytes one
+/// iteration reads. Elements per second hides that the layouts read
+/// different amounts per element; this line puts them on the same scale.
+pub fn print_bandwidth(bytes_per_iteration: usize, average_time_seconds: f64) {
+    println!(
+        "Effective Bandwidth        : {:.2} GiB/s ({} B per iteration)",
+        gibibytes_per_second(bytes_per_iteration, average_time_seconds),
+        bytes_per_iteration
+    );
+}
+
+/// Runs `f` until `budget` has elapsed, checking the clock after every call,
+/// and returns how many calls completed and the wall-clock seconds they took.
+/// `f` runs at least once, so even a zero budget yields an average.
+pub fn measure_for_duration<F, R>(budget: Duration, mut f: F) -> (usize, f64)
+where
+    F: FnMut() -> R,
+{
+    let start = Instant::now();
+    let mut iterations = 0;
+
+    loop {
+        let _ = f();
+        iterations += 1;
+        if start.elapsed() >= budget {
+            break;
+        }
+    }
+
+    (iterations, start.elapsed().as_secs_f64())
+}
+
+/// Cap and relative tolerance the binaries pass to `adaptive_warmup`.
+pub const ADAPTIVE_WARMUP_MAX_ITERATIONS: usize = 100;
+pub const ADAPTIVE_WARMUP_TOLERANCE: f64 = 0.05;
+
+/// Runs `f` until an iteration takes within `tolerance` (relative) of the
+/// time of the one before it, or `max_iters` times, and returns how many
+/// iterations ran. Stopping once timings settle, rather than after a fixed
+/// count, keeps cold caches and frequency ramp-up out of the measurement.
+pub fn adaptive_warmup<F, R>(max_iters: usize, tolerance: f64, mut f: F) -> usize
+where
+    F: FnMut() -> R,
+{
+    let mut previous_seconds: Option<f64> = None;
+
+    for iteration in 1..=max_iters {
+        let start = Instant::now();
+        let _ = f();
+        let seconds = start.elapsed().as_secs_f64();
+
+        if let Some(previous) = previous_seconds
+            && (seconds - previous).abs() < tolerance * previous
+        {
+            return iteration;
+        }
+        previous_seconds = Some(seconds);
+    }
+
+    max_iters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::InstantTimer;
+
+    #[test]
+    fn money_orders_and_sums_like_the_raw_amounts() {
+        let amounts = [Money(300.0), Money(-2.5), Money(0.25), Money(1000.0)];
+
+        assert!(Money(250.0) < Money(300.0) && Money(300.0) >= Money(300.0));
+        assert_eq!(
+            Money(f32::NAN).partial_cmp(&Money(0.0)),
+            f32::NAN.partial_cmp(&0.0)
+        );
+        assert_eq!(Money(1.5) + Money(2.25), Money(3.75));
+        assert_eq!(Money(2.0) * 0.0, Money::ZERO);
+        assert_eq!(amounts.iter().sum::<Money>(), Money(1297.75));
+        assert_eq!(amounts.into_iter().sum::<Money>().raw(), 1297.75);
+        assert_eq!(std::iter::empty::<Money>().sum::<Money>(), Money::ZERO);
+        assert_eq!(format!("{:.2}", Money(2.5)), "2.50");
+    }
+
+    #[test]
+    fn bandwidth_counts_gibibytes() {
+        assert_eq!(gibibytes_per_second(1 << 30, 1.0), 1.0);
+        assert_eq!(gibibytes_per_second(5 << 20, 0.001), 5.0 * 1000.0 / 1024.0);
+    }
+
+    #[test]
+    fn sanitize_balances_zeroes_only_non_finite_values() {
+        let mut balances = [1.5, f32::NAN, f32::INFINITY, -2.0, f32::NEG_INFINITY];
+
+        assert_eq!(sanitize_balances(&mut balances), 3);
+        assert_eq!(balances, [1.5, 0.0, 0.0, -2.0, 0.0]);
+        assert_eq!(sanitize_balances(&mut balances), 0);
+    }
+
+    #[test]
+    fn generator_is_a_pure_function_of_its_seed() {
+        let users = generate_users(1000, 17, 0.6);
+
+        assert_eq!(users, generate_users(1000, 17, 0.6));
+        assert_ne!(users, generate_users(1000, 18, 0.6));
+        assert!(
+            users
+                .iter()
+                .enumerate()
+                .all(|(i, user)| user.id == i as i32)
+        );
+        assert_eq!(
+            users,
+            generate_users_with_rng(1000, 0.6, &mut Pcg32::seed_from_u64(17))
+        );
+    }
+
+    #[test]
+    fn scores_come_from_their_own_stream() {
+        let users = generate_users(1000, 17, 0.6);
+
+        // The scores were added without moving the balances: the first one is
+        // still the pinned first draw of the seed-17 stream.
+        assert_eq!(users[0].balance.raw().to_bits(), 0x43d2_3ed8);
+        assert!(users.iter().all(|user| (0.0..100.0).contains(&user.score)));
+        assert_ne!(users[0].score, users[1].score);
+        assert_eq!(users[3].value(Metric::Balance), users[3].balance.raw());
+        assert_eq!(users[3].value(Metric::Score), users[3].score);
+    }
+
+    #[test]
+    fn negative_balances_keep_the_ids_and_active_flags() {
+        let standard = generate_users(1000, 17, 0.6);
+        let overdrawn = generate_users_allowing_negative(1000, 17, 0.6);
+
+        assert!(
+            overdrawn
+                .iter()
+                .all(|user| (-1000.0..1000.0).contains(&user.balance.raw()))
+        );
+        assert!(overdrawn.iter().any(|user| user.balance < Money::ZERO));
+        assert!(
+            standard
+                .iter()
+                .zip(&overdrawn)
+                .all(|(a, b)| (a.id, a.active, a.score) == (b.id, b.active, b.score))
+        );
+    }
+
+    #[test]
+    fn cold_measurement_evicts_before_every_iteration() {
+        let mut scratch = vec![0u8; 1000];
+        let mut evictions = 0;
+
+        measure_execution_time_detailed_with(
+            &mut InstantTimer::default(),
+            3,
+            || {
+                evict_cache(&mut scratch);
+                evictions += 1;
+            },
+            || (),
+        );
+
+        assert_eq!(evictions, 3);
+        for (i, &byte) in scratch.iter().enumerate() {
+            assert_eq!(byte, if i % 64 == 0 { 3 } else { 0 });
+        }
+    }
+
+    #[test]
+    fn measure_with_runs_between_before_every_iteration() {
+        let order = std::cell::RefCell::new(Vec::new());
+
+        let seconds = measure_execution_time_with(
+            &mut InstantTimer::default(),
+            2,
+            || order.borrow_mut().push("between"),
+            || order.borrow_mut().push("timed"),
+        );
+
+        assert!(seconds >= 0.0);
+        assert_eq!(order.into_inner(), ["between", "timed", "between", "timed"]);
+    }
+
+    #[test]
+    fn stats_interpolate_the_median_and_use_the_sample_stddev() {
+        let odd = Stats::from_samples(&[3.0, 1.0, 2.0]);
+        assert_eq!(
+            (odd.min, odd.median, odd.max, odd.mean),
+            (1.0, 2.0, 3.0, 2.0)
+        );
+        assert_eq!(odd.stddev, 1.0);
+
+        let even = Stats::from_samples(&[4.0, 1.0, 3.0, 2.0]);
+        assert_eq!(even.median, 2.5);
+        assert!((even.stddev - (5.0f64 / 3.0).sqrt()).abs() < 1e-12);
+
+        assert_eq!(Stats::from_samples(&[7.0]).stddev, 0.0);
+    }
+
+    #[test]
+    fn measure_execution_time_is_non_negative() {
+        let mut calls = 0;
+        let seconds = measure_execution_time(&mut InstantTimer::default(), 3, || calls += 1);
+
+        assert!(seconds >= 0.0);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn measure_for_duration_runs_until_the_budget_is_spent() {
+        let mut calls = 0;
+        let (iterations, seconds) = measure_for_duration(Duration::ZERO, || calls += 1);
+        assert_eq!((iterations, calls), (1, 1));
+        assert!(seconds >= 0.0);
+
+        let (iterations, seconds) =
+            measure_for_duration(Duration::from_millis(5), || std::hint::black_box(0));
+        assert!(iterations > 1);
+        assert!(seconds >= 0.005);
+    }
+
+    #[test]
+    fn adaptive_warmup_stops_once_stable_or_at_the_cap() {
+        let mut calls = 0;
+        assert_eq!(adaptive_warmup(10, 0.0, || calls += 1), 10);
+        assert_eq!(calls, 10);
+
+        assert_eq!(
+            adaptive_warmup(10, f64::INFINITY, || std::hint::black_box(0)),
+            2
+        );
+        assert_eq!(
+            adaptive_warmup(0, f64::INFINITY, || std::hint::black_box(0)),
+            0
+        );
+    }
+
+    #[test]
+    fn common_flags_are_the_only_flags() {
+        let parse = |list: &[&str]| parse_common_flags(list.iter().map(|arg| arg.to_string()));
+
+        assert_eq!(parse(&[]), Ok(CommonFlags::default()));
+        assert_eq!(
+            parse(&["--json-out", "-", "--csv-out", "a.csv", "--pin-core", "2"]),
+            Ok(CommonFlags {
+                csv_out: Some("a.csv".to_string()),
+                json_out: Some("-".to_string()),
+                pin_core: Some(2),
+                cold_cache: false,
+                sweep: None,
+                allow_negative: false,
+            })
+        );
+        assert!(parse(&["--csv-out"]).is_err());
+        assert!(parse(&["--json-out"]).is_err());
+        assert!(parse(&["--pin-core", "-1"]).is_err());
+        assert!(parse(&["--cold-cache"]).unwrap().cold_cache);
+        assert_eq!(
+            parse(&["--sweep", "1000,10000"]).unwrap().sweep,
+            Some(vec![1_000, 10_000])
+        );
+        assert!(parse(&["--sweep", "1000,0"]).is_err());
+        assert!(parse(&["--allow-negative"]).unwrap().allow_negative);
+        let negative_sweep = parse(&["--allow-negative", "--sweep", "10"]).unwrap();
+        assert!(negative_sweep.allow_negative);
+        assert_eq!(negative_sweep.sweep, Some(vec![10]));
+        assert!(parse(&["--contention"]).is_err());
+    }
+
+    #[test]
+    fn csv_out_flag_rejects_the_other_common_flags() {
+        let parse = |list: &[&str]| parse_csv_out_flag(list.iter().map(|arg| arg.to_string()));
+
+        assert_eq!(parse(&[]), Ok(None));
+        assert_eq!(
+            parse(&["--csv-out", "a.csv"]),
+            Ok(Some("a.csv".to_string()))
+        );
+        assert!(parse(&["--csv-out", "a.csv", "--cold-cache"]).is_err());
+        assert!(parse(&["--pin-core", "2"]).is_err());
+    }
+
+    #[test]
+    fn builder_sets_every_parameter() {
+        let config = BenchmarkConfig::builder()
+            .elements(1_000)
+            .min_balance(500.0)
+            .seed(42)
+            .warmup(0)
+            .iterations(3)
+            .active_prob(1.0)
+            .build();
+
+        assert_eq!(
+            config,
+            Ok(BenchmarkConfig {
+                elements_count: 1_000,
+                minimum_balance: 500.0,
+                random_seed: 42,
+                warmup_iterations: 0,
+                iterations: 3,
+                active_probability: 1.0,
+            })
+        );
+        assert_eq!(
+            BenchmarkConfig::builder().build(),
+            Ok(BenchmarkConfig::default())
+        );
+    }
+
+    #[test]
+    fn ids_stop_at_the_last_non_negative_i32() {
+        assert_eq!(MAX_USERS - 1, i32::MAX as usize);
+        assert_eq!(
+            try_generate_users(MAX_USERS + 1, 17, 0.5).unwrap_err(),
+            ConfigError::TooManyElements(MAX_USERS + 1)
+        );
+        assert_eq!(
+            try_generate_users(4, 17, 0.5),
+            Ok(generate_users(4, 17, 0.5))
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "do not fit i32 ids")]
+    fn generating_past_the_id_range_panics_in_debug_builds() {
+        generate_users(MAX_USERS + 1, 17, 0.5);
+    }
+
+    #[test]
+    fn builder_rejects_each_invalid_parameter() {
+        assert_eq!(
+            BenchmarkConfig::builder().elements(0).build(),
+            Err(ConfigError::ZeroElements)
+        );
+        assert!(
+            BenchmarkConfig::builder()
+                .elements(MAX_USERS)
+                .build()
+                .is_ok()
+        );
+        assert_eq!(
+            BenchmarkConfig::builder().elements(MAX_USERS + 1).build(),
+            Err(ConfigError::TooManyElements(MAX_USERS + 1))
+        );
+        assert_eq!(
+            BenchmarkConfig::builder().iterations(0).build(),
+            Err(ConfigError::ZeroIterations)
+        );
+        for balance in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert!(matches!(
+                BenchmarkConfig::builder().min_balance(balance).build(),
+                Err(ConfigError::NonFiniteMinBalance(_))
+            ));
+        }
+        for probability in [-0.1, 1.5, f64::NAN] {
+            assert!(matches!(
+                BenchmarkConfig::builder().active_prob(probability).build(),
+                Err(ConfigError::ActiveProbOutOfRange(_))
+            ));
+        }
+        assert_eq!(
+            ConfigError::NonFiniteMinBalance(f32::INFINITY).to_string(),
+            "min-balance must be finite, got inf"
+        );
+    }
+}
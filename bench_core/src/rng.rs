@@ -0,0 +1,188 @@
+//! Self-contained PRNG used for dataset generation.
+//!
+//! The benchmarks only need a reproducible stream, so rather than depending on
+//! `rand`'s `StdRng` (whose algorithm may change between major versions) we
+//! carry our own PCG32 (XSH RR 64/32, O'Neill 2014). The generator and the two
+//! distributions below only use integer arithmetic plus one exact float scale,
+//! so the stream is identical on every platform and across dependency updates.
+
+/// Multiplier of the underlying 64-bit LCG.
+const PCG_MULTIPLIER: u64 = 6364136223846793005;
+
+/// Stream selector used by `seed_from_u64` (the reference `PCG32_INITIALIZER` stream).
+const PCG_DEFAULT_STREAM: u64 = 0xda3e39cb94b95bdb;
+
+pub struct Pcg32 {
+    state: u64,
+    increment: u64,
+}
+
+impl Pcg32 {
+    /// Seeds the generator exactly like the reference `pcg32_srandom_r`.
+    pub fn new(initial_state: u64, stream: u64) -> Self {
+        let mut rng = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+        rng.step();
+        rng.state = rng.state.wrapping_add(initial_state);
+        rng.step();
+        rng
+    }
+
+    pub fn seed_from_u64(seed: u64) -> Self {
+        Self::new(seed, PCG_DEFAULT_STREAM)
+    }
+
+    /// A generator on `stream`, seeded from this one's current state, which
+    /// is left untouched: drawing from the fork does not shift this stream.
+    pub fn fork(&self, stream: u64) -> Self {
+        Self::new(self.state, stream)
+    }
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(PCG_MULTIPLIER)
+            .wrapping_add(self.increment);
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old_state = self.state;
+        self.step();
+
+        let xor_shifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+        let rotation = (old_state >> 59) as u32;
+        xor_shifted.rotate_right(rotation)
+    }
+
+    pub fn sample<T, D: Distribution<T>>(&mut self, distribution: D) -> T {
+        distribution.sample(self)
+    }
+}
+
+pub trait Distribution<T> {
+    fn sample(&self, rng: &mut Pcg32) -> T;
+}
+
+/// Uniform `f32` in `[low, high)`, built from the top 24 bits of one draw.
+#[derive(Clone, Copy)]
+pub struct Uniform {
+    low: f32,
+    range: f32,
+}
+
+impl Uniform {
+    pub fn new(low: f32, high: f32) -> Self {
+        assert!(low < high, "Uniform::new called with low >= high");
+        Self {
+            low,
+            range: high - low,
+        }
+    }
+}
+
+impl Distribution<f32> for Uniform {
+    fn sample(&self, rng: &mut Pcg32) -> f32 {
+        let unit = (rng.next_u32() >> 8) as f32 * (1.0 / (1u32 << 24) as f32);
+        self.low + self.range * unit
+    }
+}
+
+/// Bernoulli trial succeeding with probability `p`, decided by one 32-bit draw.
+#[derive(Clone, Copy)]
+pub struct Bernoulli {
+    threshold: u64,
+}
+
+impl Bernoulli {
+    /// Returns `None` unless `p` lies in `[0.0, 1.0]`.
+    pub fn new(p: f64) -> Option<Self> {
+        if !(0.0..=1.0).contains(&p) {
+            return None;
+        }
+
+        Some(Self {
+            threshold: (p * (1u64 << 32) as f64) as u64,
+        })
+    }
+}
+
+impl Distribution<bool> for Bernoulli {
+    fn sample(&self, rng: &mut Pcg32) -> bool {
+        (rng.next_u32() as u64) < self.threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOLDEN_SEED_17: [u32; 4] = [0x6ba54c06, 0x26714b9e, 0xd85b5ae9, 0x2d44fa46];
+    const GOLDEN_FIRST_BALANCE_BITS: u32 = 0x43d23ed8; // 420.49097
+    const GOLDEN_FIRST_ACTIVE: bool = true;
+
+    #[test]
+    fn matches_reference_pcg32_stream() {
+        // First outputs of the reference `pcg32-demo` seeded with (42, 54).
+        let mut rng = Pcg32::new(42, 54);
+        let expected = [
+            0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e,
+        ];
+
+        for value in expected {
+            assert_eq!(rng.next_u32(), value);
+        }
+    }
+
+    #[test]
+    fn benchmark_seed_stream_is_pinned() {
+        let mut rng = Pcg32::seed_from_u64(17);
+        let stream: Vec<u32> = (0..4).map(|_| rng.next_u32()).collect();
+
+        assert_eq!(stream, GOLDEN_SEED_17);
+    }
+
+    #[test]
+    fn distributions_are_pinned() {
+        let mut rng = Pcg32::seed_from_u64(17);
+        let balance_dist = Uniform::new(0.0, 1000.0);
+        let active_dist = Bernoulli::new(0.6).unwrap();
+
+        let balance = rng.sample(balance_dist);
+        let active = rng.sample(active_dist);
+
+        assert_eq!(balance.to_bits(), GOLDEN_FIRST_BALANCE_BITS);
+        assert_eq!(active, GOLDEN_FIRST_ACTIVE);
+    }
+
+    #[test]
+    fn a_fork_leaves_the_parent_stream_alone() {
+        let mut rng = Pcg32::seed_from_u64(17);
+        let mut fork = rng.fork(3);
+        let forked: Vec<u32> = (0..4).map(|_| fork.next_u32()).collect();
+        let stream: Vec<u32> = (0..4).map(|_| rng.next_u32()).collect();
+
+        assert_eq!(stream, GOLDEN_SEED_17);
+        assert_ne!(forked, GOLDEN_SEED_17);
+    }
+
+    #[test]
+    fn bernoulli_rejects_invalid_probabilities() {
+        assert!(Bernoulli::new(-0.1).is_none());
+        assert!(Bernoulli::new(1.1).is_none());
+        assert!(Bernoulli::new(f64::NAN).is_none());
+    }
+
+    #[test]
+    fn bernoulli_edges_are_exact() {
+        let mut rng = Pcg32::seed_from_u64(17);
+        let never = Bernoulli::new(0.0).unwrap();
+        let always = Bernoulli::new(1.0).unwrap();
+
+        for _ in 0..1000 {
+            assert!(!rng.sample(never));
+            assert!(rng.sample(always));
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! `--sweep <sizes>`: the whole benchmark repeated for every dataset size in a
+//! comma-separated list, printed as a scaling table and, with `--csv-out`,
+//! appended as one row per size for graphing.
+//!
+//! Each size regenerates its users from the run's seed with the generator the
+//! fixed-size run uses, `generate_users` or, under `--allow-negative`,
+//! `generate_users_allowing_negative`. That is the draw `--elements <size>`
+//! would make, so a row is comparable with a fixed-size run and every smaller
+//! dataset is a prefix of the larger ones.
+
+use crate::csv::{self, CsvRecord};
+use crate::timer::Timer;
+use crate::{
+    BenchmarkConfig, ConfigError, MAX_USERS, Stats, User, measure_execution_time_detailed_with,
+};
+
+/// Parses a list such as `1000,10000,100000`; sizes run in the given order.
+/// Each size is checked like `--elements`: nonzero and at most `MAX_USERS`.
+pub fn parse_sizes(value: &str) -> Result<Vec<usize>, String> {
+    value
+        .split(',')
+        .map(|size| match size.trim().parse() {
+            Ok(0) => Err("--sweep sizes must be nonzero".to_string()),
+            Ok(size) if size > MAX_USERS => Err(ConfigError::TooManyElements(size).to_string()),
+            Ok(size) => Ok(size),
+            Err(_) => Err(format!(
+                "--sweep expects comma-separated sizes, got '{value}'"
+            )),
+        })
+        .collect()
+}
+
+/// The figures measured at one size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SweepRow {
+    pub elements: usize,
+    pub iterations: usize,
+    pub stats: Stats,
+    pub checksum: f32,
+}
+
+impl SweepRow {
+    pub fn ns_per_element(&self) -> f64 {
+        self.stats.mean * 1e9 / self.elements as f64
+    }
+
+    pub fn elements_per_second(&self) -> f64 {
+        self.elements as f64 / self.stats.mean
+    }
+
+    pub fn csv_record<'a>(&self, implementation: &'a str, seed: u64) -> CsvRecord<'a> {
+        CsvRecord {
+            implementation,
+            elements: self.elements,
+            seed,
+            iterations: self.iterations,
+            total_time_seconds: self.stats.mean * self.iterations as f64,
+            checksum: self.checksum,
+        }
+    }
+}
+
+/// For every size: regenerates the users with `generate`, hands them to
+/// `prepare`, warms up `scan` over the result and times it with `timer`,
+/// running `between` before each timed iteration. Warmup and iteration counts
+/// come from `config`, like the fixed-size run.
+pub fn run<T, S, B, P, F>(
+    timer: &mut T,
+    sizes: &[usize],
+    config: &BenchmarkConfig,
+    generate: fn(usize, u64, f64) -> Vec<User>,
+    mut between: B,
+    mut prepare: P,
+    mut scan: F,
+) -> Vec<SweepRow>
+where
+    T: Timer,
+    B: FnMut(),
+    P: FnMut(Vec<User>) -> S,
+    F: FnMut(&S) -> f32,
+{
+    sizes
+        .iter()
+        .map(|&elements| {
+            let state = prepare(generate(
+                elements,
+                config.random_seed,
+                config.active_probability,
+            ));
+
+            let mut checksum = scan(&state);
+            for _ in 1..config.warmup_iterations {
+                checksum = scan(&state);
+            }
+
+            let stats = measure_execution_time_detailed_with(
+                timer,
+                config.iterations,
+                &mut between,
+                || scan(&state),
+            );
+
+            SweepRow {
+                elements,
+                iterations: config.iterations,
+                stats,
+                checksum,
+            }
+        })
+        .collect()
+}
+
+pub fn print_table(label: &str, rows: &[SweepRow]) {
+    println!();
+    println!("[ {label} Scaling ]");
+    println!(
+        "{:>12}  {:>12}  {:>16}  {:>14}",
+        "Elements", "ns/element", "M elements/s", "Checksum"
+    );
+    for row in rows {
+        println!(
+            "{:>12}  {:>12.4}  {:>16.2}  {:>14.2}",
+            row.elements,
+            row.ns_per_element(),
+            row.elements_per_second() / 1e6,
+            row.checksum
+        );
+    }
+    println!();
+}
+
+/// Appends one `--csv-out` row per size, in the format of the fixed-size runs.
+pub fn append_csv(
+    path: &str,
+    implementation: &str,
+    seed: u64,
+    rows: &[SweepRow],
+) -> Result<(), String> {
+    let records: Vec<CsvRecord> = rows
+        .iter()
+        .map(|row| row.csv_record(implementation, seed))
+        .collect();
+    csv::append(path, &records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::InstantTimer;
+    use crate::{Money, generate_users, generate_users_allowing_negative};
+
+    #[test]
+    fn sizes_are_a_nonzero_comma_separated_list() {
+        assert_eq!(parse_sizes("1000, 10000,100"), Ok(vec![1_000, 10_000, 100]));
+        assert!(parse_sizes("1000,0").unwrap_err().contains("nonzero"));
+        assert!(parse_sizes("1000,,10").unwrap_err().contains("'1000,,10'"));
+        assert!(parse_sizes("1e3").is_err());
+    }
+
+    #[test]
+    fn sizes_stop_at_the_id_range() {
+        assert_eq!(
+            parse_sizes(&format!("10,{MAX_USERS}")),
+            Ok(vec![10, MAX_USERS])
+        );
+        assert_eq!(
+            parse_sizes(&format!("10,{}", MAX_USERS + 1)),
+            Err(ConfigError::TooManyElements(MAX_USERS + 1).to_string())
+        );
+    }
+
+    #[test]
+    fn every_size_scans_a_freshly_generated_prefix() {
+        let config = BenchmarkConfig::builder()
+            .warmup(1)
+            .iterations(2)
+            .build()
+            .unwrap();
+        let largest = generate_users(1000, config.random_seed, config.active_probability);
+        let sum = |users: &[User]| -> f32 {
+            users
+                .iter()
+                .filter(|user| user.active)
+                .map(|user| user.balance)
+                .sum::<Money>()
+                .raw()
+        };
+
+        let rows = run(
+            &mut InstantTimer::default(),
+            &[10, 1000],
+            &config,
+            generate_users,
+            || {},
+            |users| users,
+            |users| sum(users),
+        );
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].elements, 10);
+        assert_eq!(rows[0].checksum, sum(&largest[..10]));
+        assert_eq!(rows[1].checksum, sum(&largest));
+        assert_eq!(rows[1].csv_record("aos-p", 7).iterations, 2);
+    }
+
+    #[test]
+    fn sizes_are_drawn_with_the_given_generator() {
+        let config = BenchmarkConfig::builder()
+            .warmup(1)
+            .iterations(1)
+            .build()
+            .unwrap();
+        let overdrawn = |users: &Vec<User>| -> f32 {
+            users
+                .iter()
+                .filter(|user| user.balance < Money::ZERO)
+                .count() as f32
+        };
+
+        let rows = run(
+            &mut InstantTimer::default(),
+            &[1000],
+            &config,
+            generate_users_allowing_negative,
+            || {},
+            |users| users,
+            overdrawn,
+        );
+
+        assert!(rows[0].checksum > 0.0);
+    }
+}
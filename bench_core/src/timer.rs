@@ -0,0 +1,65 @@
+//! Timing backends.
+//!
+//! The harness only needs "how long did this take", so it talks to a `Timer`
+//! instead of `std::time` directly; cycle counters or perf events can then
+//! replace `InstantTimer` without touching the measurement loops.
+
+use std::time::Instant;
+
+/// One interval reported by a `Timer`.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Measurement {
+    pub seconds: f64,
+}
+
+pub trait Timer {
+    /// Starts (or restarts) the interval.
+    fn start(&mut self);
+
+    /// Ends the interval begun by the last `start`.
+    fn stop(&mut self) -> Measurement;
+}
+
+/// Wall-clock timer backed by the monotonic `Instant`.
+#[derive(Debug, Default)]
+pub struct InstantTimer {
+    started: Option<Instant>,
+}
+
+impl Timer for InstantTimer {
+    fn start(&mut self) {
+        self.started = Some(Instant::now());
+    }
+
+    fn stop(&mut self) -> Measurement {
+        let started = self
+            .started
+            .take()
+            .expect("InstantTimer::stop called before start");
+
+        Measurement {
+            seconds: started.elapsed().as_secs_f64(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instant_timer_is_monotonic_and_nonzero() {
+        let mut outer = InstantTimer::default();
+        let mut inner = InstantTimer::default();
+
+        outer.start();
+        inner.start();
+        let sum: u64 = std::hint::black_box(0..1_000_000u64).sum();
+        std::hint::black_box(sum);
+        let inner_measurement = inner.stop();
+        let outer_measurement = outer.stop();
+
+        assert!(inner_measurement.seconds > 0.0);
+        assert!(outer_measurement >= inner_measurement);
+    }
+}
@@ -0,0 +1,285 @@
+use std::hint::black_box;
+
+use bench_core::{BenchmarkConfig, Money, User};
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use repository_domain_p_optimized2::UserService;
+use repository_p::{
+    RepositoryKind, SortedVectorUserRepository, UserRepository, VectorUserRepository,
+};
+
+/// From cache-resident to DRAM-bound; the binaries default to 10k.
+const SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+/// `VectorUserRepository::find_by_id` is a linear scan, so a full sum is
+/// O(n^2) and larger sizes would take minutes per sample.
+const LINEAR_LOOKUP_MAX_SIZE: usize = 1_000;
+
+fn sum_active_balances(c: &mut Criterion) {
+    let BenchmarkConfig {
+        minimum_balance,
+        random_seed,
+        active_probability,
+        ..
+    } = BenchmarkConfig::default();
+    let mut group = c.benchmark_group("sum_active_balances");
+
+    for size in SIZES {
+        group.throughput(Throughput::Elements(size as u64));
+
+        let columns = dod_p::generate_columns(
+            size,
+            active_probability,
+            &mut dod_p::rng::Pcg32::seed_from_u64(random_seed),
+        );
+        group.bench_with_input(BenchmarkId::new("dod", size), &columns.view(), |b, view| {
+            b.iter(|| dod_p::sum_active_balances(black_box(view), minimum_balance))
+        });
+
+        let users = bench_core::generate_users(size, random_seed, active_probability);
+        if size <= LINEAR_LOOKUP_MAX_SIZE {
+            let linear = VectorUserRepository::new(users.clone());
+            group.bench_with_input(
+                BenchmarkId::new("repository_linear", size),
+                &linear,
+                |b, repository| {
+                    b.iter(|| {
+                        repository_p::sum_active_balances_by_id(
+                            black_box(repository),
+                            Money(minimum_balance),
+                        )
+                    })
+                },
+            );
+        }
+        let sorted = SortedVectorUserRepository::new(users);
+        group.bench_with_input(
+            BenchmarkId::new("repository_sorted", size),
+            &sorted,
+            |b, repository| {
+                b.iter(|| {
+                    repository_p::sum_active_balances_by_id(
+                        black_box(repository),
+                        Money(minimum_balance),
+                    )
+                })
+            },
+        );
+
+        let service = UserService::new(repository_domain_p_optimized2::VectorUserRepository::new(
+            bench_core::generate_users(size, random_seed, active_probability),
+        ));
+        group.bench_with_input(
+            BenchmarkId::new("domain_service", size),
+            &service,
+            |b, service| {
+                b.iter(|| {
+                    repository_domain_p_optimized2::sum_active_balances(
+                        black_box(service),
+                        Money(minimum_balance),
+                    )
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// The same sorted-repository sum through a vtable, a monomorphized generic
+/// and an enum `match`, so the only difference is how `find_by_id` is reached.
+fn dispatch(c: &mut Criterion) {
+    let BenchmarkConfig {
+        minimum_balance,
+        random_seed,
+        active_probability,
+        ..
+    } = BenchmarkConfig::default();
+    let minimum_balance = Money(minimum_balance);
+    let mut group = c.benchmark_group("dispatch");
+
+    for size in SIZES {
+        group.throughput(Throughput::Elements(size as u64));
+
+        let sorted = SortedVectorUserRepository::new(bench_core::generate_users(
+            size,
+            random_seed,
+            active_probability,
+        ));
+        group.bench_with_input(BenchmarkId::new("dyn", size), &sorted, |b, repository| {
+            b.iter(|| {
+                repository_p::sum_active_balances_by_id(black_box(repository), minimum_balance)
+            })
+        });
+        group.bench_with_input(
+            BenchmarkId::new("generic", size),
+            &sorted,
+            |b, repository| {
+                b.iter(|| {
+                    repository_p::sum_active_balances_generic(
+                        black_box(repository),
+                        minimum_balance,
+                    )
+                })
+            },
+        );
+
+        let kind = RepositoryKind::Sorted(sorted);
+        group.bench_with_input(BenchmarkId::new("enum", size), &kind, |b, repository| {
+            b.iter(|| {
+                repository_p::sum_active_balances_enum(black_box(repository), minimum_balance)
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// Collecting the qualifying users instead of summing them: a packed
+/// `Vec<f32>` gathered from the columns against the `Vec<&User>` the
+/// repository hands out, each of which still points into a 16-byte row.
+fn qualifying_users(c: &mut Criterion) {
+    let BenchmarkConfig {
+        minimum_balance,
+        random_seed,
+        active_probability,
+        ..
+    } = BenchmarkConfig::default();
+    let mut group = c.benchmark_group("qualifying_users");
+
+    for size in SIZES {
+        group.throughput(Throughput::Elements(size as u64));
+
+        let columns = dod_p::generate_columns(
+            size,
+            active_probability,
+            &mut dod_p::rng::Pcg32::seed_from_u64(random_seed),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("dod_balances", size),
+            &columns.view(),
+            |b, view| b.iter(|| dod_p::qualifying_balances(black_box(view), minimum_balance)),
+        );
+
+        let repository = VectorUserRepository::new(bench_core::generate_users(
+            size,
+            random_seed,
+            active_probability,
+        ));
+        group.bench_with_input(
+            BenchmarkId::new("repository_users", size),
+            &repository,
+            |b, repository| {
+                b.iter(|| {
+                    black_box(repository)
+                        .iter()
+                        .filter(|user| repository_p::qualifies(user, Money(minimum_balance)))
+                        .collect::<Vec<&User>>()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+/// The repository way of bumping every qualifying balance: a `find_by_id`
+/// and an `update_balance` per user, each a lookup of its own, so every
+/// write lands wherever a lookup points. Returns the rows written.
+fn add_to_active_balances(
+    repository: &mut dyn UserRepository,
+    delta: Money,
+    minimum_balance: Money,
+) -> usize {
+    let mut written = 0;
+
+    for i in 0..repository.count() {
+        let id = i as i32;
+        if repository
+            .find_by_id(id)
+            .is_some_and(|user| repository_p::qualifies(user, minimum_balance))
+        {
+            repository.update_balance(id, delta);
+            written += 1;
+        }
+    }
+
+    written
+}
+
+/// In-place updates of the qualifying balances: one sequential pass over the
+/// columns, storing only to qualifying rows and marking them dirty, against
+/// two binary searches and a scattered write per user in the sorted
+/// repository. Each sample updates a fresh copy, so the qualifying set and
+/// the rows written stay the same.
+fn update_qualifying(c: &mut Criterion) {
+    let BenchmarkConfig {
+        minimum_balance,
+        random_seed,
+        active_probability,
+        ..
+    } = BenchmarkConfig::default();
+    let mut group = c.benchmark_group("update_qualifying");
+
+    for size in SIZES {
+        group.throughput(Throughput::Elements(size as u64));
+
+        let columns = dod_p::generate_columns(
+            size,
+            active_probability,
+            &mut dod_p::rng::Pcg32::seed_from_u64(random_seed),
+        );
+        let sorted = SortedVectorUserRepository::new(bench_core::generate_users(
+            size,
+            random_seed,
+            active_probability,
+        ));
+        assert_eq!(
+            columns
+                .clone()
+                .view_mut()
+                .add_to_active_balances(1.0, minimum_balance),
+            add_to_active_balances(&mut sorted.clone(), Money(1.0), Money(minimum_balance)),
+            "both paths must write the same rows"
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("dod_view_mut", size),
+            &columns,
+            |b, columns| {
+                b.iter_batched_ref(
+                    || columns.clone(),
+                    |columns| {
+                        columns
+                            .view_mut()
+                            .add_to_active_balances(1.0, minimum_balance)
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("repository_sorted", size),
+            &sorted,
+            |b, repository| {
+                b.iter_batched_ref(
+                    || repository.clone(),
+                    |repository| {
+                        add_to_active_balances(repository, Money(1.0), Money(minimum_balance))
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    sum_active_balances,
+    dispatch,
+    qualifying_users,
+    update_qualifying
+);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+//! Criterion benches of `sum_active_balances` across the implementations; the
+//! benchmarks themselves live in `benches/`.
+
+#[cfg(test)]
+mod tests {
+    use bench_core::{Money, User, sanitize_balances};
+    use dod_p::UsersColumns;
+    use repository_p::VectorUserRepository;
+
+    fn sums(users: &[User]) -> (f32, f32) {
+        let columns = UsersColumns::from_users(users);
+        let repository = VectorUserRepository::new(users.to_vec());
+        (
+            dod_p::sum_active_balances(&columns.view(), 250.0),
+            repository_p::sum_active_balances(&repository, Money(250.0)).raw(),
+        )
+    }
+
+    #[test]
+    fn a_nan_balance_splits_the_scans_until_sanitized() {
+        let mut users: Vec<User> = [500.0, f32::NAN, 300.0]
+            .into_iter()
+            .enumerate()
+            .map(|(id, balance)| User {
+                id: id as i32,
+                balance: Money(balance),
+                active: true,
+                score: 0.0,
+            })
+            .collect();
+
+        // `NaN >= 250.0` is false, so the repository skips the row, but the
+        // DoD scan still adds `NaN * 0.0`, which is NaN.
+        let (dod, repository) = sums(&users);
+        assert!(dod.is_nan());
+        assert_eq!(repository, 800.0);
+
+        assert_eq!(
+            sanitize_balances(users.iter_mut().map(|user| &mut user.balance.0)),
+            1
+        );
+        assert_eq!(sums(&users), (800.0, 800.0));
+    }
+}
@@ -0,0 +1,34 @@
+//! The three headline implementations scanned over one shared dataset: each
+//! must produce the same `f32` sum, bit for bit.
+
+use bench_core::{BenchmarkConfig, CANONICAL_CHECKSUM, Money, generate_users};
+use dod_p::UsersColumns;
+use repository_domain_p_optimized2::UserService;
+use repository_p::SortedVectorUserRepository;
+
+#[test]
+fn implementations_agree_bit_for_bit() {
+    let config = BenchmarkConfig::default();
+    let users = generate_users(
+        config.elements_count,
+        config.random_seed,
+        config.active_probability,
+    );
+    let minimum_balance = config.minimum_balance;
+
+    let columns = UsersColumns::from_users(&users);
+    let dod = dod_p::sum_active_balances(&columns.view(), minimum_balance);
+
+    let repository = SortedVectorUserRepository::new(users.clone());
+    let repository = repository_p::sum_active_balances(&repository, Money(minimum_balance)).raw();
+
+    let service = UserService::new(repository_domain_p_optimized2::VectorUserRepository::new(
+        users,
+    ));
+    let domain =
+        repository_domain_p_optimized2::sum_active_balances(&service, Money(minimum_balance)).raw();
+
+    assert_eq!(dod.to_bits(), repository.to_bits(), "dod vs repository");
+    assert_eq!(dod.to_bits(), domain.to_bits(), "dod vs domain");
+    assert_eq!(dod, CANONICAL_CHECKSUM);
+}
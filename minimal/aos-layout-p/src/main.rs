@@ -0,0 +1,354 @@
+use bench_core::csv::{self, CsvRecord};
+use bench_core::timer::InstantTimer;
+use bench_core::{measure_execution_time, parse_csv_out_flag};
+
+/// Naturally aligned layout: 9 bytes of fields padded to 12.
+///
+/// `PAD` appends that many filler bytes, so the same scan and generator can be
+/// rerun at any struct size without writing a new record type.
+#[derive(Debug, Clone, Copy)]
+struct User<const PAD: usize = 0> {
+    id: i32,
+    balance: f32,
+    active: bool,
+    #[allow(dead_code)]
+    padding: [u8; PAD],
+}
+
+/// Same fields in the same order without padding: 9 bytes, so most balances sit at unaligned
+/// addresses and some straddle two cache lines.
+///
+/// Taking `&user.balance` on this type is undefined behaviour (and rejected by
+/// the compiler), so the scan reads fields through raw pointers instead.
+#[repr(C, packed)]
+#[derive(Debug, Clone, Copy)]
+struct PackedUser {
+    #[allow(dead_code)]
+    id: i32,
+    balance: f32,
+    active: bool,
+}
+
+impl From<User> for PackedUser {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            balance: user.balance,
+            active: user.active,
+        }
+    }
+}
+
+#[inline(never)]
+fn sum_active_balances<const PAD: usize>(users: &[User<PAD>], minimum_balance: f32) -> f32 {
+    let mut accumulated_balance = 0.0f32;
+
+    for user in users {
+        if user.active && user.balance >= minimum_balance {
+            accumulated_balance += user.balance;
+        }
+    }
+
+    accumulated_balance
+}
+
+#[inline(never)]
+fn sum_active_balances_packed(users: &[PackedUser], minimum_balance: f32) -> f32 {
+    let mut accumulated_balance = 0.0f32;
+
+    for user in users {
+        // SAFETY: `&raw const` produces a raw pointer without going through a
+        // reference, and `read_unaligned` has no alignment requirement.
+        let balance = unsafe { (&raw const user.balance).read_unaligned() };
+        // `bool` has alignment 1, so a by-value copy is always aligned.
+        let active = user.active;
+
+        if active && balance >= minimum_balance {
+            accumulated_balance += balance;
+        }
+    }
+
+    accumulated_balance
+}
+
+/// Fractions of `balance` fields that are unaligned and that straddle a
+/// 64-byte cache line, for `count` records of `record_bytes` laid out from a
+/// line boundary.
+fn misaligned_balance_fractions(
+    record_bytes: usize,
+    balance_offset: usize,
+    count: usize,
+) -> (f64, f64) {
+    const CACHE_LINE_BYTES: usize = 64;
+
+    let (mut unaligned, mut splits) = (0, 0);
+    for i in 0..count {
+        let start = i * record_bytes + balance_offset;
+        let end = start + size_of::<f32>() - 1;
+
+        if !start.is_multiple_of(align_of::<f32>()) {
+            unaligned += 1;
+        }
+        if start / CACHE_LINE_BYTES != end / CACHE_LINE_BYTES {
+            splits += 1;
+        }
+    }
+
+    (
+        unaligned as f64 / count as f64,
+        splits as f64 / count as f64,
+    )
+}
+
+/// The shared dataset, copied into padded records.
+fn generate_users<const PAD: usize>(count: usize, seed: u64) -> Vec<User<PAD>> {
+    bench_core::generate_users(count, seed, 0.6)
+        .into_iter()
+        .map(|user| User {
+            id: user.id,
+            balance: user.balance.raw(),
+            active: user.active,
+            padding: [0; PAD],
+        })
+        .collect()
+}
+
+/// Times the aligned scan over `User<PAD>` and prints one row of the sweep.
+fn sweep_row<const PAD: usize>(count: usize, seed: u64, minimum_balance: f32, iterations: usize) {
+    let users = generate_users::<PAD>(count, seed);
+
+    let mut checksum = sum_active_balances(&users, minimum_balance);
+    let mut timer = InstantTimer::default();
+    let total_time_seconds = measure_execution_time(&mut timer, iterations, || {
+        checksum = sum_active_balances(&users, minimum_balance);
+    });
+
+    let average_time_seconds = total_time_seconds / iterations as f64;
+    let record_bytes = size_of::<User<PAD>>();
+
+    println!(
+        "{:>4} | {:>11} | {:>13.2} | {:>15.2} | {:>10.3} | {:>12.2}",
+        PAD,
+        record_bytes,
+        (record_bytes * count) as f64 / (1024.0 * 1024.0),
+        checksum,
+        average_time_seconds * 1e9 / count as f64,
+        count as f64 / average_time_seconds / 1e6
+    );
+}
+
+/// Prints one results block and returns the measured elements per second.
+fn print_results(
+    label: &str,
+    record_bytes: usize,
+    count: usize,
+    checksum: f32,
+    total_time_seconds: f64,
+    iterations: usize,
+) -> f64 {
+    let average_time_seconds = total_time_seconds / iterations as f64;
+    let elements_per_second = count as f64 / average_time_seconds;
+    let nanoseconds_per_element = (average_time_seconds * 1e9) / count as f64;
+
+    println!();
+    println!("[ {} Results ]", label);
+    println!("Record Size                : {} bytes", record_bytes);
+    println!(
+        "Footprint                  : {:.2} MiB",
+        (record_bytes * count) as f64 / (1024.0 * 1024.0)
+    );
+    println!("Checksum                   : {:.8}", checksum);
+    println!("Total Time                 : {:.2} s", total_time_seconds);
+    println!("Average Time per Iteration : {:.2} s", average_time_seconds);
+    println!(
+        "Elements per Second        : {:.2} M",
+        elements_per_second / 1e6
+    );
+    println!(
+        "Nanoseconds per Element    : {:.2}",
+        nanoseconds_per_element
+    );
+
+    elements_per_second
+}
+
+fn main() {
+    const ELEMENTS_COUNT: usize = 4_000_000;
+    const MINIMUM_BALANCE: f32 = 250.0;
+    const RANDOM_SEED: u64 = 17;
+    const WARMUP_ITERATIONS: usize = 2;
+    const ITERATIONS: usize = 8;
+    // Smaller than ELEMENTS_COUNT so the PAD = 240 column stays around 240 MiB.
+    const SWEEP_ELEMENTS_COUNT: usize = 1_000_000;
+
+    let csv_out = match parse_csv_out_flag(std::env::args().skip(1)) {
+        Ok(csv_out) => csv_out,
+        Err(message) => {
+            eprintln!("error: {message}");
+            std::process::exit(2);
+        }
+    };
+
+    println!();
+    println!("[ AoS Layout Benchmark ]");
+    println!("Elements Count    : {}", ELEMENTS_COUNT);
+    println!("Minimum Balance   : {:.2}", MINIMUM_BALANCE);
+    println!("Random Seed       : {}", RANDOM_SEED);
+    println!("Warmup Iterations : {}", WARMUP_ITERATIONS);
+    println!("Iterations        : {}", ITERATIONS);
+
+    println!();
+    println!("Generating elements...");
+
+    let users = generate_users::<0>(ELEMENTS_COUNT, RANDOM_SEED);
+    let packed_users: Vec<PackedUser> = users.iter().copied().map(PackedUser::from).collect();
+
+    println!();
+    println!("Warming up...");
+
+    let mut checksum = 0.0f32;
+    let mut packed_checksum = 0.0f32;
+    for _ in 0..WARMUP_ITERATIONS {
+        checksum = sum_active_balances(&users, MINIMUM_BALANCE);
+        packed_checksum = sum_active_balances_packed(&packed_users, MINIMUM_BALANCE);
+    }
+
+    println!();
+    println!("Benchmarking...");
+
+    let mut timer = InstantTimer::default();
+    let aligned_time_seconds = measure_execution_time(&mut timer, ITERATIONS, || {
+        sum_active_balances(&users, MINIMUM_BALANCE)
+    });
+    let packed_time_seconds = measure_execution_time(&mut timer, ITERATIONS, || {
+        sum_active_balances_packed(&packed_users, MINIMUM_BALANCE)
+    });
+
+    let aligned_elements_per_second = print_results(
+        "Aligned AoS",
+        size_of::<User>(),
+        ELEMENTS_COUNT,
+        checksum,
+        aligned_time_seconds,
+        ITERATIONS,
+    );
+    let packed_elements_per_second = print_results(
+        "Packed AoS",
+        size_of::<PackedUser>(),
+        ELEMENTS_COUNT,
+        packed_checksum,
+        packed_time_seconds,
+        ITERATIONS,
+    );
+
+    if let Some(path) = &csv_out {
+        let record = |implementation, total_time_seconds, checksum| CsvRecord {
+            implementation,
+            elements: ELEMENTS_COUNT,
+            seed: RANDOM_SEED,
+            iterations: ITERATIONS,
+            total_time_seconds,
+            checksum,
+        };
+        let records = [
+            record("aos-layout-p-aligned", aligned_time_seconds, checksum),
+            record("aos-layout-p-packed", packed_time_seconds, packed_checksum),
+        ];
+        if let Err(message) = csv::append(path, &records) {
+            eprintln!("error: {message}");
+            std::process::exit(1);
+        }
+    }
+
+    let (unaligned_fraction, split_fraction) = misaligned_balance_fractions(
+        size_of::<PackedUser>(),
+        std::mem::offset_of!(PackedUser, balance),
+        ELEMENTS_COUNT,
+    );
+
+    println!();
+    println!("[ Layout Comparison ]");
+    println!(
+        "Footprint Saved            : {:.1} %",
+        (1.0 - size_of::<PackedUser>() as f64 / size_of::<User>() as f64) * 100.0
+    );
+    println!(
+        "Packed / Aligned Speed     : {:.2}x",
+        packed_elements_per_second / aligned_elements_per_second
+    );
+    println!(
+        "Unaligned Balances         : {:.1} %",
+        unaligned_fraction * 100.0
+    );
+    println!(
+        "Cache-Line-Split Balances  : {:.1} %",
+        split_fraction * 100.0
+    );
+    println!();
+    println!("Packing only pays off while the scan is bandwidth-bound; every split");
+    println!("balance costs two cache-line reads, which eats into the smaller footprint.");
+    println!();
+
+    println!("[ Padding Sweep ({} elements) ]", SWEEP_ELEMENTS_COUNT);
+    println!(" PAD | Record Size | Footprint MiB | Checksum        | ns/element | M elements/s");
+    sweep_row::<0>(
+        SWEEP_ELEMENTS_COUNT,
+        RANDOM_SEED,
+        MINIMUM_BALANCE,
+        ITERATIONS,
+    );
+    sweep_row::<16>(
+        SWEEP_ELEMENTS_COUNT,
+        RANDOM_SEED,
+        MINIMUM_BALANCE,
+        ITERATIONS,
+    );
+    sweep_row::<64>(
+        SWEEP_ELEMENTS_COUNT,
+        RANDOM_SEED,
+        MINIMUM_BALANCE,
+        ITERATIONS,
+    );
+    sweep_row::<240>(
+        SWEEP_ELEMENTS_COUNT,
+        RANDOM_SEED,
+        MINIMUM_BALANCE,
+        ITERATIONS,
+    );
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packed_scan_matches_aligned_scan() {
+        let users: Vec<User> = (0..1000)
+            .map(|i| User {
+                id: i,
+                balance: (i * 37 % 1000) as f32,
+                active: i % 3 != 0,
+                padding: [],
+            })
+            .collect();
+        let packed_users: Vec<PackedUser> = users.iter().copied().map(PackedUser::from).collect();
+
+        assert_eq!(size_of::<PackedUser>(), 9);
+        assert_eq!(
+            sum_active_balances_packed(&packed_users, 250.0),
+            sum_active_balances(&users, 250.0)
+        );
+    }
+
+    #[test]
+    fn padding_changes_size_not_checksum() {
+        assert_eq!(size_of::<User<0>>(), 12);
+        assert_eq!(size_of::<User<16>>(), 28);
+        assert_eq!(size_of::<User<240>>(), 252);
+        assert_eq!(
+            sum_active_balances(&generate_users::<240>(1000, 17), 250.0),
+            sum_active_balances(&generate_users::<0>(1000, 17), 250.0)
+        );
+    }
+}
@@ -1,8 +1,49 @@
 #![feature(portable_simd)]
+use std::simd::num::SimdFloat;
 use std::simd::*;
 use std::time::Instant;
 
+type Reduction = fn(&[f64]) -> f64;
+
+/// Folds all lanes into one scalar; shared by both reductions below so they
+/// only differ in how often it runs.
+#[inline(always)]
+fn horizontal_sum(lanes: f64x8) -> f64 {
+    lanes.reduce_sum()
+}
+
+/// The common mistake: a lane-crossing horizontal sum on every chunk, which
+/// serializes the loop on the scalar accumulator.
+#[inline(never)]
+fn sum_reduce_per_chunk(balances: &[f64]) -> f64 {
+    let chunks = balances.chunks_exact(8);
+    let remainder = chunks.remainder();
+
+    let mut total = 0.0;
+    for chunk in chunks {
+        total += horizontal_sum(f64x8::from_slice(chunk));
+    }
+
+    total + remainder.iter().sum::<f64>()
+}
+
+/// Accumulates whole vectors and pays for the horizontal sum once at the end.
+#[inline(never)]
+fn sum_reduce_deferred(balances: &[f64]) -> f64 {
+    let chunks = balances.chunks_exact(8);
+    let remainder = chunks.remainder();
+
+    let mut lanes = f64x8::splat(0.0);
+    for chunk in chunks {
+        lanes += f64x8::from_slice(chunk);
+    }
+
+    horizontal_sum(lanes) + remainder.iter().sum::<f64>()
+}
+
 fn main() {
+    const REDUCTION_ITERATIONS: usize = 10_000;
+
     let mut balances: Vec<f64> = vec![100.0; 10_000];
 
     let start = Instant::now();
@@ -22,4 +63,35 @@ fn main() {
     }
 
     println!("SIMD DoD pattern took {:?}", start.elapsed());
+
+    let reductions: [(&str, Reduction); 2] = [
+        ("Per-chunk reduction", sum_reduce_per_chunk),
+        ("Deferred reduction", sum_reduce_deferred),
+    ];
+
+    let mut elements_per_second = Vec::new();
+    for (label, reduce) in reductions {
+        let mut checksum = 0.0;
+        let start = Instant::now();
+        for _ in 0..REDUCTION_ITERATIONS {
+            checksum = reduce(std::hint::black_box(&balances));
+        }
+        let elapsed = start.elapsed();
+
+        let throughput = (balances.len() * REDUCTION_ITERATIONS) as f64 / elapsed.as_secs_f64();
+        elements_per_second.push(throughput);
+
+        println!(
+            "{:<20} took {:?} | {:.2} M elements/s | checksum {}",
+            label,
+            elapsed,
+            throughput / 1e6,
+            checksum
+        );
+    }
+
+    println!(
+        "Deferred reduction speedup: {:.2}x",
+        elements_per_second[1] / elements_per_second[0]
+    );
 }
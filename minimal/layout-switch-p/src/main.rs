@@ -0,0 +1,204 @@
+//! One driver compiled against either layout: the `layout-soa` (default) or
+//! `layout-aos` feature decides which `layout` module exists, and
+//! `run_benchmark` is the same source for both. Only the layout and its scan
+//! differ between the two binaries, so their assembly can be diffed directly:
+//!
+//! ```text
+//! cargo rustc -p layout-switch-p --release -- --emit asm
+//! cargo rustc -p layout-switch-p --release --features layout-aos -- --emit asm
+//! ```
+
+#[cfg(not(any(feature = "layout-soa", feature = "layout-aos")))]
+compile_error!("enable one of the `layout-soa` or `layout-aos` features");
+
+use bench_core::csv::{self, CsvRecord};
+use bench_core::timer::InstantTimer;
+use bench_core::{
+    BenchmarkConfig, CANONICAL_CHECKSUM, generate_users, measure_execution_time,
+    parse_csv_out_flag, print_bandwidth, print_results,
+};
+
+/// The rows as generated, scanned in place: every `User` is fetched whole.
+#[cfg(feature = "layout-aos")]
+mod layout {
+    use bench_core::User;
+
+    pub const NAME: &str = "AoS";
+    pub const IMPLEMENTATION: &str = "layout-switch-p-aos";
+
+    /// Bytes the scan reads per user.
+    pub const BYTES_PER_ELEMENT: usize = size_of::<User>();
+
+    pub type Users = Vec<User>;
+
+    pub fn from_users(users: Vec<User>) -> Users {
+        users
+    }
+
+    #[inline(never)]
+    pub fn sum_active_balances(users: &Users, minimum_balance: f32) -> f32 {
+        let mut accumulated_balance = 0.0f32;
+
+        for user in users {
+            let balance_value = user.balance.raw();
+            if user.active && balance_value >= minimum_balance {
+                accumulated_balance += balance_value;
+            }
+        }
+
+        accumulated_balance
+    }
+}
+
+/// One column per field; the scan reads only `balances` and `active`.
+#[cfg(all(feature = "layout-soa", not(feature = "layout-aos")))]
+mod layout {
+    use bench_core::User;
+
+    pub const NAME: &str = "SoA";
+    pub const IMPLEMENTATION: &str = "layout-switch-p-soa";
+
+    /// Bytes the scan reads per user.
+    pub const BYTES_PER_ELEMENT: usize = size_of::<f32>() + size_of::<u8>();
+
+    pub struct Users {
+        #[allow(dead_code)]
+        pub ids: Vec<i32>,
+        pub balances: Vec<f32>,
+        pub active: Vec<u8>,
+    }
+
+    pub fn from_users(users: Vec<User>) -> Users {
+        Users {
+            ids: users.iter().map(|user| user.id).collect(),
+            balances: users.iter().map(|user| user.balance.raw()).collect(),
+            active: users.iter().map(|user| u8::from(user.active)).collect(),
+        }
+    }
+
+    #[inline(never)]
+    pub fn sum_active_balances(users: &Users, minimum_balance: f32) -> f32 {
+        let mut accumulated_balance = 0.0f32;
+
+        for (&balance_value, &active) in users.balances.iter().zip(&users.active) {
+            if active != 0 && balance_value >= minimum_balance {
+                accumulated_balance += balance_value;
+            }
+        }
+
+        accumulated_balance
+    }
+}
+
+/// Generates the shared dataset, lays it out as the selected `layout` and
+/// times its scan. Returns the run as a `--csv-out` row; its checksum is the
+/// same for both layouts.
+fn run_benchmark(config: &BenchmarkConfig) -> CsvRecord<'static> {
+    let BenchmarkConfig {
+        elements_count,
+        minimum_balance,
+        random_seed,
+        warmup_iterations,
+        iterations,
+        active_probability,
+    } = *config;
+
+    println!();
+    println!("[ {} Layout Switch Benchmark ]", layout::NAME);
+    println!("Elements Count    : {}", elements_count);
+    println!("Minimum Balance   : {:.2}", minimum_balance);
+    println!("Random Seed       : {}", random_seed);
+    println!("Warmup Iterations : {}", warmup_iterations);
+    println!("Iterations        : {}", iterations);
+
+    println!();
+    println!("Generating elements...");
+
+    let users = layout::from_users(generate_users(
+        elements_count,
+        random_seed,
+        active_probability,
+    ));
+
+    println!();
+    println!("Warming up...");
+
+    let mut checksum = 0.0f32;
+    for _ in 0..warmup_iterations {
+        checksum = layout::sum_active_balances(&users, minimum_balance);
+    }
+
+    println!();
+    println!("Benchmarking...");
+
+    let mut timer = InstantTimer::default();
+    let total_time_seconds = measure_execution_time(&mut timer, iterations, || {
+        layout::sum_active_balances(&users, minimum_balance)
+    });
+
+    print_results(
+        layout::NAME,
+        checksum as f64,
+        total_time_seconds,
+        iterations,
+        elements_count,
+    );
+    print_bandwidth(
+        elements_count * layout::BYTES_PER_ELEMENT,
+        total_time_seconds / iterations as f64,
+    );
+    println!();
+
+    CsvRecord {
+        implementation: layout::IMPLEMENTATION,
+        elements: elements_count,
+        seed: random_seed,
+        iterations,
+        total_time_seconds,
+        checksum,
+    }
+}
+
+fn main() {
+    let csv_out = match parse_csv_out_flag(std::env::args().skip(1)) {
+        Ok(csv_out) => csv_out,
+        Err(message) => {
+            eprintln!("error: {message}");
+            std::process::exit(2);
+        }
+    };
+
+    let record = run_benchmark(&BenchmarkConfig::default());
+    println!(
+        "Checksum Match    : {}",
+        record.checksum == CANONICAL_CHECKSUM
+    );
+
+    if let Some(path) = &csv_out
+        && let Err(message) = csv::append(path, &[record])
+    {
+        eprintln!("error: {message}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run under each feature, this pins both layouts to the same checksum.
+    #[test]
+    fn selected_layout_matches_the_shared_checksum() {
+        let config = BenchmarkConfig::default();
+        let users = layout::from_users(generate_users(
+            config.elements_count,
+            config.random_seed,
+            config.active_probability,
+        ));
+
+        assert_eq!(
+            layout::sum_active_balances(&users, config.minimum_balance),
+            CANONICAL_CHECKSUM
+        );
+    }
+}
@@ -1,45 +1,185 @@
-use std::collections::HashMap;
-use std::time::Instant;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{BuildHasher, RandomState};
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 struct User {
+    #[allow(dead_code)]
     id: u32,
     balance: f64,
 }
 
 trait UserRepository {
     fn get_all(&self) -> Vec<&User>; // Changed return type
+    fn find_by_id(&self, id: u32) -> Option<&User>;
     fn update_balance(&mut self, id: u32, delta: f64);
+    /// Applies every `(id, delta)` in one pass; unknown ids are skipped.
+    fn update_balance_many(&mut self, deltas: &[(u32, f64)]);
 }
 
-struct InMemoryUserRepository {
-    users: HashMap<u32, User>,
+/// Generic over the hasher so the same repository can be measured with
+/// SipHash (std default) and the faster integer-friendly hashers.
+struct InMemoryUserRepository<S = RandomState> {
+    users: HashMap<u32, User, S>,
 }
 
-impl UserRepository for InMemoryUserRepository {
+impl<S: BuildHasher + Default> InMemoryUserRepository<S> {
+    fn with_users(count: u32) -> Self {
+        Self {
+            users: (0..count)
+                .map(|i| (i, User { id: i, balance: 100.0 }))
+                .collect(),
+        }
+    }
+}
+
+impl<S: BuildHasher> UserRepository for InMemoryUserRepository<S> {
     fn get_all(&self) -> Vec<&User> {
         self.users.values().collect()
     }
 
+    fn find_by_id(&self, id: u32) -> Option<&User> {
+        self.users.get(&id)
+    }
+
     fn update_balance(&mut self, id: u32, delta: f64) {
         if let Some(user) = self.users.get_mut(&id) {
             user.balance += delta;
         }
     }
+
+    /// Still one hash per delta; the batch only saves the per-call overhead.
+    fn update_balance_many(&mut self, deltas: &[(u32, f64)]) {
+        for &(id, delta) in deltas {
+            if let Some(user) = self.users.get_mut(&id) {
+                user.balance += delta;
+            }
+        }
+    }
 }
 
-fn main() {
-    let mut repo = InMemoryUserRepository {
-        users: (0..10_000)
-            .map(|i| (i, User { id: i, balance: 100.0 }))
-            .collect(),
-    };
+/// Ordered alternative: `get_all` walks the ids in ascending order and
+/// lookups are O(log n) tree descents instead of hashes.
+struct BTreeMapUserRepository {
+    users: BTreeMap<u32, User>,
+}
 
-    let start = Instant::now();
+impl BTreeMapUserRepository {
+    fn with_users(count: u32) -> Self {
+        Self {
+            users: (0..count)
+                .map(|i| (i, User { id: i, balance: 100.0 }))
+                .collect(),
+        }
+    }
+}
 
+impl UserRepository for BTreeMapUserRepository {
+    fn get_all(&self) -> Vec<&User> {
+        self.users.values().collect()
+    }
+
+    fn find_by_id(&self, id: u32) -> Option<&User> {
+        self.users.get(&id)
+    }
+
+    fn update_balance(&mut self, id: u32, delta: f64) {
+        if let Some(user) = self.users.get_mut(&id) {
+            user.balance += delta;
+        }
+    }
+
+    fn update_balance_many(&mut self, deltas: &[(u32, f64)]) {
+        for &(id, delta) in deltas {
+            if let Some(user) = self.users.get_mut(&id) {
+                user.balance += delta;
+            }
+        }
+    }
+}
+
+struct RepositoryTimings {
+    update: Duration,
+    update_many: Duration,
+    find: Duration,
+    scan: Duration,
+}
+
+fn benchmark_hasher<S: BuildHasher + Default>(label: &str) -> RepositoryTimings {
+    benchmark_repository(label, InMemoryUserRepository::<S>::with_users(10_000))
+}
+
+fn benchmark_repository(label: &str, mut repo: impl UserRepository) -> RepositoryTimings {
+    let start = Instant::now();
     for i in 0..10_000 {
         repo.update_balance(i, 1.0);
     }
+    let update = start.elapsed();
+
+    let deltas: Vec<(u32, f64)> = (0..10_000).map(|i| (i, 1.0)).collect();
+    let start = Instant::now();
+    repo.update_balance_many(&deltas);
+    let update_many = start.elapsed();
+
+    let start = Instant::now();
+    let mut found_balance = 0.0;
+    for i in 0..10_000 {
+        if let Some(user) = repo.find_by_id(i) {
+            found_balance += user.balance;
+        }
+    }
+    let find = start.elapsed();
+
+    let start = Instant::now();
+    let scanned_balance: f64 = repo.get_all().iter().map(|user| user.balance).sum();
+    let scan = start.elapsed();
 
-    println!("HashMap repository took {:?}", start.elapsed());
+    println!(
+        "{:<8} update {:>10?} | update_many {:>10?} | find_by_id {:>10?} | get_all {:>10?} | checksum {} / {}",
+        label, update, update_many, find, scan, found_balance, scanned_balance
+    );
+
+    RepositoryTimings {
+        update,
+        update_many,
+        find,
+        scan,
+    }
+}
+
+fn print_speedup(label: &str, baseline: &RepositoryTimings, timings: &RepositoryTimings) {
+    println!(
+        "{:<8} speedup vs SipHash: update {:.2}x | update_many {:.2}x | find_by_id {:.2}x | get_all {:.2}x",
+        label,
+        baseline.update.as_secs_f64() / timings.update.as_secs_f64(),
+        baseline.update_many.as_secs_f64() / timings.update_many.as_secs_f64(),
+        baseline.find.as_secs_f64() / timings.find.as_secs_f64(),
+        baseline.scan.as_secs_f64() / timings.scan.as_secs_f64()
+    );
+}
+
+fn main() {
+    let baseline = benchmark_hasher::<RandomState>("SipHash");
+    println!("HashMap repository took {:?}", baseline.update);
+    print_speedup("SipHash", &baseline, &baseline);
+
+    #[cfg(feature = "fxhash")]
+    {
+        let timings = benchmark_hasher::<rustc_hash::FxBuildHasher>("FxHash");
+        print_speedup("FxHash", &baseline, &timings);
+    }
+
+    #[cfg(feature = "ahash")]
+    {
+        let timings = benchmark_hasher::<ahash::RandomState>("aHash");
+        print_speedup("aHash", &baseline, &timings);
+    }
+
+    let timings = benchmark_repository("BTreeMap", BTreeMapUserRepository::with_users(10_000));
+    print_speedup("BTreeMap", &baseline, &timings);
+
+    #[cfg(not(any(feature = "fxhash", feature = "ahash")))]
+    {
+        println!("Enable the `fxhash` and/or `ahash` features to compare hashers.");
+    }
 }
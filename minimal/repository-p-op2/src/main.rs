@@ -2,19 +2,53 @@ use std::time::Instant;
 
 #[derive(Debug)]
 struct User {
+    #[allow(dead_code)]
     id: u32,
     balance: f64,
 }
 
+#[allow(dead_code)]
 trait UserRepository {
     fn get_all(&self) -> &Vec<User>;
     fn update_balance(&mut self, id: u32, delta: f64);
+    /// Applies every `(id, delta)` in one pass; unknown ids are skipped.
+    fn update_balance_many(&mut self, deltas: &[(u32, f64)]);
 }
 
 struct InMemoryUserRepository {
     users: Vec<User>,
 }
 
+/// Direct indexing needs user `i` at position `i`, i.e. ids `0..n` in order.
+fn check_contiguous_ids(ids: impl IntoIterator<Item = u32>) -> Result<(), String> {
+    for (position, id) in ids.into_iter().enumerate() {
+        if id != position as u32 {
+            return Err(format!(
+                "user at position {position} has id {id}; direct indexing needs ids 0..n in order"
+            ));
+        }
+    }
+    Ok(())
+}
+
+impl InMemoryUserRepository {
+    /// Ids must be `0..n` in order; only debug builds check.
+    fn new(users: Vec<User>) -> Self {
+        debug_assert_eq!(
+            check_contiguous_ids(users.iter().map(|user| user.id)),
+            Ok(())
+        );
+        Self { users }
+    }
+
+    /// Like `new`, but rejects ids that direct indexing would misread.
+    #[allow(dead_code)]
+    fn try_new(users: Vec<User>) -> Result<Self, String> {
+        check_contiguous_ids(users.iter().map(|user| user.id))?;
+        Ok(Self { users })
+    }
+}
+
 impl UserRepository for InMemoryUserRepository {
     fn get_all(&self) -> &Vec<User> {
         &self.users
@@ -26,14 +60,23 @@ impl UserRepository for InMemoryUserRepository {
             user.balance += delta;
         }
     }
+
+    fn update_balance_many(&mut self, deltas: &[(u32, f64)]) {
+        // Scatter: every id is its own index, so no sorting or lookup is needed.
+        for &(id, delta) in deltas {
+            if let Some(user) = self.users.get_mut(id as usize) {
+                user.balance += delta;
+            }
+        }
+    }
 }
 
 fn main() {
-    let mut repo = InMemoryUserRepository {
-        users: (0..10_000)
+    let mut repo = InMemoryUserRepository::new(
+        (0..10_000)
             .map(|i| User { id: i, balance: 100.0 })
             .collect(),
-    };
+    );
 
     let start = Instant::now();
 
@@ -42,4 +85,51 @@ fn main() {
     }
 
     println!("Direct indexing repository took {:?}", start.elapsed());
+
+    let deltas: Vec<(u32, f64)> = (0..10_000).map(|i| (i, 1.0)).collect();
+    let start = Instant::now();
+    repo.update_balance_many(&deltas);
+    println!("Batched update_balance_many took {:?}", start.elapsed());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_new_rejects_non_contiguous_ids() {
+        let users = |ids: &[u32]| -> Vec<User> {
+            ids.iter().map(|&id| User { id, balance: 1.0 }).collect()
+        };
+
+        assert!(InMemoryUserRepository::try_new(users(&[0, 1, 2])).is_ok());
+        assert!(InMemoryUserRepository::try_new(users(&[])).is_ok());
+        let error = InMemoryUserRepository::try_new(users(&[0, 2, 3]))
+            .err()
+            .unwrap();
+        assert!(error.contains("position 1 has id 2"));
+    }
+
+    #[test]
+    fn batched_updates_match_one_at_a_time() {
+        let users = || -> Vec<User> { (0..4).map(|id| User { id, balance: 1.0 }).collect() };
+        let deltas = [(3, 2.0), (0, 0.5), (3, 1.0), (9, 100.0)];
+
+        let mut batched = InMemoryUserRepository::new(users());
+        batched.update_balance_many(&deltas);
+        let mut looped = InMemoryUserRepository::new(users());
+        for &(id, delta) in &deltas {
+            looped.update_balance(id, delta);
+        }
+
+        let balances = |repository: &InMemoryUserRepository| -> Vec<f64> {
+            repository
+                .get_all()
+                .iter()
+                .map(|user| user.balance)
+                .collect()
+        };
+        assert_eq!(balances(&batched), [1.5, 1.0, 1.0, 4.0]);
+        assert_eq!(balances(&batched), balances(&looped));
+    }
 }
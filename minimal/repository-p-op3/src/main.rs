@@ -1,24 +1,54 @@
 use std::time::Instant;
 
+use rayon::prelude::*;
+
+/// Below this many users `update_all` stays serial: handing the work to
+/// rayon's threads costs more than the adds it would spread.
+const PARALLEL_UPDATE_THRESHOLD: usize = 100_000;
+
+/// Fewest balances one rayon task updates, so tasks stay worth scheduling.
+const PARALLEL_UPDATE_CHUNK: usize = 16_384;
+
 #[derive(Debug)]
 struct User {
     id: u32,
     balance: f64,
 }
 
+#[allow(dead_code)]
 trait UserRepository {
     fn get_all(&self) -> Vec<User>; // Return owned users
     fn update_balance(&mut self, id: u32, delta: f64);
+    /// Applies every `(id, delta)` in one pass; unknown ids are skipped.
+    fn update_balance_many(&mut self, deltas: &[(u32, f64)]);
     fn get_user(&self, id: u32) -> Option<User>;
 }
 
 struct InMemoryUserRepository {
+    #[allow(dead_code)]
     ids: Vec<u32>,
     balances: Vec<f64>,
 }
 
+/// Direct indexing needs user `i` at position `i`, i.e. ids `0..n` in order.
+fn check_contiguous_ids(ids: impl IntoIterator<Item = u32>) -> Result<(), String> {
+    for (position, id) in ids.into_iter().enumerate() {
+        if id != position as u32 {
+            return Err(format!(
+                "user at position {position} has id {id}; direct indexing needs ids 0..n in order"
+            ));
+        }
+    }
+    Ok(())
+}
+
 impl InMemoryUserRepository {
+    /// Ids must be `0..n` in order; only debug builds check.
     fn new(users: Vec<User>) -> Self {
+        debug_assert_eq!(
+            check_contiguous_ids(users.iter().map(|user| user.id)),
+            Ok(())
+        );
         let mut ids = Vec::with_capacity(users.len());
         let mut balances = Vec::with_capacity(users.len());
 
@@ -29,6 +59,29 @@ impl InMemoryUserRepository {
 
         Self { ids, balances }
     }
+
+    /// Like `new`, but rejects ids that direct indexing would misread.
+    #[allow(dead_code)]
+    fn try_new(users: Vec<User>) -> Result<Self, String> {
+        check_contiguous_ids(users.iter().map(|user| user.id))?;
+        Ok(Self::new(users))
+    }
+
+    /// Adds `delta` to every balance, across rayon's threads once there are
+    /// at least `PARALLEL_UPDATE_THRESHOLD` users. Every balance gets exactly
+    /// one add either way, so the result is identical to the serial loop.
+    fn update_all(&mut self, delta: f64) {
+        if self.balances.len() < PARALLEL_UPDATE_THRESHOLD {
+            for balance in &mut self.balances {
+                *balance += delta;
+            }
+        } else {
+            self.balances
+                .par_iter_mut()
+                .with_min_len(PARALLEL_UPDATE_CHUNK)
+                .for_each(|balance| *balance += delta);
+        }
+    }
 }
 
 impl UserRepository for InMemoryUserRepository {
@@ -47,6 +100,15 @@ impl UserRepository for InMemoryUserRepository {
         }
     }
 
+    fn update_balance_many(&mut self, deltas: &[(u32, f64)]) {
+        // Scatter: every id is its own index, so no sorting or lookup is needed.
+        for &(id, delta) in deltas {
+            if let Some(balance) = self.balances.get_mut(id as usize) {
+                *balance += delta;
+            }
+        }
+    }
+
     fn get_user(&self, id: u32) -> Option<User> {
         if let Some(&balance) = self.balances.get(id as usize) {
             Some(User { id, balance })
@@ -71,7 +133,107 @@ fn main() {
 
     println!("DoD repository took {:?}", start.elapsed());
 
+    let deltas: Vec<(u32, f64)> = (0..10_000).map(|i| (i, 1.0)).collect();
+    let start = Instant::now();
+    repo.update_balance_many(&deltas);
+    println!("Batched update_balance_many took {:?}", start.elapsed());
+
+    const LARGE_USERS_COUNT: u32 = 4_000_000;
+    let mut large_repo = InMemoryUserRepository::new(
+        (0..LARGE_USERS_COUNT)
+            .map(|i| User {
+                id: i,
+                balance: 100.0,
+            })
+            .collect(),
+    );
+
+    let start = Instant::now();
+    for i in 0..LARGE_USERS_COUNT {
+        large_repo.update_balance(i, 1.0);
+    }
+    let serial_elapsed = start.elapsed();
+
+    // The first rayon call spins up the global pool; an untimed pass that adds
+    // nothing keeps that out of the measurement.
+    let threads = rayon::current_num_threads();
+    large_repo.update_all(0.0);
+
+    let start = Instant::now();
+    large_repo.update_all(1.0);
+    let parallel_elapsed = start.elapsed();
+
+    println!(
+        "Serial update_balance over {} users took {:?}",
+        LARGE_USERS_COUNT, serial_elapsed
+    );
+    println!(
+        "Parallel update_all over {} users ({} threads) took {:?} ({:.2}x the serial loop)",
+        LARGE_USERS_COUNT,
+        threads,
+        parallel_elapsed,
+        serial_elapsed.as_secs_f64() / parallel_elapsed.as_secs_f64()
+    );
+
     // Verify it works
     let user = repo.get_user(0).unwrap();
     println!("First user: id={}, balance={}", user.id, user.balance);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn try_new_rejects_non_contiguous_ids() {
+        let users = |ids: &[u32]| -> Vec<User> {
+            ids.iter().map(|&id| User { id, balance: 1.0 }).collect()
+        };
+
+        let repository = InMemoryUserRepository::try_new(users(&[0, 1, 2])).unwrap();
+        assert_eq!(repository.get_user(2).map(|user| user.id), Some(2));
+        let error = InMemoryUserRepository::try_new(users(&[1, 2]))
+            .err()
+            .unwrap();
+        assert!(error.contains("position 0 has id 1"));
+    }
+
+    #[test]
+    fn batched_updates_match_one_at_a_time() {
+        let users = || -> Vec<User> { (0..4).map(|id| User { id, balance: 1.0 }).collect() };
+        let deltas = [(3, 2.0), (0, 0.5), (3, 1.0), (9, 100.0)];
+
+        let mut batched = InMemoryUserRepository::new(users());
+        batched.update_balance_many(&deltas);
+        let mut looped = InMemoryUserRepository::new(users());
+        for &(id, delta) in &deltas {
+            looped.update_balance(id, delta);
+        }
+
+        assert_eq!(batched.balances, [1.5, 1.0, 1.0, 4.0]);
+        assert_eq!(batched.balances, looped.balances);
+    }
+
+    #[test]
+    fn update_all_matches_the_serial_loop_on_both_sides_of_the_threshold() {
+        for count in [3, PARALLEL_UPDATE_THRESHOLD + 1] {
+            let users = || -> Vec<User> {
+                (0..count as u32)
+                    .map(|id| User {
+                        id,
+                        balance: id as f64 * 0.1,
+                    })
+                    .collect()
+            };
+
+            let mut updated = InMemoryUserRepository::new(users());
+            updated.update_all(0.3);
+            let mut looped = InMemoryUserRepository::new(users());
+            for id in 0..count as u32 {
+                looped.update_balance(id, 0.3);
+            }
+
+            assert_eq!(updated.balances, looped.balances);
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use std::time::Instant;
+
+/// Sums every `stride`-th float, i.e. the "balance" field of a struct made of
+/// `stride` floats stored back to back. Only the stride changes between runs,
+/// so the AoS cost curve comes purely from how much of each cache line is used.
+#[inline(never)]
+fn strided_sum(values: &[f32], stride: usize) -> f32 {
+    values.iter().step_by(stride).sum()
+}
+
+fn main() {
+    const ELEMENTS_READ: usize = 1_000_000;
+    const STRIDES: [usize; 5] = [1, 2, 4, 8, 16];
+    const ITERATIONS: usize = 8;
+
+    // One allocation, large enough for the widest stride, shared by every run.
+    let values: Vec<f32> = (0..ELEMENTS_READ * STRIDES[STRIDES.len() - 1])
+        .map(|i| (i % 1000) as f32)
+        .collect();
+
+    println!("Stride | Checksum        | ns/element | M elements/s");
+
+    for stride in STRIDES {
+        let window = &values[..ELEMENTS_READ * stride];
+
+        // Warm up so every stride is measured in the same steady state.
+        let mut checksum = strided_sum(window, stride);
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            checksum = strided_sum(window, stride);
+        }
+        let average_time_seconds = start.elapsed().as_secs_f64() / ITERATIONS as f64;
+
+        let nanoseconds_per_element = average_time_seconds * 1e9 / ELEMENTS_READ as f64;
+        let elements_per_second = ELEMENTS_READ as f64 / average_time_seconds;
+
+        println!(
+            "{:>6} | {:>15.2} | {:>10.3} | {:>12.2}",
+            stride,
+            checksum,
+            nanoseconds_per_element,
+            elements_per_second / 1e6
+        );
+    }
+}